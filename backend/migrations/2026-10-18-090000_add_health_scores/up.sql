@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE current_metrics
+    ADD COLUMN piotroski_f_score SMALLINT,
+    ADD COLUMN altman_z_score DOUBLE PRECISION;
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE red_flags (
+    id SERIAL PRIMARY KEY,
+    company_id INTEGER REFERENCES companies(id) ON DELETE CASCADE NOT NULL,
+    flag TEXT NOT NULL,
+    detected_on DATE NOT NULL,
+    UNIQUE(company_id, flag)
+);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE forecasts
+    DROP COLUMN pe_ratio,
+    DROP COLUMN ps_ratio,
+    DROP COLUMN pfcf_ratio,
+    DROP COLUMN ev_ebit,
+    DROP COLUMN ev_sales;
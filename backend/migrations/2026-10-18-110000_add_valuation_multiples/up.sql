@@ -0,0 +1,7 @@
+-- Your SQL goes here
+ALTER TABLE forecasts
+    ADD COLUMN pe_ratio DOUBLE PRECISION,
+    ADD COLUMN ps_ratio DOUBLE PRECISION,
+    ADD COLUMN pfcf_ratio DOUBLE PRECISION,
+    ADD COLUMN ev_ebit DOUBLE PRECISION,
+    ADD COLUMN ev_sales DOUBLE PRECISION;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE peer_ranks (
+    id SERIAL PRIMARY KEY,
+    company_id INTEGER REFERENCES companies(id) ON DELETE CASCADE NOT NULL,
+    industry VARCHAR(50) NOT NULL,
+    peer_count INTEGER NOT NULL,
+    gross_margin_rank DOUBLE PRECISION,
+    operating_margin_rank DOUBLE PRECISION,
+    revenue_growth_yoy_rank DOUBLE PRECISION,
+    revenue_growth_multi_year_rank DOUBLE PRECISION,
+    free_cash_flow_margin_rank DOUBLE PRECISION,
+    shares_change_rank DOUBLE PRECISION,
+    last_updated DATE NOT NULL,
+    UNIQUE(company_id)
+);
@@ -0,0 +1,18 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE current_metrics
+    DROP COLUMN premiums_growth_yoy_ttm,
+    DROP COLUMN premiums_growth_multi_year,
+    DROP COLUMN loss_ratio_ttm,
+    DROP COLUMN loss_ratio_short_term_trend,
+    DROP COLUMN loss_ratio_long_term_trend,
+    DROP COLUMN combined_ratio_ttm,
+    DROP COLUMN combined_ratio_short_term_trend,
+    DROP COLUMN combined_ratio_long_term_trend;
+
+ALTER TABLE earnings_report
+    DROP COLUMN premiums_earned,
+    DROP COLUMN premiums_growth_yoy,
+    DROP COLUMN policy_benefits,
+    DROP COLUMN underwriting_expenses,
+    DROP COLUMN loss_ratio,
+    DROP COLUMN combined_ratio;
@@ -0,0 +1,18 @@
+-- Your SQL goes here
+ALTER TABLE earnings_report
+    ADD COLUMN premiums_earned DOUBLE PRECISION,
+    ADD COLUMN premiums_growth_yoy DOUBLE PRECISION,
+    ADD COLUMN policy_benefits DOUBLE PRECISION,
+    ADD COLUMN underwriting_expenses DOUBLE PRECISION,
+    ADD COLUMN loss_ratio DOUBLE PRECISION,
+    ADD COLUMN combined_ratio DOUBLE PRECISION;
+
+ALTER TABLE current_metrics
+    ADD COLUMN premiums_growth_yoy_ttm DOUBLE PRECISION,
+    ADD COLUMN premiums_growth_multi_year DOUBLE PRECISION,
+    ADD COLUMN loss_ratio_ttm DOUBLE PRECISION,
+    ADD COLUMN loss_ratio_short_term_trend TEXT,
+    ADD COLUMN loss_ratio_long_term_trend TEXT,
+    ADD COLUMN combined_ratio_ttm DOUBLE PRECISION,
+    ADD COLUMN combined_ratio_short_term_trend TEXT,
+    ADD COLUMN combined_ratio_long_term_trend TEXT;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE symbol_directory (
+    id SERIAL PRIMARY KEY,
+    ticker VARCHAR(6) NOT NULL,
+    isin VARCHAR(12),
+    exchange VARCHAR(9) NOT NULL,
+    UNIQUE(ticker, exchange)
+);
+
+CREATE INDEX symbol_directory_isin_idx ON symbol_directory (isin);
+
+INSERT INTO symbol_directory (ticker, isin, exchange)
+SELECT ticker, isin, exchange FROM companies
+ON CONFLICT DO NOTHING;
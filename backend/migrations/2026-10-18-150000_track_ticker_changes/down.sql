@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE ticker_history;
+
+ALTER TABLE companies
+    DROP COLUMN delisted;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+ALTER TABLE companies
+    ADD COLUMN delisted BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE ticker_history (
+    id SERIAL PRIMARY KEY,
+    company_id INTEGER REFERENCES companies(id) ON DELETE CASCADE NOT NULL,
+    ticker VARCHAR(6) NOT NULL,
+    exchange VARCHAR(9) NOT NULL,
+    replaced_on DATE NOT NULL
+);
+
+CREATE INDEX ticker_history_ticker_idx ON ticker_history (ticker, exchange);
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE fx_rates (
+    id SERIAL PRIMARY KEY,
+    base_currency VARCHAR(3) NOT NULL,
+    quote_currency VARCHAR(3) NOT NULL,
+    rate DOUBLE PRECISION NOT NULL,
+    last_updated DATE NOT NULL,
+    UNIQUE(base_currency, quote_currency)
+);
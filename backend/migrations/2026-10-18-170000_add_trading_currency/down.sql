@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE forecasts
+    DROP COLUMN trading_currency,
+    DROP COLUMN price_target_available;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+ALTER TABLE forecasts
+    ADD COLUMN trading_currency VARCHAR(3),
+    ADD COLUMN price_target_available BOOLEAN;
+
+UPDATE forecasts
+SET trading_currency = CASE companies.exchange
+    WHEN 'TSE' THEN 'JPY'
+    WHEN 'LSE' THEN 'GBP'
+    ELSE 'USD'
+END
+FROM companies
+WHERE forecasts.company_id = companies.id;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE earnings_report
+    DROP COLUMN split_factor;
+
+DROP TABLE corporate_actions;
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE corporate_actions (
+    id SERIAL PRIMARY KEY,
+    company_id INTEGER REFERENCES companies(id) ON DELETE CASCADE NOT NULL,
+    action_type TEXT NOT NULL,
+    ratio DOUBLE PRECISION NOT NULL CHECK (ratio > 0),
+    effective_date DATE NOT NULL,
+    UNIQUE(company_id, effective_date)
+);
+
+ALTER TABLE earnings_report
+    ADD COLUMN split_factor DOUBLE PRECISION NOT NULL DEFAULT 1;
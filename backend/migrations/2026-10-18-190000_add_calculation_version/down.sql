@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE current_metrics
+    DROP COLUMN calculation_version;
+
+ALTER TABLE earnings_report
+    DROP COLUMN calculation_version;
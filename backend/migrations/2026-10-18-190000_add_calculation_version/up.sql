@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE earnings_report
+    ADD COLUMN calculation_version INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE current_metrics
+    ADD COLUMN calculation_version INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE data_quality_issues;
+
+UPDATE earnings_report
+    SET revenue_growth_yoy = reported_revenue_growth_yoy;
+
+ALTER TABLE earnings_report
+    DROP COLUMN reported_revenue_growth_yoy;
@@ -0,0 +1,17 @@
+-- Your SQL goes here
+ALTER TABLE earnings_report
+    ADD COLUMN reported_revenue_growth_yoy DOUBLE PRECISION;
+
+UPDATE earnings_report
+    SET reported_revenue_growth_yoy = revenue_growth_yoy;
+
+CREATE TABLE data_quality_issues (
+    id SERIAL PRIMARY KEY,
+    company_id INTEGER REFERENCES companies(id) ON DELETE CASCADE NOT NULL,
+    earnings_id INTEGER REFERENCES earnings_report(id) ON DELETE CASCADE NOT NULL,
+    field TEXT NOT NULL,
+    computed_value DOUBLE PRECISION,
+    reported_value DOUBLE PRECISION,
+    detected_on DATE NOT NULL,
+    UNIQUE(earnings_id, field)
+);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE ingest_issues (
+    id SERIAL PRIMARY KEY,
+    company_id INTEGER REFERENCES companies(id) ON DELETE CASCADE NOT NULL,
+    period TEXT NOT NULL,
+    check_name TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    message TEXT NOT NULL,
+    detected_on DATE NOT NULL
+);
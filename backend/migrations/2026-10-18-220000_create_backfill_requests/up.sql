@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE backfill_requests (
+    id SERIAL PRIMARY KEY,
+    company_id INTEGER REFERENCES companies(id) ON DELETE CASCADE NOT NULL,
+    period TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    requested_on DATE NOT NULL,
+    last_attempted DATE,
+    UNIQUE(company_id, period)
+);
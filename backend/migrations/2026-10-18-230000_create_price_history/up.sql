@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE price_history (
+    id SERIAL PRIMARY KEY,
+    company_id INTEGER REFERENCES companies(id) ON DELETE CASCADE NOT NULL,
+    price_date DATE NOT NULL,
+    close DOUBLE PRECISION NOT NULL,
+    UNIQUE(company_id, price_date)
+);
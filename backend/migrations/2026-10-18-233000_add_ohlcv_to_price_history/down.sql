@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE price_history
+    DROP COLUMN open,
+    DROP COLUMN high,
+    DROP COLUMN low,
+    DROP COLUMN volume;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE price_history
+    ADD COLUMN open DOUBLE PRECISION,
+    ADD COLUMN high DOUBLE PRECISION,
+    ADD COLUMN low DOUBLE PRECISION,
+    ADD COLUMN volume BIGINT;
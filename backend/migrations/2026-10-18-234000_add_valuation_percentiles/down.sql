@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE current_metrics
+    DROP COLUMN pe_ratio_percentile,
+    DROP COLUMN ps_ratio_percentile;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE current_metrics
+    ADD COLUMN pe_ratio_percentile DOUBLE PRECISION,
+    ADD COLUMN ps_ratio_percentile DOUBLE PRECISION;
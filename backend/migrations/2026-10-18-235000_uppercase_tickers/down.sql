@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+-- the original spelling of the tickers is not kept, so they stay in upper case
+SELECT 1;
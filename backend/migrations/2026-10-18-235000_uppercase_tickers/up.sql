@@ -0,0 +1,20 @@
+-- Your SQL goes here
+DELETE FROM symbol_directory AS duplicate
+    USING symbol_directory AS kept
+    WHERE UPPER(duplicate.ticker) = UPPER(kept.ticker)
+        AND duplicate.exchange = kept.exchange
+        AND duplicate.id <> kept.id
+        AND (kept.ticker = UPPER(kept.ticker) OR kept.id < duplicate.id)
+        AND duplicate.ticker <> UPPER(duplicate.ticker);
+
+UPDATE symbol_directory
+    SET ticker = UPPER(ticker)
+    WHERE ticker <> UPPER(ticker);
+
+UPDATE companies
+    SET ticker = UPPER(ticker)
+    WHERE ticker <> UPPER(ticker);
+
+UPDATE ticker_history
+    SET ticker = UPPER(ticker)
+    WHERE ticker <> UPPER(ticker);
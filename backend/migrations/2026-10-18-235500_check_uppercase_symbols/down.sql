@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE ticker_history
+    DROP CONSTRAINT ticker_history_ticker_upper;
+
+ALTER TABLE companies
+    DROP CONSTRAINT companies_ticker_upper;
+
+ALTER TABLE symbol_directory
+    DROP CONSTRAINT symbol_directory_isin_upper,
+    DROP CONSTRAINT symbol_directory_ticker_upper;
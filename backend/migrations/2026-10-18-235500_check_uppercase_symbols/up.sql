@@ -0,0 +1,14 @@
+-- Your SQL goes here
+UPDATE symbol_directory
+    SET isin = UPPER(isin)
+    WHERE isin <> UPPER(isin);
+
+ALTER TABLE symbol_directory
+    ADD CONSTRAINT symbol_directory_ticker_upper CHECK (ticker = UPPER(ticker)),
+    ADD CONSTRAINT symbol_directory_isin_upper CHECK (isin = UPPER(isin));
+
+ALTER TABLE companies
+    ADD CONSTRAINT companies_ticker_upper CHECK (ticker = UPPER(ticker));
+
+ALTER TABLE ticker_history
+    ADD CONSTRAINT ticker_history_ticker_upper CHECK (ticker = UPPER(ticker));
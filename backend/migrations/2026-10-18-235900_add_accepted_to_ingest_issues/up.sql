@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE ingest_issues
+    ADD COLUMN accepted BOOLEAN NOT NULL DEFAULT FALSE;
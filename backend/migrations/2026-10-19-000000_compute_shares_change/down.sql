@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+UPDATE earnings_report
+    SET shares_change_yoy = reported_shares_change_yoy
+    WHERE reported_shares_change_yoy IS NOT NULL;
+
+ALTER TABLE earnings_report
+    DROP COLUMN reported_shares_change_yoy;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE earnings_report
+    ADD COLUMN reported_shares_change_yoy DOUBLE PRECISION;
+
+UPDATE earnings_report
+    SET reported_shares_change_yoy = shares_change_yoy;
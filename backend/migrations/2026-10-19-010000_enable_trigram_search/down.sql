@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX companies_company_name_trgm_idx;
+DROP INDEX companies_ticker_trgm_idx;
+
+DROP EXTENSION IF EXISTS pg_trgm;
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX companies_ticker_trgm_idx ON companies USING GIN (ticker gin_trgm_ops);
+CREATE INDEX companies_company_name_trgm_idx ON companies USING GIN (company_name gin_trgm_ops);
@@ -0,0 +1,12 @@
+-- This file should undo anything in `up.sql`
+UPDATE earnings_report
+    SET goodwill_impairment = 0
+    WHERE goodwill_impairment IS NULL;
+
+UPDATE earnings_report
+    SET shares_change_yoy = 0
+    WHERE shares_change_yoy IS NULL;
+
+ALTER TABLE earnings_report
+    ALTER COLUMN goodwill_impairment SET NOT NULL,
+    ALTER COLUMN shares_change_yoy SET NOT NULL;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE earnings_report
+    ALTER COLUMN goodwill_impairment DROP NOT NULL,
+    ALTER COLUMN shares_change_yoy DROP NOT NULL;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+UPDATE ingest_issues
+    SET check_name = 'unparseable_period'
+    WHERE check_name IN ('unparseable_ttm_period', 'unparseable_annual_period');
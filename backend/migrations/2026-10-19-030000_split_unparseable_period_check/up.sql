@@ -0,0 +1,5 @@
+-- Your SQL goes here
+-- the duration of earlier dropped statements is unknown, so they are kept until the next full ingest
+UPDATE ingest_issues
+    SET check_name = 'unparseable_annual_period'
+    WHERE check_name = 'unparseable_period';
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE companies
+    DROP COLUMN ticker_failures,
+    DROP COLUMN last_ticker_failure;
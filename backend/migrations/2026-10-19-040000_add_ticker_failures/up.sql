@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE companies
+    ADD COLUMN ticker_failures INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN last_ticker_failure DATE;
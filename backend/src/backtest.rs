@@ -0,0 +1,215 @@
+use crate::calculate;
+use crate::metrics::{self, TheoreticalEps};
+use crate::models::earnings_model::EarningsReport;
+use crate::models::price_history_model::PriceHistory;
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+
+/// days a stored price may be away from the day it is looked up for
+const MAX_PRICE_GAP_DAYS: i64 = 7;
+
+/// price targets that can be replayed from stored earnings.
+/// the guidance target is left out because past revenue estimates aren't stored.
+pub const TARGET_TYPES: [&str; 4] = [
+    "price_current_revenue_growth",
+    "price_current_gp_growth",
+    "price_multi_year_revenue_growth",
+    "price_multi_year_gp_growth",
+];
+
+/// price target of one past quarter and the price move that followed
+pub struct BacktestSample {
+    pub target_type: &'static str,
+    /// upside to the target in percent at the time the earnings became public
+    pub implied_return: f64,
+    /// price change in percent over the horizon
+    pub realized_return: f64,
+}
+
+/// hit rate and return statistics of one target type
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetStats {
+    pub target_type: &'static str,
+    pub samples: usize,
+    /// share of samples in percent where the price moved towards the target
+    pub hit_rate: Option<f64>,
+    pub mean_return: Option<f64>,
+    pub median_return: Option<f64>,
+    /// mean return of samples trading below the target
+    pub mean_return_below_target: Option<f64>,
+    /// mean return of samples trading above the target
+    pub mean_return_above_target: Option<f64>,
+    /// correlation between implied upside and realized return
+    pub correlation: Option<f64>,
+}
+
+/// last close on or before the given date
+fn price_on_or_before(prices: &[PriceHistory], date: NaiveDate) -> Option<f64> {
+    let idx = prices.partition_point(|price| price.price_date <= date);
+    prices[..idx]
+        .last()
+        .filter(|price| date - price.price_date <= Duration::days(MAX_PRICE_GAP_DAYS))
+        .map(|price| price.close)
+}
+
+/// first close on or after the given date
+fn price_on_or_after(prices: &[PriceHistory], date: NaiveDate) -> Option<f64> {
+    let idx = prices.partition_point(|price| price.price_date < date);
+    prices
+        .get(idx)
+        .filter(|price| price.price_date - date <= Duration::days(MAX_PRICE_GAP_DAYS))
+        .map(|price| price.close)
+}
+
+/// price targets the given TTM earnings would have produced, in the order of TARGET_TYPES.
+/// annual earnings ending after the quarter are not used for the multi-year growth rates.
+fn replay_targets(
+    stock_data: &EarningsReport,
+    annual_reports: &[&EarningsReport],
+    net_margin_factor: f64,
+    fx_rate: Option<f64>,
+) -> [Option<f64>; 4] {
+    let (theoretical_net_margin, is_optimized) =
+        metrics::is_net_margin_optimized(stock_data, net_margin_factor);
+    let net_margin = match is_optimized {
+        true => Some(stock_data.net_margin),
+        false => Some(theoretical_net_margin),
+    };
+    let theoretical_eps = TheoreticalEps::new(
+        Some(stock_data.revenue),
+        net_margin,
+        Some(stock_data.shares_outstanding_diluted),
+        fx_rate,
+    );
+    let past_annual: Vec<&EarningsReport> = annual_reports
+        .iter()
+        .copied()
+        .filter(|report| report.period_ending <= stock_data.period_ending)
+        .take(metrics::MULTI_YEAR_GROWTH_YEARS as usize)
+        .collect();
+    let multi_year_average = |value: fn(&EarningsReport) -> Option<f64>| {
+        let values: Vec<Option<f64>> = past_annual.iter().map(|report| value(report)).collect();
+        calculate::average_options(&values, true)
+    };
+    let share_change = stock_data.shares_change_yoy;
+    [
+        stock_data.revenue_growth_yoy,
+        stock_data.gross_profit_growth_yoy,
+        multi_year_average(|report| report.revenue_growth_yoy),
+        multi_year_average(|report| report.gross_profit_growth_yoy),
+    ]
+    .map(|growth| {
+        calculate::calculate_price_target_option(theoretical_eps.eps, growth, share_change)
+    })
+}
+
+/// replays the price targets of every stored quarter of a company and compares them
+/// with the price after the given number of days.
+/// fx_rate converts the reporting currency into the trading currency of the prices.
+pub fn replay_company(
+    industry: &str,
+    reports: &[EarningsReport],
+    prices: &[PriceHistory],
+    fx_rate: Option<f64>,
+    horizon_days: i64,
+) -> Vec<BacktestSample> {
+    let net_margin_factor = calculate::get_net_margin_factor(industry);
+    let mut annual_reports: Vec<&EarningsReport> = reports
+        .iter()
+        .filter(|report| report.duration == "Y")
+        .collect();
+    annual_reports.sort_by(|a, b| b.year_str.cmp(&a.year_str));
+    let mut samples = Vec::new();
+    for stock_data in reports.iter().filter(|report| report.duration == "T") {
+        let public_date = stock_data.period_ending + Duration::days(metrics::REPORT_LAG_DAYS);
+        let Some(start_price) = price_on_or_before(prices, public_date).filter(|val| *val > 0.)
+        else {
+            continue;
+        };
+        let Some(end_price) = price_on_or_after(prices, public_date + Duration::days(horizon_days))
+        else {
+            continue;
+        };
+        let targets = replay_targets(stock_data, &annual_reports, net_margin_factor, fx_rate);
+        for (target_type, target) in TARGET_TYPES.into_iter().zip(targets) {
+            if let Some(target) = target {
+                samples.push(BacktestSample {
+                    target_type,
+                    implied_return: (target / start_price - 1.) * 100.,
+                    realized_return: (end_price / start_price - 1.) * 100.,
+                });
+            }
+        }
+    }
+    samples
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    match values.len() {
+        0 => None,
+        len => Some(values.iter().sum::<f64>() / len as f64),
+    }
+}
+
+fn median(values: &[f64]) -> Option<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    match sorted.len() {
+        0 => None,
+        len if len % 2 == 0 => Some((sorted[mid - 1] + sorted[mid]) / 2.),
+        _ => Some(sorted[mid]),
+    }
+}
+
+/// Pearson correlation, None when either series doesn't vary
+fn correlation(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let (x_mean, y_mean) = (mean(xs)?, mean(ys)?);
+    let covariance: f64 = xs
+        .iter()
+        .zip(ys)
+        .map(|(x, y)| (x - x_mean) * (y - y_mean))
+        .sum();
+    let x_spread: f64 = xs.iter().map(|x| (x - x_mean).powi(2)).sum();
+    let y_spread: f64 = ys.iter().map(|y| (y - y_mean).powi(2)).sum();
+    let denominator = (x_spread * y_spread).sqrt();
+    (denominator > 0.).then(|| covariance / denominator)
+}
+
+/// hit rates and return statistics for each target type
+pub fn summarize(samples: &[BacktestSample]) -> Vec<TargetStats> {
+    TARGET_TYPES
+        .into_iter()
+        .map(|target_type| {
+            let target_samples: Vec<&BacktestSample> = samples
+                .iter()
+                .filter(|sample| sample.target_type == target_type)
+                .collect();
+            let implied: Vec<f64> = target_samples.iter().map(|s| s.implied_return).collect();
+            let realized: Vec<f64> = target_samples.iter().map(|s| s.realized_return).collect();
+            let hits = target_samples
+                .iter()
+                .filter(|s| s.implied_return * s.realized_return > 0.)
+                .count();
+            let returns_where = |is_below_target: bool| -> Vec<f64> {
+                target_samples
+                    .iter()
+                    .filter(|s| (s.implied_return > 0.) == is_below_target)
+                    .map(|s| s.realized_return)
+                    .collect()
+            };
+            TargetStats {
+                target_type,
+                samples: target_samples.len(),
+                hit_rate: (!target_samples.is_empty())
+                    .then(|| hits as f64 / target_samples.len() as f64 * 100.),
+                mean_return: mean(&realized),
+                median_return: median(&realized),
+                mean_return_below_target: mean(&returns_where(true)),
+                mean_return_above_target: mean(&returns_where(false)),
+                correlation: correlation(&implied, &realized),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,385 @@
+use bullseye::db::{establish_connection_pool, lookup_exchange};
+use bullseye::errors::BullsEyeError;
+use bullseye::export::{CurrencyLabels, ExportTable};
+use bullseye::helper::{normalize_ticker, parse_exchange};
+use bullseye::import;
+use bullseye::models::forecast_models::Forecasts;
+use bullseye::services;
+use bullseye_api::model::{get_exchange_string, Exchange};
+use diesel::pg::PgConnection;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: bullseye-admin <command>
+
+commands:
+    refresh <ticker> [exchange]         scrape all earnings and update metrics
+    recompute --all|--outdated          recalculate ratios, growths, trends and price targets
+                                        and list companies whose metrics changed materially
+    peer-ranks                          recalculate the peer ranks of every industry
+    import <file.csv|file.json>         import statements in the documented layout
+    import-prices <file.csv>            import daily prices in the documented layout
+    backtest [--horizon DAYS] [ticker [exchange]]
+                                        compare past price targets with the prices that followed
+    export screener <file.csv|xlsx>     write the screener table to a file
+    export earnings <ticker> [exchange] <file.csv|xlsx>
+                                        write the earnings history of a company to a file
+    list-stale                          list companies with outdated earnings or prices
+    backfill                            scrape the full history of companies with missing periods
+    delete <ticker> [exchange]          remove a company and everything stored for it
+    relist <ticker> [exchange]          clear the delisted flag of a company that still trades
+    issues <ticker> [exchange]          list the ingest issues of a company
+    data-issues <ticker> [exchange]     list stored growth rates that disagree with the computed ones
+    accept-issue <ticker> [exchange] <period> <check>
+                                        stop a reviewed ingest issue from blocking the metrics,
+                                        e.g. accept-issue AAPL \"Q4 2024\" balance_sheet_identity";
+
+/// days after the earnings release the backtest compares the price targets with
+const DEFAULT_BACKTEST_HORIZON_DAYS: i64 = 365;
+
+/// resolves the ticker through the symbol directory unless the exchange is given
+fn resolve_listing(
+    ticker: &str,
+    exchange_str: Option<&str>,
+    conn: &mut PgConnection,
+) -> Result<(String, Exchange), BullsEyeError> {
+    match exchange_str {
+        Some(exchange_str) => {
+            let exchange = parse_exchange(exchange_str)
+                .ok_or_else(|| BullsEyeError::UnsupportedExchangeError(exchange_str.to_string()))?;
+            Ok((normalize_ticker(ticker), exchange))
+        }
+        None => lookup_exchange(ticker, conn),
+    }
+}
+
+fn write_table(table: ExportTable, path: &str, sheet_name: &str) -> Result<(), BullsEyeError> {
+    let content = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("xlsx") => table.to_xlsx(sheet_name)?,
+        Some("csv") => table.to_csv()?,
+        _ => return Err(BullsEyeError::UnsupportedFormatError(path.to_string())),
+    };
+    fs::write(path, content).map_err(|e| BullsEyeError::ExportError(format!("{}: {}", path, e)))
+}
+
+async fn refresh(
+    ticker: &str,
+    exchange_str: Option<&str>,
+    conn: &mut PgConnection,
+) -> Result<(), BullsEyeError> {
+    let (ticker, exchange) = resolve_listing(ticker, exchange_str, conn)?;
+    let company = services::get_company(&ticker, &exchange, conn).await?;
+    services::update_earnings_all(company.id, &company.ticker, &exchange, conn).await?;
+    services::update_metrics_annual(company.id, conn)?;
+    services::update_all_peer_ranks(conn)?;
+    println!("refreshed {}:{}", company.exchange, company.ticker);
+    Ok(())
+}
+
+fn recompute(all: bool, conn: &mut PgConnection) -> Result<(), BullsEyeError> {
+    let summary = services::recompute_metrics(all, conn)?;
+    for report in &summary.reports {
+        println!("{}:{}", report.exchange, report.ticker);
+        for change in &report.changes {
+            let format_val = |val: Option<f64>| {
+                val.map(|num| num.to_string())
+                    .unwrap_or_else(|| "none".to_string())
+            };
+            println!(
+                "    {}: {} -> {}",
+                change.field,
+                format_val(change.before),
+                format_val(change.after)
+            );
+        }
+    }
+    for company in &summary.blocked {
+        println!(
+            "{}:{} skipped: blocked by an ingest issue",
+            company.exchange, company.ticker
+        );
+    }
+    println!("{} companies changed materially", summary.reports.len());
+    Ok(())
+}
+
+fn import_file(path: &str, conn: &mut PgConnection) -> Result<(), BullsEyeError> {
+    for batch in import::read_file(path)? {
+        let summary = services::import_statements(batch, conn)?;
+        println!(
+            "{}:{} imported {} ttm and {} annual statements",
+            summary.exchange, summary.ticker, summary.ttm_rows, summary.annual_rows
+        );
+    }
+    services::update_all_peer_ranks(conn)?;
+    Ok(())
+}
+
+fn peer_ranks(conn: &mut PgConnection) -> Result<(), BullsEyeError> {
+    let industries = services::update_all_peer_ranks(conn)?;
+    println!("ranked peers in {} industries", industries);
+    Ok(())
+}
+
+fn import_prices(path: &str, conn: &mut PgConnection) -> Result<(), BullsEyeError> {
+    let summary = services::import_prices(import::read_price_file(path)?, conn)?;
+    for listing in &summary.unknown_listings {
+        println!("skipped {}: company not ingested", listing);
+    }
+    println!("imported {} daily prices", summary.price_rows);
+    Ok(())
+}
+
+fn backtest(
+    horizon_days: i64,
+    listing_args: &[&str],
+    conn: &mut PgConnection,
+) -> Result<(), BullsEyeError> {
+    let listing = match listing_args {
+        [ticker] => Some(resolve_listing(ticker, None, conn)?),
+        [ticker, exchange] => Some(resolve_listing(ticker, Some(exchange), conn)?),
+        _ => None,
+    };
+    let report = services::run_backtest(
+        listing
+            .as_ref()
+            .map(|(ticker, exchange)| (ticker.as_str(), exchange)),
+        horizon_days,
+        conn,
+    )?;
+    println!(
+        "{} companies, {} day horizon",
+        report.companies, report.horizon_days
+    );
+    let format_val = |val: Option<f64>| {
+        val.map(|num| format!("{:.2}", num))
+            .unwrap_or_else(|| "none".to_string())
+    };
+    for stats in &report.targets {
+        println!("{}", stats.target_type);
+        println!("    samples: {}", stats.samples);
+        println!("    hit rate: {}%", format_val(stats.hit_rate));
+        println!(
+            "    return mean/median: {}% / {}%",
+            format_val(stats.mean_return),
+            format_val(stats.median_return)
+        );
+        println!(
+            "    mean return below/above target: {}% / {}%",
+            format_val(stats.mean_return_below_target),
+            format_val(stats.mean_return_above_target)
+        );
+        println!(
+            "    upside/return correlation: {}",
+            format_val(stats.correlation)
+        );
+    }
+    Ok(())
+}
+
+fn export_screener(path: &str, conn: &mut PgConnection) -> Result<(), BullsEyeError> {
+    let all_companies = services::get_all_companies(&[], None, conn)?;
+    let table = ExportTable::from_records(&all_companies, &CurrencyLabels::default())?;
+    write_table(table, path, "screener")
+}
+
+fn export_earnings(
+    ticker: &str,
+    exchange_str: Option<&str>,
+    path: &str,
+    conn: &mut PgConnection,
+) -> Result<(), BullsEyeError> {
+    let (ticker, exchange) = resolve_listing(ticker, exchange_str, conn)?;
+    let (company, history) = services::get_earnings_history(&ticker, &exchange, conn)?;
+    let forecast = Forecasts::load_by_id(company.id, conn)?;
+    let labels = CurrencyLabels {
+        reporting: history.first().map(|earnings| earnings.currency.as_str()),
+        trading: forecast.trading_currency.as_deref(),
+        converted: None,
+    };
+    let table = ExportTable::from_records(&history, &labels)?;
+    write_table(table, path, &format!("{}_earnings", company.ticker))
+}
+
+fn list_stale(conn: &mut PgConnection) -> Result<(), BullsEyeError> {
+    for (company, forecast) in services::get_stale_companies(conn)? {
+        let last_updated = forecast
+            .last_updated
+            .map(|date| date.to_string())
+            .unwrap_or_else(|| "never".to_string());
+        let next_earnings = forecast
+            .next_earnings_date
+            .map(|date| date.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        println!(
+            "{}:{}\tlast updated {}\tnext earnings {}",
+            company.exchange, company.ticker, last_updated, next_earnings
+        );
+    }
+    Ok(())
+}
+
+async fn backfill(conn: &mut PgConnection) -> Result<(), BullsEyeError> {
+    let backfilled = services::run_due_backfills(conn).await?;
+    for company in &backfilled {
+        println!("backfilled {}:{}", company.exchange, company.ticker);
+    }
+    println!("{} companies backfilled", backfilled.len());
+    Ok(())
+}
+
+fn delete(
+    ticker: &str,
+    exchange_str: Option<&str>,
+    conn: &mut PgConnection,
+) -> Result<(), BullsEyeError> {
+    let (ticker, exchange) = resolve_listing(ticker, exchange_str, conn)?;
+    let company = services::delete_company(&ticker, &exchange, conn)?;
+    println!(
+        "deleted {}:{} ({})",
+        get_exchange_string(&exchange),
+        company.ticker,
+        company.company_name
+    );
+    Ok(())
+}
+
+fn relist(
+    ticker: &str,
+    exchange_str: Option<&str>,
+    conn: &mut PgConnection,
+) -> Result<(), BullsEyeError> {
+    let (ticker, exchange) = resolve_listing(ticker, exchange_str, conn)?;
+    let company = services::relist_company(&ticker, &exchange, conn)?;
+    println!(
+        "relisted {}:{} ({})",
+        get_exchange_string(&exchange),
+        company.ticker,
+        company.company_name
+    );
+    Ok(())
+}
+
+fn list_issues(
+    ticker: &str,
+    exchange_str: Option<&str>,
+    conn: &mut PgConnection,
+) -> Result<(), BullsEyeError> {
+    let (ticker, exchange) = resolve_listing(ticker, exchange_str, conn)?;
+    for issue in services::get_ingest_issues(&ticker, &exchange, conn)? {
+        let status = if issue.accepted { "accepted" } else { "open" };
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            issue.period,
+            issue.check_name,
+            issue.severity.as_str(),
+            status,
+            issue.message
+        );
+    }
+    Ok(())
+}
+
+fn list_data_issues(
+    ticker: &str,
+    exchange_str: Option<&str>,
+    conn: &mut PgConnection,
+) -> Result<(), BullsEyeError> {
+    let (ticker, exchange) = resolve_listing(ticker, exchange_str, conn)?;
+    let format_value = |value: Option<f64>| value.map_or("-".to_string(), |v| v.to_string());
+    for issue in services::get_data_quality_issues(&ticker, &exchange, conn)? {
+        println!(
+            "{}\t{}\tcomputed {}\treported {}\t{}",
+            issue.earnings_id,
+            issue.field,
+            format_value(issue.computed_value),
+            format_value(issue.reported_value),
+            issue.detected_on
+        );
+    }
+    Ok(())
+}
+
+fn accept_issue(
+    ticker: &str,
+    exchange_str: Option<&str>,
+    period: &str,
+    check_name: &str,
+    conn: &mut PgConnection,
+) -> Result<(), BullsEyeError> {
+    let (ticker, exchange) = resolve_listing(ticker, exchange_str, conn)?;
+    services::accept_ingest_issue(&ticker, &exchange, period, check_name, conn)?;
+    println!(
+        "accepted {} in {} of {}:{}",
+        check_name,
+        period,
+        get_exchange_string(&exchange),
+        ticker
+    );
+    Ok(())
+}
+
+async fn run(args: &[String], conn: &mut PgConnection) -> Result<bool, BullsEyeError> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["refresh", ticker] => refresh(ticker, None, conn).await?,
+        ["refresh", ticker, exchange] => refresh(ticker, Some(exchange), conn).await?,
+        ["recompute", "--all"] => recompute(true, conn)?,
+        ["recompute", "--outdated"] => recompute(false, conn)?,
+        ["peer-ranks"] => peer_ranks(conn)?,
+        ["import", path] => import_file(path, conn)?,
+        ["import-prices", path] => import_prices(path, conn)?,
+        ["backtest", "--horizon", days, listing @ ..] if listing.len() <= 2 => {
+            match days.parse::<i64>() {
+                Ok(days) if days > 0 => backtest(days, listing, conn)?,
+                _ => return Ok(false),
+            }
+        }
+        ["backtest", listing @ ..] if listing.len() <= 2 && !listing.contains(&"--horizon") => {
+            backtest(DEFAULT_BACKTEST_HORIZON_DAYS, listing, conn)?
+        }
+        ["export", "screener", path] => export_screener(path, conn)?,
+        ["export", "earnings", ticker, path] => export_earnings(ticker, None, path, conn)?,
+        ["export", "earnings", ticker, exchange, path] => {
+            export_earnings(ticker, Some(exchange), path, conn)?
+        }
+        ["list-stale"] => list_stale(conn)?,
+        ["backfill"] => backfill(conn).await?,
+        ["delete", ticker] => delete(ticker, None, conn)?,
+        ["delete", ticker, exchange] => delete(ticker, Some(exchange), conn)?,
+        ["relist", ticker] => relist(ticker, None, conn)?,
+        ["relist", ticker, exchange] => relist(ticker, Some(exchange), conn)?,
+        ["issues", ticker] => list_issues(ticker, None, conn)?,
+        ["issues", ticker, exchange] => list_issues(ticker, Some(exchange), conn)?,
+        ["data-issues", ticker] => list_data_issues(ticker, None, conn)?,
+        ["data-issues", ticker, exchange] => list_data_issues(ticker, Some(exchange), conn)?,
+        ["accept-issue", ticker, period, check_name] => {
+            accept_issue(ticker, None, period, check_name, conn)?
+        }
+        ["accept-issue", ticker, exchange, period, check_name] => {
+            accept_issue(ticker, Some(exchange), period, check_name, conn)?
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let pool = establish_connection_pool().unwrap();
+    let conn = &mut pool.get().unwrap();
+    match run(&args, conn).await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => {
+            eprintln!("{}", USAGE);
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
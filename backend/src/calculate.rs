@@ -1,29 +1,37 @@
 use crate::models::metrics_model::Trend;
 
+/// version of the formulas deriving ratios, growth rates and metrics.
+/// bump it whenever a calculation changes so stored values get recomputed.
+pub const CALCULATION_VERSION: i32 = 3;
+
 pub fn calculate_price_target_option(
     eps: Option<f64>,
     growth_pct: Option<f64>,
     share_change: Option<f64>,
 ) -> Option<f64> {
     eps.zip(growth_pct).zip(share_change).map(|((e, g), s)| {
-        if s > 50. {
-            e * calculate_growth_adjustment_factor(g - 50.)
-        } else if s < -10. {
-            e * calculate_growth_adjustment_factor(g + 10.)
-        } else {
-            e * calculate_growth_adjustment_factor(g - s)
-        }
+        e * calculate_growth_adjustment_factor(calculate_share_adjusted_growth(g, s))
     })
 }
 
-pub fn calculate_growth_adjustment_factor(growth: f64) -> f64 {
-    let (punishment, adjusted_growth) = match growth {
+/// growth rate net of the share count change, which is capped at 50% dilution and 10% buybacks
+pub fn calculate_share_adjusted_growth(growth: f64, share_change: f64) -> f64 {
+    growth - share_change.clamp(-10., 50.)
+}
+
+/// punishment for excessive growth and the yearly growth multiplier used for the price target
+pub fn calculate_growth_adjustment_terms(growth: f64) -> (f64, f64) {
+    match growth {
         val if val > 60. => (0.6, 1.6),
         val if val > 50. => (0.6 + 0.2 * (60. - val) / 10., 1. + val / 100.),
         val if val > 30. => (0.8 + 0.2 * (50. - val) / 20., 1. + val / 100.),
         val if val > 1. => (1., 1. + val / 100.),
         _ => (1., 1.01),
-    };
+    }
+}
+
+pub fn calculate_growth_adjustment_factor(growth: f64) -> f64 {
+    let (punishment, adjusted_growth) = calculate_growth_adjustment_terms(growth);
     let factor = (adjusted_growth.powi(10) / 2.6 * 10. + 5.) * punishment;
     factor
 }
@@ -78,22 +86,27 @@ pub fn calculate_short_term_trend_option(
         .map(|(i, v)| {
             let past_four_vals = &vals[i + 1..i + 5];
             let past_four_ave = average_options(past_four_vals, ignore_none);
-            match (v, past_four_ave) {
-                (&Some(curr), Some(prev)) => {
-                    if curr - prev >= flat_threshold {
-                        Trend::Uptrend
-                    } else if curr - prev <= -flat_threshold {
-                        Trend::Downtrend
-                    } else {
-                        Trend::Flat
-                    }
-                }
-                _ => Trend::Irrelevant,
-            }
+            compare_with_average(*v, past_four_ave, flat_threshold)
         })
         .collect()
 }
 
+/// votes on the direction of the current value against the reference average
+pub fn compare_with_average(curr: Option<f64>, average: Option<f64>, flat_threshold: f64) -> Trend {
+    match (curr, average) {
+        (Some(curr), Some(prev)) => {
+            if curr - prev >= flat_threshold {
+                Trend::Uptrend
+            } else if curr - prev <= -flat_threshold {
+                Trend::Downtrend
+            } else {
+                Trend::Flat
+            }
+        }
+        _ => Trend::Irrelevant,
+    }
+}
+
 pub fn calculate_long_term_trend(vals: &[f64], flat_threshold: f64) -> Trend {
     if vals.len() < 2 {
         return Trend::Irrelevant;
@@ -125,22 +138,7 @@ pub fn calculate_long_term_trend_option(
     let old_interval = &vals[split_point..];
     let new_ave = average_options(new_interval, ignore_none);
     let old_ave = average_options(old_interval, ignore_none);
-    match (new_ave, old_ave) {
-        (Some(new_val), Some(old_val)) => {
-            if new_val - old_val >= flat_threshold {
-                Trend::Uptrend
-            } else if new_val - old_val <= -flat_threshold {
-                Trend::Downtrend
-            } else {
-                Trend::Flat
-            }
-        }
-        _ => Trend::Irrelevant,
-    }
-}
-
-pub fn calculate_average_growth(growth_vec: Vec<f64>) -> f64 {
-    growth_vec.iter().sum::<f64>() / growth_vec.len() as f64
+    compare_with_average(new_ave, old_ave, flat_threshold)
 }
 
 pub fn average_options(options: &[Option<f64>], ignore_none: bool) -> Option<f64> {
@@ -241,6 +239,41 @@ pub fn calculate_margin_portion(total: Option<f64>, margin: Option<f64>) -> Opti
     total.zip(margin).map(|(x, y)| x * y / 100.)
 }
 
+/// calculates valuation multiple, which is not meaningful for non-positive base values
+pub fn calculate_multiple(value: Option<f64>, base: Option<f64>) -> Option<f64> {
+    value
+        .zip(base)
+        .filter(|(_, bottom)| *bottom > 0.)
+        .map(|(top, bottom)| (top / bottom * 100.).round() / 100.)
+}
+
+/// calculates quantile (0-1) of the values with linear interpolation
+pub fn calculate_quantile(values: &[f64], quantile: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let position = (sorted.len() - 1) as f64 * quantile.clamp(0., 1.);
+    let lower = sorted[position.floor() as usize];
+    let upper = sorted[position.ceil() as usize];
+    let val = lower + (upper - lower) * position.fract();
+    Some((val * 100.).round() / 100.)
+}
+
+/// calculates percentile rank (0-100) of the value within the population.
+/// ties count as half below.
+pub fn calculate_percentile_rank(value: Option<f64>, population: &[f64]) -> Option<f64> {
+    if population.is_empty() {
+        return None;
+    }
+    value.map(|val| {
+        let below = population.iter().filter(|x| **x < val).count() as f64;
+        let equal = population.iter().filter(|x| **x == val).count() as f64;
+        ((below + equal / 2.) / population.len() as f64 * 10000.).round() / 100.
+    })
+}
+
 pub fn calculate_per_share(total: Option<f64>, share: Option<f64>) -> Option<f64> {
     total.zip(share).map(|(x, y)| x / y)
 }
@@ -1,14 +1,28 @@
 use crate::calculate;
 use crate::errors::BullsEyeError;
+use crate::flags;
+use crate::fx::FxTable;
+use crate::gaps;
+use crate::health;
 use crate::helper;
-use crate::metrics;
+use crate::metrics::{self, TheoreticalEps};
+use crate::models::backfill_model;
 use crate::models::companies_model::Company;
+use crate::models::corporate_actions_model::CorporateAction;
 use crate::models::earnings_model::EarningsReport;
+use crate::models::flags_model;
 use crate::models::forecast_models::Forecasts;
+use crate::models::fx_model::FxRate;
+use crate::models::ingest_issues_model::{self, NewIngestIssue};
 use crate::models::metrics_model::CurrentMetrics;
+use crate::models::peers_model::{self, IndustryDistribution, NewPeerRanks};
+use crate::models::price_history_model::{self, PriceHistory};
+use crate::models::symbols_model::SymbolEntry;
 use crate::query;
+use crate::validation;
+use crate::valuation::{self, MultiplePoint, ValuationInputs};
 use bullseye_api::model::Exchange;
-use chrono::{Duration, Local};
+use chrono::{Duration, Local, Utc};
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, Pool};
@@ -27,11 +41,29 @@ pub fn establish_connection_pool() -> Result<Pool<ConnectionManager<PgConnection
     }
 }
 
-//TODO: add error
-pub fn lookup_exchange(ticker: &str) -> Exchange {
-    match &ticker[0..1].parse::<u64>() {
-        Ok(_) => Exchange::TSE,
-        Err(_) => Exchange::NASDAQ,
+/// resolves a ticker or ISIN into its listed ticker and exchange through the symbol directory
+pub fn lookup_exchange(
+    symbol: &str,
+    conn: &mut PgConnection,
+) -> Result<(String, Exchange), BullsEyeError> {
+    let listings = SymbolEntry::load_by_symbol(symbol, conn)?;
+    match listings.as_slice() {
+        [] => Err(BullsEyeError::UnknownSymbolError(symbol.to_string())),
+        [listing] => {
+            let exchange = helper::parse_exchange(&listing.exchange)
+                .ok_or_else(|| BullsEyeError::UnsupportedExchangeError(listing.exchange.clone()))?;
+            Ok((listing.ticker.clone(), exchange))
+        }
+        _ => {
+            let exchanges: Vec<&str> = listings
+                .iter()
+                .map(|listing| listing.exchange.as_str())
+                .collect();
+            Err(BullsEyeError::AmbiguousSymbolError(
+                symbol.to_string(),
+                exchanges.join(", "),
+            ))
+        }
     }
 }
 
@@ -57,26 +89,136 @@ pub fn join_data(
     Ok(all_data)
 }
 
-/// updates all missing ratios for all earnings data
-pub fn update_ratios_batch(conn: &mut PgConnection) -> Result<(), DieselError> {
+/// loads all companies and their metrics
+pub fn load_all_metrics(
+    conn: &mut PgConnection,
+) -> Result<Vec<(Company, CurrentMetrics)>, DieselError> {
+    use crate::schema::companies::dsl::*;
+    use crate::schema::current_metrics::dsl::*;
+    let all_data = companies
+        .inner_join(current_metrics)
+        .order(industry.asc())
+        .load::<(Company, CurrentMetrics)>(conn)?;
+    Ok(all_data)
+}
+
+/// loads all companies and their metrics in the given industry
+pub fn load_industry_metrics(
+    target_industry: &str,
+    conn: &mut PgConnection,
+) -> Result<Vec<(Company, CurrentMetrics)>, DieselError> {
+    use crate::schema::companies::dsl::*;
+    use crate::schema::current_metrics::dsl::*;
+    let all_data = companies
+        .inner_join(current_metrics)
+        .filter(industry.eq(target_industry))
+        .load::<(Company, CurrentMetrics)>(conn)?;
+    Ok(all_data)
+}
+
+/// updates percentile ranks of key metrics for all companies in the given industry
+pub fn update_peer_ranks(
+    target_industry: &str,
+    conn: &mut PgConnection,
+) -> Result<(), DieselError> {
+    let peers = load_industry_metrics(target_industry, conn)?;
+    let distribution = IndustryDistribution::new(&peers);
+    let entries: Vec<NewPeerRanks> = peers
+        .iter()
+        .map(|(company, metrics)| {
+            NewPeerRanks::create_new_entry(company.id, target_industry, metrics, &distribution)
+        })
+        .collect();
+    peers_model::upsert_peer_ranks_batch(&entries, conn)?;
+    Ok(())
+}
+
+/// updates missing or outdated ratios for the earnings data of the given company in one transaction
+pub fn update_ratios_batch(comp_id: i32, conn: &mut PgConnection) -> Result<(), DieselError> {
     use crate::schema::earnings_report::dsl::*;
-    let target: Vec<EarningsReport> = earnings_report
-        .filter(ratio_calculated.eq(false))
-        .load::<EarningsReport>(conn)?;
-    for i in target {
-        i.update_ratios(conn)?;
-    }
+    conn.transaction(|conn| {
+        let target: Vec<EarningsReport> = earnings_report
+            .filter(company_id.eq(comp_id))
+            .filter(
+                ratio_calculated
+                    .eq(false)
+                    .or(calculation_version.lt(calculate::CALCULATION_VERSION)),
+            )
+            .load::<EarningsReport>(conn)?;
+        for i in target {
+            i.update_ratios(conn)?;
+        }
+        Ok(())
+    })
+}
+
+/// updates missing or outdated growth rates for the earnings data of the given company in one transaction.
+/// previous year data is looked up from the loaded earnings instead of one query per row.
+pub fn update_growths_batch(comp_id: i32, conn: &mut PgConnection) -> Result<(), DieselError> {
+    conn.transaction(|conn| {
+        let reports = EarningsReport::load_all_by_id(comp_id, conn)?;
+        for report in reports.iter().filter(|report| {
+            !report.growth_calculated || report.calculation_version < calculate::CALCULATION_VERSION
+        }) {
+            report.update_yoy_growth(report.find_prev_year_data(&reports), conn)?;
+        }
+        Ok(())
+    })
+}
+
+/// recalculates growth rates and ratios of the given company that are missing or were derived
+/// with older formulas, then records the current calculation version on its earnings data
+pub fn update_derived_fields(comp_id: i32, conn: &mut PgConnection) -> Result<(), DieselError> {
+    use crate::schema::earnings_report::dsl::*;
+    conn.transaction(|conn| {
+        update_growths_batch(comp_id, conn)?;
+        update_ratios_batch(comp_id, conn)?;
+        diesel::update(
+            earnings_report
+                .filter(company_id.eq(comp_id))
+                .filter(calculation_version.lt(calculate::CALCULATION_VERSION)),
+        )
+        .set(calculation_version.eq(calculate::CALCULATION_VERSION))
+        .execute(conn)?;
+        Ok(())
+    })
+}
+
+/// records that the metrics of the given company were derived with the current formulas
+pub fn update_calculation_version(
+    comp_id: i32,
+    conn: &mut PgConnection,
+) -> Result<(), DieselError> {
+    use crate::schema::current_metrics::dsl::*;
+    query::update_metrics_table(
+        comp_id,
+        calculation_version.eq(calculate::CALCULATION_VERSION),
+        conn,
+    )?;
     Ok(())
 }
 
-/// updates all missing growth rates for all earnings data
-pub fn update_growths_batch(conn: &mut PgConnection) -> Result<(), DieselError> {
+/// restates share counts and EPS of the given company onto the latest share basis.
+/// growth rates are recalculated when any earnings data was restated.
+pub fn update_split_adjustments(comp_id: i32, conn: &mut PgConnection) -> Result<(), DieselError> {
     use crate::schema::earnings_report::dsl::*;
-    let target = earnings_report
-        .filter(growth_calculated.eq(false))
-        .load::<EarningsReport>(conn)?;
-    for i in target {
-        i.update_yoy_growth(conn)?;
+    let actions = CorporateAction::load_by_id(comp_id, conn)?;
+    let mut is_restated = false;
+    for report in EarningsReport::load_all_by_id(comp_id, conn)? {
+        let factor: f64 = actions
+            .iter()
+            .filter(|action| action.effective_date > report.period_ending)
+            .map(|action| action.share_factor())
+            .product();
+        if (factor - report.split_factor).abs() > f64::EPSILON {
+            report.update_split_factor(factor, conn)?;
+            is_restated = true;
+        }
+    }
+    if is_restated {
+        diesel::update(earnings_report.filter(company_id.eq(comp_id)))
+            .set(growth_calculated.eq(false))
+            .execute(conn)?;
     }
     Ok(())
 }
@@ -98,21 +240,112 @@ pub fn update_earnings_date(
     Ok(())
 }
 
-/// updates current stock price in the metrics table
+/// updates current stock price in the metrics table.
+/// prices scraped after the close of a trading day are kept as its close.
 pub fn update_price(
     comp_id: i32,
     price: Option<f64>,
     conn: &mut PgConnection,
 ) -> Result<(), DieselError> {
     use crate::schema::forecasts::dsl::*;
+    let company = Company::load_by_id(comp_id, conn)?;
     query::update_forecasts_table(
         comp_id,
         (
             latest_price.eq(price),
+            trading_currency.eq(helper::trading_currency(&company.exchange)),
             last_updated.eq(Local::now().date_naive()),
         ),
         conn,
     )?;
+    let trading_day = helper::closed_trading_day(&company.exchange, Utc::now());
+    if let Some((price, day)) = price.zip(trading_day) {
+        price_history_model::insert_closing_price(comp_id, day, price, conn)?;
+    }
+    update_valuation_multiples(comp_id, conn)?;
+    Ok(())
+}
+
+/// figures the current valuation multiples are calculated from, using the latest price and TTM metrics
+pub fn current_valuation_inputs(
+    comp_id: i32,
+    conn: &mut PgConnection,
+) -> Result<ValuationInputs, DieselError> {
+    let target_forecast = Forecasts::load_by_id(comp_id, conn)?;
+    let target_metrics = CurrentMetrics::load_by_id(comp_id, conn)?;
+    let latest_operating_income = EarningsReport::latest_quarter_data_if_existed(comp_id, conn)?
+        .map(|earnings| earnings.operating_income);
+    let fx_rate = reporting_to_trading_rate(&target_metrics, &target_forecast, conn)?;
+    Ok(ValuationInputs::new(
+        target_forecast.latest_price,
+        fx_rate,
+        &target_metrics,
+        latest_operating_income,
+    ))
+}
+
+/// updates P/E, P/S, P/FCF, EV/EBIT and EV/Sales with the latest price and TTM metrics
+pub fn update_valuation_multiples(
+    comp_id: i32,
+    conn: &mut PgConnection,
+) -> Result<(), DieselError> {
+    use crate::schema::forecasts::dsl::*;
+    let inputs = current_valuation_inputs(comp_id, conn)?;
+    query::update_forecasts_table(
+        comp_id,
+        (
+            pe_ratio.eq(inputs.multiple(pe_ratio::NAME)),
+            ps_ratio.eq(inputs.multiple(ps_ratio::NAME)),
+            pfcf_ratio.eq(inputs.multiple(pfcf_ratio::NAME)),
+            ev_ebit.eq(inputs.multiple(ev_ebit::NAME)),
+            ev_sales.eq(inputs.multiple(ev_sales::NAME)),
+        ),
+        conn,
+    )?;
+    update_valuation_percentiles(comp_id, conn)?;
+    Ok(())
+}
+
+/// daily P/E and P/S of the given company from its stored prices and TTM earnings.
+/// empty when the currencies differ and no exchange rate is stored.
+pub fn load_multiple_history(
+    comp_id: i32,
+    conn: &mut PgConnection,
+) -> Result<Vec<MultiplePoint>, DieselError> {
+    let target_forecast = Forecasts::load_by_id(comp_id, conn)?;
+    let target_metrics = CurrentMetrics::load_by_id(comp_id, conn)?;
+    let Some(fx_rate) = reporting_to_trading_rate(&target_metrics, &target_forecast, conn)? else {
+        return Ok(Vec::new());
+    };
+    let reports = EarningsReport::load_all_by_id(comp_id, conn)?;
+    let prices = PriceHistory::load_by_id(comp_id, conn)?;
+    Ok(valuation::multiple_history(&reports, &prices, fx_rate))
+}
+
+/// updates the percentiles of the current P/E and P/S within their own history
+pub fn update_valuation_percentiles(
+    comp_id: i32,
+    conn: &mut PgConnection,
+) -> Result<(), DieselError> {
+    use crate::schema::current_metrics::dsl::*;
+    let target_forecast = Forecasts::load_by_id(comp_id, conn)?;
+    let history = load_multiple_history(comp_id, conn)?;
+    query::update_metrics_table(
+        comp_id,
+        (
+            pe_ratio_percentile.eq(valuation::historical_percentile(
+                &history,
+                |point| point.pe_ratio,
+                target_forecast.pe_ratio,
+            )),
+            ps_ratio_percentile.eq(valuation::historical_percentile(
+                &history,
+                |point| point.ps_ratio,
+                target_forecast.ps_ratio,
+            )),
+        ),
+        conn,
+    )?;
     Ok(())
 }
 
@@ -130,21 +363,25 @@ pub fn update_estimate(
 /// updates all multi-year growth rate columns in the metrics table
 pub fn update_multi_yr_growth(comp_id: i32, conn: &mut PgConnection) -> Result<(), DieselError> {
     use crate::schema::current_metrics::dsl::*;
-    let target = query::load_multiple_earnings_annual(comp_id, 4, conn)?;
+    let target =
+        query::load_multiple_earnings_annual(comp_id, metrics::MULTI_YEAR_GROWTH_YEARS, conn)?;
     let net_interest_income_growth = extract_field(&target, |data| data.net_interest_growth_yoy);
     let net_interest_income_growth_ave =
         calculate::average_options(&net_interest_income_growth, true);
     let rev_growth = extract_field(&target, |data| data.revenue_growth_yoy);
     let rev_growth_ave = calculate::average_options(&rev_growth, true);
+    let premiums_growth = extract_field(&target, |data| data.premiums_growth_yoy);
+    let premiums_growth_ave = calculate::average_options(&premiums_growth, true);
     let gp_growth = extract_field(&target, |data| data.gross_profit_growth_yoy);
     let gp_growth_ave = calculate::average_options(&gp_growth, true);
     let shares_change = extract_field(&target, |data| data.shares_change_yoy);
-    let shares_change_ave = calculate::calculate_average_growth(shares_change);
+    let shares_change_ave = calculate::average_options(&shares_change, true);
     query::update_metrics_table(
         comp_id,
         (
             net_interest_income_growth_multi_year.eq(net_interest_income_growth_ave),
             revenue_growth_multi_year.eq(rev_growth_ave),
+            premiums_growth_multi_year.eq(premiums_growth_ave),
             gross_profit_growth_multi_year.eq(gp_growth_ave),
             shares_change_multi_year.eq(shares_change_ave),
         ),
@@ -159,38 +396,26 @@ pub fn update_short_term_trends(
     conn: &mut PgConnection,
 ) -> Result<CurrentMetrics, DieselError> {
     use crate::schema::current_metrics::dsl::*;
-    let target = query::load_multiple_earnings_ttm(comp_id, 8, conn)?;
-    let nim_trend =
-        metrics::get_short_term_trend_option(&target, |f| f.net_interest_margin, 4, true, 0.5, 2);
-    let cor_trend =
-        metrics::get_short_term_trend_option(&target, |f| f.cost_of_risk, 4, true, 0.5, 2);
-    let gross_margin_trend =
-        metrics::get_short_term_trend_option(&target, |f| f.gross_margin, 4, true, 0.5, 2);
-    let sga_ratio_trend =
-        metrics::get_short_term_trend_option(&target, |f| f.sga_gp_ratio, 4, true, 0.01, 2);
-    let rnd_ratio_trend =
-        metrics::get_short_term_trend_option(&target, |f| f.rnd_gp_ratio, 4, true, 0.01, 2);
-    let operating_margin_trend =
-        metrics::get_short_term_trend(&target, |f| f.operating_margin, 4, 0.5, 2);
-    // query::update_metrics_table(
-    //     comp_id,
-    //     (
-    //         gross_margin_short_term_trend.eq(gross_margin_trend),
-    //         sga_short_term_trend.eq(sga_ratio_trend),
-    //         rnd_short_term_trend.eq(rnd_ratio_trend),
-    //         operating_margin_short_term_trend.eq(operating_margin_trend),
-    //     ),
-    //     conn,
-    // )?;
+    let target =
+        query::load_multiple_earnings_ttm(comp_id, gaps::SHORT_TERM_TREND_QUARTERS as i64, conn)?;
+    let trend = |name: &str| {
+        metrics::SHORT_TERM_TRENDS
+            .iter()
+            .find(|spec| spec.metric == name)
+            .map(|spec| metrics::get_short_term_trend_spec(&target, spec))
+    };
     let updated_row = query::update_and_return_table(
         current_metrics.filter(company_id.eq(comp_id)),
         (
-            net_interest_margin_short_term_trend.eq(nim_trend),
-            cost_of_risk_short_term_trend.eq(cor_trend),
-            gross_margin_short_term_trend.eq(gross_margin_trend),
-            sga_short_term_trend.eq(sga_ratio_trend),
-            rnd_short_term_trend.eq(rnd_ratio_trend),
-            operating_margin_short_term_trend.eq(operating_margin_trend),
+            net_interest_margin_short_term_trend
+                .eq(trend(net_interest_margin_short_term_trend::NAME)),
+            cost_of_risk_short_term_trend.eq(trend(cost_of_risk_short_term_trend::NAME)),
+            loss_ratio_short_term_trend.eq(trend(loss_ratio_short_term_trend::NAME)),
+            combined_ratio_short_term_trend.eq(trend(combined_ratio_short_term_trend::NAME)),
+            gross_margin_short_term_trend.eq(trend(gross_margin_short_term_trend::NAME)),
+            sga_short_term_trend.eq(trend(sga_short_term_trend::NAME)),
+            rnd_short_term_trend.eq(trend(rnd_short_term_trend::NAME)),
+            operating_margin_short_term_trend.eq(trend(operating_margin_short_term_trend::NAME)),
         ),
         conn,
     )?;
@@ -203,39 +428,31 @@ pub fn update_long_term_trends(
     conn: &mut PgConnection,
 ) -> Result<CurrentMetrics, DieselError> {
     use crate::schema::current_metrics::dsl::*;
-    let target = query::load_multiple_earnings_annual(comp_id, 6, conn)?;
-    let nim_trend =
-        metrics::get_long_term_trend_option(&target, |f| f.net_interest_margin, false, 1.);
-    let cor_trend = metrics::get_long_term_trend_option(&target, |f| f.cost_of_risk, false, 1.);
-    let gross_margin_trend =
-        metrics::get_long_term_trend_option(&target, |f| f.gross_margin, false, 1.);
-    let sga_ratio_trend =
-        metrics::get_long_term_trend_option(&target, |f| f.sga_gp_ratio, false, 0.02);
-    let rnd_ratio_trend =
-        metrics::get_long_term_trend_option(&target, |f| f.rnd_gp_ratio, false, 0.02);
-    let operating_margin_trend = metrics::get_long_term_trend(&target, |f| f.operating_margin, 1.);
-    let dilution_trend = metrics::get_long_term_trend(&target, |f| f.shares_change_yoy, 2.);
-    let retained_earnings_change_trend =
-        metrics::get_long_term_trend(&target, |f| f.retained_earnings, 100.);
-    let net_cash_change_trend = metrics::get_long_term_trend(&target, |f| f.net_cash, 100.);
-    let ocfm_trend =
-        metrics::get_long_term_trend_option(&target, |f| f.operating_cash_flow_margin, false, 1.);
-    let ffom_trend = metrics::get_long_term_trend_option(&target, |f| f.ffo_margin, false, 1.);
-
+    let target =
+        query::load_multiple_earnings_annual(comp_id, metrics::LONG_TERM_TREND_YEARS, conn)?;
+    let trend = |name: &str| {
+        metrics::LONG_TERM_TRENDS
+            .iter()
+            .find(|spec| spec.metric == name)
+            .map(|spec| metrics::get_long_term_trend_spec(&target, spec))
+    };
     let updated_row = query::update_and_return_table(
         current_metrics.filter(company_id.eq(comp_id)),
         (
-            net_interest_margin_long_term_trend.eq(nim_trend),
-            cost_of_risk_long_term_trend.eq(cor_trend),
-            gross_margin_long_term_trend.eq(gross_margin_trend),
-            sga_long_term_trend.eq(sga_ratio_trend),
-            rnd_long_term_trend.eq(rnd_ratio_trend),
-            operating_margin_long_term_trend.eq(operating_margin_trend),
-            shares_change_trend.eq(dilution_trend),
-            retained_earnings_trend.eq(retained_earnings_change_trend),
-            net_cash_trend.eq(net_cash_change_trend),
-            operating_cash_flow_margin_trend.eq(ocfm_trend),
-            ffo_margin_trend.eq(ffom_trend),
+            net_interest_margin_long_term_trend
+                .eq(trend(net_interest_margin_long_term_trend::NAME)),
+            cost_of_risk_long_term_trend.eq(trend(cost_of_risk_long_term_trend::NAME)),
+            loss_ratio_long_term_trend.eq(trend(loss_ratio_long_term_trend::NAME)),
+            combined_ratio_long_term_trend.eq(trend(combined_ratio_long_term_trend::NAME)),
+            gross_margin_long_term_trend.eq(trend(gross_margin_long_term_trend::NAME)),
+            sga_long_term_trend.eq(trend(sga_long_term_trend::NAME)),
+            rnd_long_term_trend.eq(trend(rnd_long_term_trend::NAME)),
+            operating_margin_long_term_trend.eq(trend(operating_margin_long_term_trend::NAME)),
+            shares_change_trend.eq(trend(shares_change_trend::NAME)),
+            retained_earnings_trend.eq(trend(retained_earnings_trend::NAME)),
+            net_cash_trend.eq(trend(net_cash_trend::NAME)),
+            operating_cash_flow_margin_trend.eq(trend(operating_cash_flow_margin_trend::NAME)),
+            ffo_margin_trend.eq(trend(ffo_margin_trend::NAME)),
         ),
         conn,
     )?;
@@ -255,63 +472,181 @@ pub fn copy_latest_data(comp_id: i32, conn: &mut PgConnection) -> Result<(), Die
             .order((year_str.desc(), quarter_str.desc())),
         conn,
     )?;
+    let latest = |name: &str| {
+        metrics::LATEST_QUARTER_FIELDS
+            .iter()
+            .find(|(metric, _, _)| *metric == name)
+            .and_then(|(_, _, value)| value(&latest_earnings_ttm))
+    };
     query::update_metrics_table(
         comp_id,
         (
-            current_metrics::currency.eq(latest_earnings_ttm.currency),
-            net_interest_income_growth_yoy_ttm.eq(latest_earnings_ttm.net_interest_growth_yoy),
-            net_interest_margin_ttm.eq(latest_earnings_ttm.net_interest_margin),
-            cost_of_risk_ttm.eq(latest_earnings_ttm.cost_of_risk),
-            revenue_ttm.eq(latest_earnings_ttm.revenue),
-            revenue_growth_yoy_ttm.eq(latest_earnings_ttm.revenue_growth_yoy),
-            gross_profit_growth_yoy_ttm.eq(latest_earnings_ttm.gross_profit_growth_yoy),
-            gross_margin_ttm.eq(latest_earnings_ttm.gross_margin),
-            sga_ratio_ttm.eq(latest_earnings_ttm.sga_gp_ratio),
-            rnd_ratio_ttm.eq(latest_earnings_ttm.rnd_gp_ratio),
-            operating_margin_ttm.eq(latest_earnings_ttm.operating_margin),
-            interest_expense_ratio_ttm.eq(latest_earnings_ttm.interest_expenses_op_income_ratio),
-            net_margin_ttm.eq(latest_earnings_ttm.net_margin),
-            shares_outstanding_diluted_ttm.eq(latest_earnings_ttm.shares_outstanding_diluted),
-            shares_change_ttm.eq(latest_earnings_ttm.shares_change_yoy),
-            retained_earnings_ttm.eq(latest_earnings_ttm.retained_earnings),
-            net_cash_ttm.eq(latest_earnings_ttm.net_cash),
-            operating_cash_flow_ttm.eq(latest_earnings_ttm.operating_cash_flow),
-            operating_cash_flow_margin_ttm.eq(latest_earnings_ttm.operating_cash_flow_margin),
-            free_cash_flow_ttm.eq(latest_earnings_ttm.free_cash_flow),
-            free_cash_flow_margin_ttm.eq(latest_earnings_ttm.free_cash_flow_margin),
-            ffo_margin_ttm.eq(latest_earnings_ttm.ffo_margin),
+            current_metrics::currency.eq(&latest_earnings_ttm.currency),
+            net_interest_income_growth_yoy_ttm.eq(latest(net_interest_income_growth_yoy_ttm::NAME)),
+            net_interest_margin_ttm.eq(latest(net_interest_margin_ttm::NAME)),
+            cost_of_risk_ttm.eq(latest(cost_of_risk_ttm::NAME)),
+            premiums_growth_yoy_ttm.eq(latest(premiums_growth_yoy_ttm::NAME)),
+            loss_ratio_ttm.eq(latest(loss_ratio_ttm::NAME)),
+            combined_ratio_ttm.eq(latest(combined_ratio_ttm::NAME)),
+            revenue_ttm.eq(latest(revenue_ttm::NAME)),
+            revenue_growth_yoy_ttm.eq(latest(revenue_growth_yoy_ttm::NAME)),
+            gross_profit_growth_yoy_ttm.eq(latest(gross_profit_growth_yoy_ttm::NAME)),
+            gross_margin_ttm.eq(latest(gross_margin_ttm::NAME)),
+            sga_ratio_ttm.eq(latest(sga_ratio_ttm::NAME)),
+            rnd_ratio_ttm.eq(latest(rnd_ratio_ttm::NAME)),
+            operating_margin_ttm.eq(latest(operating_margin_ttm::NAME)),
+            interest_expense_ratio_ttm.eq(latest(interest_expense_ratio_ttm::NAME)),
+            net_margin_ttm.eq(latest(net_margin_ttm::NAME)),
+            shares_outstanding_diluted_ttm.eq(latest(shares_outstanding_diluted_ttm::NAME)),
+            shares_change_ttm.eq(latest(shares_change_ttm::NAME)),
+            retained_earnings_ttm.eq(latest(retained_earnings_ttm::NAME)),
+            net_cash_ttm.eq(latest(net_cash_ttm::NAME)),
+            operating_cash_flow_ttm.eq(latest(operating_cash_flow_ttm::NAME)),
+            operating_cash_flow_margin_ttm.eq(latest(operating_cash_flow_margin_ttm::NAME)),
+            free_cash_flow_ttm.eq(latest(free_cash_flow_ttm::NAME)),
+            free_cash_flow_margin_ttm.eq(latest(free_cash_flow_margin_ttm::NAME)),
+            ffo_margin_ttm.eq(latest(ffo_margin_ttm::NAME)),
         ),
         conn,
     )?;
     Ok(())
 }
 
-/// updates price target in the metrics table, which is calculated with current-year or multi-year growth rate
-pub fn update_price_target(comp_id: i32, conn: &mut PgConnection) -> Result<(), DieselError> {
-    use crate::schema::companies;
-    use crate::schema::companies::dsl::*;
+/// stock price the Altman Z-score is calculated with, converted into the reporting currency
+/// so that the market cap can be compared with the liabilities
+pub fn altman_z_score_price(
+    comp_id: i32,
+    conn: &mut PgConnection,
+) -> Result<Option<f64>, DieselError> {
+    let target_forecast = Forecasts::load_by_id(comp_id, conn)?;
+    let target_metrics = CurrentMetrics::load_by_id(comp_id, conn)?;
+    let fx_rate = reporting_to_trading_rate(&target_metrics, &target_forecast, conn)?;
+    Ok(target_forecast
+        .latest_price
+        .zip(fx_rate)
+        .map(|(price, rate)| price / rate))
+}
+
+/// updates Piotroski F-score, Altman Z-score and net cash health in the metrics table
+pub fn update_health_scores(comp_id: i32, conn: &mut PgConnection) -> Result<(), DieselError> {
     use crate::schema::current_metrics::dsl::*;
-    use crate::schema::forecasts::dsl::*;
+    let annual_earnings = query::load_multiple_earnings_annual(comp_id, 2, conn)?;
+    let f_score = health::consecutive_years(&annual_earnings)
+        .map(|(curr, prev)| health::calculate_piotroski_f_score(curr, prev));
+    let latest_earnings = EarningsReport::latest_quarter_data(comp_id, conn)?;
+    let price = altman_z_score_price(comp_id, conn)?;
+    let z_score = health::calculate_altman_z_score(&latest_earnings, price);
+    let is_cash_healthy = metrics::has_healthy_cash_position(&latest_earnings);
+    query::update_metrics_table(
+        comp_id,
+        (
+            piotroski_f_score.eq(f_score),
+            altman_z_score.eq(z_score),
+            has_healthy_net_cash.eq(is_cash_healthy),
+        ),
+        conn,
+    )?;
+    Ok(())
+}
+
+/// evaluates red-flag checks on the latest earnings and metrics, and stores triggered flags
+pub fn update_red_flags(comp_id: i32, conn: &mut PgConnection) -> Result<(), DieselError> {
+    let config = flags::RedFlagConfig::from_env();
+    let latest_earnings = EarningsReport::latest_quarter_data(comp_id, conn)?;
+    let target_metrics = CurrentMetrics::load_by_id(comp_id, conn)?;
+    let current_flags = flags::evaluate_red_flags(&config, &latest_earnings, &target_metrics);
+    flags_model::replace_red_flags(comp_id, &current_flags, conn)?;
+    Ok(())
+}
+
+/// validates the stored statements of the given company and replaces its ingest issues.
+/// statements dropped during the ingest are reported by their fiscal quarter.
+/// rejected_annual_periods is None when the annual statements weren't ingested,
+/// so the annual statements dropped by an earlier ingest are still reported.
+pub fn update_ingest_issues(
+    comp_id: i32,
+    rejected_ttm_periods: &[String],
+    rejected_annual_periods: Option<&[String]>,
+    conn: &mut PgConnection,
+) -> Result<(), DieselError> {
+    let reports = EarningsReport::load_all_by_id(comp_id, conn)?;
+    let kept_checks = match rejected_annual_periods {
+        Some(_) => vec![],
+        None => vec![validation::UNPARSEABLE_ANNUAL_PERIOD_CHECK],
+    };
+    let entries: Vec<NewIngestIssue> = rejected_ttm_periods
+        .iter()
+        .map(|period| validation::rejected_statement(period, false))
+        .chain(
+            rejected_annual_periods
+                .unwrap_or_default()
+                .iter()
+                .map(|period| validation::rejected_statement(period, true)),
+        )
+        .chain(validation::validate_statements(&reports))
+        .map(|finding| {
+            NewIngestIssue::create_new_entry(
+                comp_id,
+                finding.period,
+                finding.check_name,
+                finding.severity,
+                finding.message,
+            )
+        })
+        .collect();
+    ingest_issues_model::replace_ingest_issues(comp_id, &entries, &kept_checks, conn)?;
+    Ok(())
+}
+
+/// requests periods missing from the stored statements of the given company from the data source.
+/// is_attempted tells if the full history was just scraped.
+pub fn update_backfill_requests(
+    comp_id: i32,
+    is_attempted: bool,
+    conn: &mut PgConnection,
+) -> Result<(), DieselError> {
+    let reports = EarningsReport::load_all_by_id(comp_id, conn)?;
+    let mut missing_periods = gaps::missing_quarters(&reports);
+    missing_periods.extend(gaps::missing_fiscal_years(&reports));
+    backfill_model::replace_backfill_requests(comp_id, &missing_periods, is_attempted, conn)?;
+    Ok(())
+}
+
+/// theoretical net margin from the current gross margin and industry, whether the actual
+/// net margin already reaches it, and the theoretical EPS from the better of both margins
+pub fn current_theoretical_eps(
+    comp_id: i32,
+    conn: &mut PgConnection,
+) -> Result<(f64, bool, TheoreticalEps), DieselError> {
     let latest_earnings = EarningsReport::latest_quarter_data(comp_id, conn)?;
-    let company: Company =
-        query::load_first_row(companies.filter(companies::id.eq(comp_id)), conn)?;
+    let company = Company::load_by_id(comp_id, conn)?;
     let target_metrics = CurrentMetrics::load_by_id(comp_id, conn)?;
     let net_margin_factor = calculate::get_net_margin_factor(&company.industry);
     let (curr_theoretical_net_margin, is_optimized) =
         metrics::is_net_margin_optimized(&latest_earnings, net_margin_factor);
-    let curr_theoretical_net_income = match is_optimized {
-        true => calculate::calculate_margin_portion(
-            target_metrics.revenue_ttm,
-            target_metrics.net_margin_ttm,
-        ),
-        false => target_metrics
-            .revenue_ttm
-            .map(|val| val * curr_theoretical_net_margin / 100.),
+    let net_margin = match is_optimized {
+        true => target_metrics.net_margin_ttm,
+        false => Some(curr_theoretical_net_margin),
     };
-    let curr_theoretical_eps = calculate::calculate_per_share(
-        curr_theoretical_net_income,
+    let target_forecast = Forecasts::load_by_id(comp_id, conn)?;
+    let fx_rate = reporting_to_trading_rate(&target_metrics, &target_forecast, conn)?;
+    let theoretical_eps = TheoreticalEps::new(
+        target_metrics.revenue_ttm,
+        net_margin,
         target_metrics.shares_outstanding_diluted_ttm,
+        fx_rate,
     );
+    Ok((curr_theoretical_net_margin, is_optimized, theoretical_eps))
+}
+
+/// updates price target in the metrics table, which is calculated with current-year or multi-year growth rate
+pub fn update_price_target(comp_id: i32, conn: &mut PgConnection) -> Result<(), DieselError> {
+    use crate::schema::current_metrics::dsl::*;
+    use crate::schema::forecasts::dsl::*;
+    let (curr_theoretical_net_margin, is_optimized, theoretical_eps) =
+        current_theoretical_eps(comp_id, conn)?;
+    let target_metrics = CurrentMetrics::load_by_id(comp_id, conn)?;
+    let curr_theoretical_eps = theoretical_eps.eps;
     let curr_theoretical_price_rev = calculate::calculate_price_target_option(
         curr_theoretical_eps,
         target_metrics.revenue_growth_yoy_ttm,
@@ -348,34 +683,64 @@ pub fn update_price_target(comp_id: i32, conn: &mut PgConnection) -> Result<(),
             price_current_gp_growth.eq(curr_theoretical_price_gp),
             price_multi_year_revenue_growth.eq(curr_theoretical_price_multi_rev),
             price_multi_year_gp_growth.eq(curr_theoretical_price_multi_gp),
+            price_target_available.eq(theoretical_eps.fx_rate.is_some()),
         ),
         conn,
     )?;
     Ok(())
 }
 
-/// updates price target in the metrics table, which is calculated based on the guidance
-pub fn update_guidance(comp_id: i32, conn: &mut PgConnection) -> Result<(), DieselError> {
-    use crate::schema::forecasts::dsl::*;
+/// rate to convert amounts in the reporting currency into the trading currency.
+/// returns None when the trading currency is unknown, or when the currencies differ
+/// and no exchange rate is stored.
+pub fn reporting_to_trading_rate(
+    target_metrics: &CurrentMetrics,
+    target_forecast: &Forecasts,
+    conn: &mut PgConnection,
+) -> Result<Option<f64>, DieselError> {
+    let reporting_currency = target_metrics.currency.as_str();
+    match target_forecast.trading_currency.as_deref() {
+        None => Ok(None),
+        Some(trading) if !reporting_currency.is_empty() && trading != reporting_currency => {
+            let fx_table = FxTable::new(FxRate::load_all(conn)?);
+            Ok(fx_table.rate(reporting_currency, trading))
+        }
+        Some(_) => Ok(Some(1.)),
+    }
+}
+
+/// growth implied by the revenue estimate for next year and the theoretical EPS it leads to
+pub fn next_year_theoretical_eps(
+    comp_id: i32,
+    conn: &mut PgConnection,
+) -> Result<(Option<f64>, TheoreticalEps), DieselError> {
     let latest_earnings = EarningsReport::latest_annual_data(comp_id, conn)?;
     let target_metrics = CurrentMetrics::load_by_id(comp_id, conn)?;
     let target_forecast = Forecasts::load_by_id(comp_id, conn)?;
     let curr_rev = latest_earnings.revenue;
     let next_yr_rev = target_forecast.revenue_next_year;
     let next_yr_rev_growth = next_yr_rev.map(|val| calculate::calculate_yoy_growth(val, curr_rev));
-    let next_theoretical_net_income = match target_metrics.is_net_margin_optimized {
-        Some(true) => {
-            calculate::calculate_margin_portion(next_yr_rev, target_metrics.net_margin_ttm)
-        }
-        Some(false) => {
-            calculate::calculate_margin_portion(next_yr_rev, target_metrics.theoretical_net_margin)
-        }
+    let net_margin = match target_metrics.is_net_margin_optimized {
+        Some(true) => target_metrics.net_margin_ttm,
+        Some(false) => target_metrics.theoretical_net_margin,
         None => None,
     };
-    let next_theoretical_eps = calculate::calculate_per_share(
-        next_theoretical_net_income,
+    let fx_rate = reporting_to_trading_rate(&target_metrics, &target_forecast, conn)?;
+    let theoretical_eps = TheoreticalEps::new(
+        next_yr_rev,
+        net_margin,
         target_metrics.shares_outstanding_diluted_ttm,
+        fx_rate,
     );
+    Ok((next_yr_rev_growth, theoretical_eps))
+}
+
+/// updates price target in the metrics table, which is calculated based on the guidance
+pub fn update_guidance(comp_id: i32, conn: &mut PgConnection) -> Result<(), DieselError> {
+    use crate::schema::forecasts::dsl::*;
+    let (next_yr_rev_growth, theoretical_eps) = next_year_theoretical_eps(comp_id, conn)?;
+    let target_metrics = CurrentMetrics::load_by_id(comp_id, conn)?;
+    let next_theoretical_eps = theoretical_eps.eps;
     let next_yr_theoretical_price = calculate::calculate_price_target_option(
         next_theoretical_eps,
         next_yr_rev_growth,
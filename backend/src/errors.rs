@@ -16,6 +16,40 @@ pub enum BullsEyeError {
     DateParseError(#[from] chrono::format::ParseError),
     #[error(transparent)]
     EnvVarError(#[from] std::env::VarError),
+    #[error("Company not found: {0}")]
+    CompanyNotFoundError(String),
+    #[error("Industry not found: {0}")]
+    IndustryNotFoundError(String),
+    #[error("Unsupported exchange: {0}")]
+    UnsupportedExchangeError(String),
+    #[error("Unknown symbol: {0}. Specify the exchange explicitly")]
+    UnknownSymbolError(String),
+    #[error("Invalid currency code: {0}")]
+    InvalidCurrencyError(String),
+    #[error("Failed to load FX rates: {0}")]
+    FxRateLoadError(String),
+    #[error("Failed to load corporate actions: {0}")]
+    CorporateActionLoadError(String),
+    #[error("Unsupported export format: {0}")]
+    UnsupportedFormatError(String),
+    #[error("Failed to export data: {0}")]
+    ExportError(String),
+    #[error("Failed to serialize data: {0}")]
+    SerializationError(#[from] serde_json::Error),
+    #[error("Ambiguous symbol: {0} is listed on {1}. Specify the exchange explicitly")]
+    AmbiguousSymbolError(String, String),
+    #[error("Failed to import statements: {0}")]
+    ImportError(String),
+    #[error("Failed to read SEC companyfacts: {0}")]
+    SecFactsError(String),
+    #[error("Missing or invalid admin token")]
+    UnauthorizedError,
+    #[error("Field can't be explained: {0}")]
+    UnexplainableFieldError(String),
+    #[error("Ingest issue not found: {0}")]
+    IngestIssueNotFoundError(String),
+    #[error("Unknown red flag: {0}. Valid flags are {1}")]
+    UnknownFlagError(String, String),
 }
 
 impl IntoResponse for BullsEyeError {
@@ -40,6 +74,23 @@ impl IntoResponse for BullsEyeError {
             BullsEyeError::DbPoolError => StatusCode::INTERNAL_SERVER_ERROR,
             BullsEyeError::DateParseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             BullsEyeError::EnvVarError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            BullsEyeError::CompanyNotFoundError(_) => StatusCode::NOT_FOUND,
+            BullsEyeError::IndustryNotFoundError(_) => StatusCode::NOT_FOUND,
+            BullsEyeError::UnsupportedExchangeError(_) => StatusCode::BAD_REQUEST,
+            BullsEyeError::UnknownSymbolError(_) => StatusCode::NOT_FOUND,
+            BullsEyeError::AmbiguousSymbolError(_, _) => StatusCode::CONFLICT,
+            BullsEyeError::InvalidCurrencyError(_) => StatusCode::BAD_REQUEST,
+            BullsEyeError::FxRateLoadError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            BullsEyeError::CorporateActionLoadError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            BullsEyeError::UnsupportedFormatError(_) => StatusCode::NOT_ACCEPTABLE,
+            BullsEyeError::ExportError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            BullsEyeError::SerializationError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            BullsEyeError::ImportError(_) => StatusCode::BAD_REQUEST,
+            BullsEyeError::SecFactsError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            BullsEyeError::UnauthorizedError => StatusCode::UNAUTHORIZED,
+            BullsEyeError::UnexplainableFieldError(_) => StatusCode::NOT_FOUND,
+            BullsEyeError::IngestIssueNotFoundError(_) => StatusCode::NOT_FOUND,
+            BullsEyeError::UnknownFlagError(_, _) => StatusCode::BAD_REQUEST,
         };
 
         (status, self.to_string()).into_response()
@@ -0,0 +1,628 @@
+use crate::calculate;
+use crate::db;
+use crate::flags::{self, RedFlagConfig};
+use crate::gaps;
+use crate::health;
+use crate::metrics::{self, TheoreticalEps, TrendSpec};
+use crate::models::companies_model::Company;
+use crate::models::earnings_model::EarningsReport;
+use crate::models::flags_model::RedFlag;
+use crate::models::forecast_models::Forecasts;
+use crate::models::metrics_model::{CurrentMetrics, Trend};
+use crate::models::peers_model::IndustryDistribution;
+use crate::query;
+use crate::validation;
+use crate::valuation::{self, MultiplePoint, ValuationInputs};
+use chrono::NaiveDate;
+use diesel::pg::PgConnection;
+use diesel::result::Error as DieselError;
+use serde::Serialize;
+
+type FieldValue = fn(&EarningsReport) -> Option<f64>;
+
+/// multi-year growth rates and the yearly growth they average
+const MULTI_YEAR_FIELDS: [(&str, &str, FieldValue); 5] = [
+    (
+        "net_interest_income_growth_multi_year",
+        "net_interest_growth_yoy",
+        |f| f.net_interest_growth_yoy,
+    ),
+    ("revenue_growth_multi_year", "revenue_growth_yoy", |f| {
+        f.revenue_growth_yoy
+    }),
+    ("premiums_growth_multi_year", "premiums_growth_yoy", |f| {
+        f.premiums_growth_yoy
+    }),
+    (
+        "gross_profit_growth_multi_year",
+        "gross_profit_growth_yoy",
+        |f| f.gross_profit_growth_yoy,
+    ),
+    ("shares_change_multi_year", "shares_change_yoy", |f| {
+        f.shares_change_yoy
+    }),
+];
+
+/// price targets from the current earnings and the metric holding the growth rate they use
+const PRICE_TARGET_FIELDS: [(&str, &str, fn(&CurrentMetrics) -> Option<f64>); 4] = [
+    (
+        "price_current_revenue_growth",
+        "revenue_growth_yoy_ttm",
+        |m| m.revenue_growth_yoy_ttm,
+    ),
+    (
+        "price_current_gp_growth",
+        "gross_profit_growth_yoy_ttm",
+        |m| m.gross_profit_growth_yoy_ttm,
+    ),
+    (
+        "price_multi_year_revenue_growth",
+        "revenue_growth_multi_year",
+        |m| m.revenue_growth_multi_year,
+    ),
+    (
+        "price_multi_year_gp_growth",
+        "gross_profit_growth_multi_year",
+        |m| m.gross_profit_growth_multi_year,
+    ),
+];
+
+/// percentiles of the current multiples within their history and the multiple they rank
+const PERCENTILE_FIELDS: [(
+    &str,
+    &str,
+    fn(&MultiplePoint) -> Option<f64>,
+    fn(&Forecasts) -> Option<f64>,
+); 2] = [
+    (
+        "pe_ratio_percentile",
+        "pe_ratio",
+        |p| p.pe_ratio,
+        |f| f.pe_ratio,
+    ),
+    (
+        "ps_ratio_percentile",
+        "ps_ratio",
+        |p| p.ps_ratio,
+        |f| f.ps_ratio,
+    ),
+];
+
+/// peer ranks and the metric they rank within the industry
+const PEER_RANK_FIELDS: [(&str, &str, fn(&CurrentMetrics) -> Option<f64>); 6] = [
+    ("gross_margin_rank", "gross_margin_ttm", |m| {
+        m.gross_margin_ttm
+    }),
+    ("operating_margin_rank", "operating_margin_ttm", |m| {
+        m.operating_margin_ttm
+    }),
+    ("revenue_growth_yoy_rank", "revenue_growth_yoy_ttm", |m| {
+        m.revenue_growth_yoy_ttm
+    }),
+    (
+        "revenue_growth_multi_year_rank",
+        "revenue_growth_multi_year",
+        |m| m.revenue_growth_multi_year,
+    ),
+    (
+        "free_cash_flow_margin_rank",
+        "free_cash_flow_margin_ttm",
+        |m| m.free_cash_flow_margin_ttm,
+    ),
+    ("shares_change_rank", "shares_change_ttm", |m| {
+        m.shares_change_ttm
+    }),
+];
+
+/// stored earnings value a metric is derived from
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputRow {
+    pub earnings_id: i32,
+    pub period: String,
+    pub period_ending: NaiveDate,
+    pub value: Option<f64>,
+}
+
+fn input_rows(reports: &[EarningsReport], value: FieldValue) -> Vec<InputRow> {
+    reports
+        .iter()
+        .map(|report| InputRow {
+            earnings_id: report.id,
+            period: validation::period_label(report),
+            period_ending: report.period_ending,
+            value: value(report),
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatestQuarter {
+    pub source: &'static str,
+    pub input: Option<InputRow>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiYearAverage {
+    pub source: &'static str,
+    pub ignore_none: bool,
+    pub inputs: Vec<InputRow>,
+    pub average: Option<f64>,
+}
+
+/// vote of one quarter against the average of the four quarters before it
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendVote {
+    pub period: String,
+    pub value: Option<f64>,
+    pub previous_average: Option<f64>,
+    pub vote: Trend,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortTermTrend {
+    pub source: &'static str,
+    pub ignore_none: bool,
+    pub flat_threshold: f64,
+    pub length: usize,
+    pub votes_needed: usize,
+    /// true when there are too few quarters to vote, which makes the trend irrelevant
+    pub insufficient_data: bool,
+    pub inputs: Vec<InputRow>,
+    pub votes: Vec<TrendVote>,
+    pub result: Trend,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LongTermTrend {
+    pub source: &'static str,
+    pub ignore_none: bool,
+    pub flat_threshold: f64,
+    /// true when there are too few years to compare, which makes the trend irrelevant
+    pub insufficient_data: bool,
+    pub inputs: Vec<InputRow>,
+    /// average of the newer half of the years
+    pub recent_average: Option<f64>,
+    /// average of the older half of the years
+    pub older_average: Option<f64>,
+    pub result: Trend,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceTarget {
+    pub theoretical_net_margin: Option<f64>,
+    pub is_net_margin_optimized: Option<bool>,
+    pub theoretical_eps: TheoreticalEps,
+    pub growth_source: &'static str,
+    pub growth: Option<f64>,
+    pub share_change: Option<f64>,
+    /// growth net of the capped share change
+    pub adjusted_growth: Option<f64>,
+    pub punishment: Option<f64>,
+    pub growth_multiplier: Option<f64>,
+    pub growth_adjustment_factor: Option<f64>,
+    pub price_target: Option<f64>,
+}
+
+impl PriceTarget {
+    fn new(
+        theoretical_net_margin: Option<f64>,
+        is_net_margin_optimized: Option<bool>,
+        theoretical_eps: TheoreticalEps,
+        growth_source: &'static str,
+        growth: Option<f64>,
+        share_change: Option<f64>,
+    ) -> Self {
+        let adjusted_growth = growth
+            .zip(share_change)
+            .map(|(g, s)| calculate::calculate_share_adjusted_growth(g, s));
+        let terms = adjusted_growth.map(calculate::calculate_growth_adjustment_terms);
+        let price_target =
+            calculate::calculate_price_target_option(theoretical_eps.eps, growth, share_change);
+        PriceTarget {
+            theoretical_net_margin,
+            is_net_margin_optimized,
+            theoretical_eps,
+            growth_source,
+            growth,
+            share_change,
+            adjusted_growth,
+            punishment: terms.map(|(punishment, _)| punishment),
+            growth_multiplier: terms.map(|(_, multiplier)| multiplier),
+            growth_adjustment_factor: adjusted_growth
+                .map(calculate::calculate_growth_adjustment_factor),
+            price_target,
+        }
+    }
+}
+
+/// one criterion of the Piotroski F-score
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreCriterion {
+    pub name: &'static str,
+    pub passed: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PiotroskiFScore {
+    /// latest two annual earnings, newest first
+    pub periods: Vec<String>,
+    /// false when the years aren't consecutive, which leaves the score empty
+    pub is_consecutive: bool,
+    pub criteria: Vec<ScoreCriterion>,
+    pub score: Option<i16>,
+}
+
+/// weighted ratio of the Altman Z-score
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreTerm {
+    pub name: &'static str,
+    pub weight: f64,
+    pub ratio: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AltmanZScore {
+    pub input_period: String,
+    /// latest price converted into the reporting currency
+    pub price: Option<f64>,
+    pub terms: Vec<ScoreTerm>,
+    pub z_score: Option<f64>,
+}
+
+/// red-flag check evaluated on the latest earnings and metrics
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedFlagCheck {
+    pub flag: RedFlag,
+    pub enabled: bool,
+    pub triggered: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedFlags {
+    pub input_period: String,
+    pub max_dilution_pct: f64,
+    pub max_interest_expense_ratio: f64,
+    pub checks: Vec<RedFlagCheck>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValuationMultiple {
+    pub numerator_source: &'static str,
+    pub denominator_source: &'static str,
+    pub inputs: ValuationInputs,
+    pub multiple: Option<f64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoricalPercentile {
+    pub source: &'static str,
+    pub current: Option<f64>,
+    /// years of history the multiple is ranked within, None when the history is too short
+    pub window_years: Option<i64>,
+    pub observations: usize,
+    pub percentile: Option<f64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerRank {
+    pub source: &'static str,
+    pub industry: String,
+    pub peer_count: usize,
+    pub value: Option<f64>,
+    /// peers with the metric available, the company itself included
+    pub observations: usize,
+    pub rank: Option<f64>,
+}
+
+/// how a metric is derived from the stored earnings
+#[derive(Serialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Derivation {
+    LatestQuarter(LatestQuarter),
+    MultiYearAverage(MultiYearAverage),
+    ShortTermTrend(ShortTermTrend),
+    LongTermTrend(LongTermTrend),
+    PriceTarget(PriceTarget),
+    PiotroskiFScore(PiotroskiFScore),
+    AltmanZScore(AltmanZScore),
+    RedFlags(RedFlags),
+    ValuationMultiple(ValuationMultiple),
+    HistoricalPercentile(HistoricalPercentile),
+    PeerRank(PeerRank),
+}
+
+fn explain_short_term_trend(spec: &TrendSpec, reports: &[EarningsReport]) -> ShortTermTrend {
+    let values = db::extract_field(reports, spec.value);
+    let length = metrics::SHORT_TERM_TREND_LENGTH;
+    let insufficient_data = values.len() < length + 4;
+    let votes = match insufficient_data {
+        true => Vec::new(),
+        false => (0..length)
+            .map(|idx| {
+                let previous_average =
+                    calculate::average_options(&values[idx + 1..idx + 5], spec.ignore_none);
+                TrendVote {
+                    period: validation::period_label(&reports[idx]),
+                    value: values[idx],
+                    previous_average,
+                    vote: calculate::compare_with_average(
+                        values[idx],
+                        previous_average,
+                        spec.flat_threshold,
+                    ),
+                }
+            })
+            .collect(),
+    };
+    ShortTermTrend {
+        source: spec.source,
+        ignore_none: spec.ignore_none,
+        flat_threshold: spec.flat_threshold,
+        length,
+        votes_needed: metrics::SHORT_TERM_TREND_VOTES,
+        insufficient_data,
+        inputs: input_rows(reports, spec.value),
+        votes,
+        result: metrics::get_short_term_trend_spec(reports, spec),
+    }
+}
+
+fn explain_long_term_trend(spec: &TrendSpec, reports: &[EarningsReport]) -> LongTermTrend {
+    let values = db::extract_field(reports, spec.value);
+    let insufficient_data = values.len() < 2;
+    let (recent_average, older_average) = match insufficient_data {
+        true => (None, None),
+        false => {
+            let (new_interval, old_interval) = values.split_at(values.len() / 2);
+            (
+                calculate::average_options(new_interval, spec.ignore_none),
+                calculate::average_options(old_interval, spec.ignore_none),
+            )
+        }
+    };
+    LongTermTrend {
+        source: spec.source,
+        ignore_none: spec.ignore_none,
+        flat_threshold: spec.flat_threshold,
+        insufficient_data,
+        inputs: input_rows(reports, spec.value),
+        recent_average,
+        older_average,
+        result: metrics::get_long_term_trend_spec(reports, spec),
+    }
+}
+
+fn explain_piotroski_f_score(annual_earnings: &[EarningsReport]) -> PiotroskiFScore {
+    let consecutive = health::consecutive_years(annual_earnings);
+    let criteria = consecutive
+        .map(|(curr, prev)| {
+            health::piotroski_criteria(curr, prev)
+                .into_iter()
+                .map(|(name, passed)| ScoreCriterion { name, passed })
+                .collect()
+        })
+        .unwrap_or_default();
+    PiotroskiFScore {
+        periods: annual_earnings
+            .iter()
+            .map(validation::period_label)
+            .collect(),
+        is_consecutive: consecutive.is_some(),
+        criteria,
+        score: consecutive.map(|(curr, prev)| health::calculate_piotroski_f_score(curr, prev)),
+    }
+}
+
+fn explain_altman_z_score(latest: &EarningsReport, price: Option<f64>) -> AltmanZScore {
+    let terms = health::altman_z_score_terms(latest, price)
+        .map(|terms| {
+            terms
+                .into_iter()
+                .map(|(name, weight, ratio)| ScoreTerm {
+                    name,
+                    weight,
+                    ratio,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    AltmanZScore {
+        input_period: validation::period_label(latest),
+        price,
+        terms,
+        z_score: health::calculate_altman_z_score(latest, price),
+    }
+}
+
+fn explain_red_flags(
+    config: &RedFlagConfig,
+    latest: &EarningsReport,
+    target_metrics: &CurrentMetrics,
+) -> RedFlags {
+    let checks = RedFlag::ALL
+        .into_iter()
+        .map(|flag| {
+            let enabled = config.enabled.contains(&flag);
+            RedFlagCheck {
+                flag,
+                enabled,
+                triggered: enabled && flags::is_flagged(flag, config, latest, target_metrics),
+            }
+        })
+        .collect();
+    RedFlags {
+        input_period: validation::period_label(latest),
+        max_dilution_pct: config.max_dilution_pct,
+        max_interest_expense_ratio: config.max_interest_expense_ratio,
+        checks,
+    }
+}
+
+/// rebuilds the derivation of the given metrics, forecasts or peer ranks column, or of the
+/// red flags, from the stored data. returns None for values that aren't derived from the earnings.
+pub fn explain_field(
+    comp_id: i32,
+    field: &str,
+    conn: &mut PgConnection,
+) -> Result<Option<Derivation>, DieselError> {
+    if let Some((_, source, value)) = metrics::LATEST_QUARTER_FIELDS
+        .iter()
+        .find(|(metric, _, _)| *metric == field)
+    {
+        let latest = query::load_multiple_earnings_ttm(comp_id, 1, conn)?;
+        return Ok(Some(Derivation::LatestQuarter(LatestQuarter {
+            source: *source,
+            input: input_rows(&latest, *value).pop(),
+        })));
+    }
+    if let Some((_, source, value)) = MULTI_YEAR_FIELDS
+        .iter()
+        .find(|(metric, _, _)| *metric == field)
+    {
+        let reports =
+            query::load_multiple_earnings_annual(comp_id, metrics::MULTI_YEAR_GROWTH_YEARS, conn)?;
+        let values = db::extract_field(&reports, *value);
+        return Ok(Some(Derivation::MultiYearAverage(MultiYearAverage {
+            source: *source,
+            ignore_none: true,
+            inputs: input_rows(&reports, *value),
+            average: calculate::average_options(&values, true),
+        })));
+    }
+    if let Some(spec) = metrics::SHORT_TERM_TRENDS
+        .iter()
+        .find(|spec| spec.metric == field)
+    {
+        let reports = query::load_multiple_earnings_ttm(
+            comp_id,
+            gaps::SHORT_TERM_TREND_QUARTERS as i64,
+            conn,
+        )?;
+        return Ok(Some(Derivation::ShortTermTrend(explain_short_term_trend(
+            spec, &reports,
+        ))));
+    }
+    if let Some(spec) = metrics::LONG_TERM_TRENDS
+        .iter()
+        .find(|spec| spec.metric == field)
+    {
+        let reports =
+            query::load_multiple_earnings_annual(comp_id, metrics::LONG_TERM_TREND_YEARS, conn)?;
+        return Ok(Some(Derivation::LongTermTrend(explain_long_term_trend(
+            spec, &reports,
+        ))));
+    }
+    if let Some((_, growth_source, growth)) = PRICE_TARGET_FIELDS
+        .iter()
+        .find(|(metric, _, _)| *metric == field)
+    {
+        let (theoretical_net_margin, is_optimized, theoretical_eps) =
+            db::current_theoretical_eps(comp_id, conn)?;
+        let target_metrics = CurrentMetrics::load_by_id(comp_id, conn)?;
+        return Ok(Some(Derivation::PriceTarget(PriceTarget::new(
+            Some(theoretical_net_margin),
+            Some(is_optimized),
+            theoretical_eps,
+            *growth_source,
+            growth(&target_metrics),
+            target_metrics.shares_change_ttm,
+        ))));
+    }
+    if field == "piotroski_f_score" {
+        let annual_earnings = query::load_multiple_earnings_annual(comp_id, 2, conn)?;
+        return Ok(Some(Derivation::PiotroskiFScore(
+            explain_piotroski_f_score(&annual_earnings),
+        )));
+    }
+    if field == "altman_z_score" {
+        let latest = EarningsReport::latest_quarter_data(comp_id, conn)?;
+        let price = db::altman_z_score_price(comp_id, conn)?;
+        return Ok(Some(Derivation::AltmanZScore(explain_altman_z_score(
+            &latest, price,
+        ))));
+    }
+    if field == "red_flags" {
+        let latest = EarningsReport::latest_quarter_data(comp_id, conn)?;
+        let target_metrics = CurrentMetrics::load_by_id(comp_id, conn)?;
+        return Ok(Some(Derivation::RedFlags(explain_red_flags(
+            &RedFlagConfig::from_env(),
+            &latest,
+            &target_metrics,
+        ))));
+    }
+    if let Some((_, numerator_source, _, denominator_source, _)) = valuation::MULTIPLE_FIELDS
+        .iter()
+        .find(|(metric, _, _, _, _)| *metric == field)
+    {
+        let inputs = db::current_valuation_inputs(comp_id, conn)?;
+        return Ok(Some(Derivation::ValuationMultiple(ValuationMultiple {
+            numerator_source: *numerator_source,
+            denominator_source: *denominator_source,
+            multiple: inputs.multiple(field),
+            inputs,
+        })));
+    }
+    if let Some((_, source, history_value, current_value)) = PERCENTILE_FIELDS
+        .iter()
+        .find(|(metric, _, _, _)| *metric == field)
+    {
+        let history = db::load_multiple_history(comp_id, conn)?;
+        let current = current_value(&Forecasts::load_by_id(comp_id, conn)?);
+        let window = valuation::percentile_window(&history, *history_value);
+        return Ok(Some(Derivation::HistoricalPercentile(
+            HistoricalPercentile {
+                source: *source,
+                current,
+                window_years: window.as_ref().map(|(years, _)| *years),
+                observations: window.as_ref().map_or(0, |(_, values)| values.len()),
+                percentile: valuation::historical_percentile(&history, *history_value, current),
+            },
+        )));
+    }
+    if let Some((_, source, value)) = PEER_RANK_FIELDS
+        .iter()
+        .find(|(metric, _, _)| *metric == field)
+    {
+        let company = Company::load_by_id(comp_id, conn)?;
+        let peers = db::load_industry_metrics(&company.industry, conn)?;
+        let population = IndustryDistribution::collect_values(&peers, *value);
+        let current = value(&CurrentMetrics::load_by_id(comp_id, conn)?);
+        return Ok(Some(Derivation::PeerRank(PeerRank {
+            source: *source,
+            industry: company.industry,
+            peer_count: peers.len(),
+            value: current,
+            observations: population.len(),
+            rank: calculate::calculate_percentile_rank(current, &population),
+        })));
+    }
+    if field == "price_next_year_revenue_growth" {
+        let (growth, theoretical_eps) = db::next_year_theoretical_eps(comp_id, conn)?;
+        let target_metrics = CurrentMetrics::load_by_id(comp_id, conn)?;
+        return Ok(Some(Derivation::PriceTarget(PriceTarget::new(
+            target_metrics.theoretical_net_margin,
+            target_metrics.is_net_margin_optimized,
+            theoretical_eps,
+            "revenue_growth_next_year",
+            growth,
+            target_metrics.shares_change_ttm,
+        ))));
+    }
+    Ok(None)
+}
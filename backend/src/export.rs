@@ -0,0 +1,383 @@
+use crate::errors::BullsEyeError;
+use crate::helper;
+use axum::response::{IntoResponse, Response};
+use http::header::{ACCEPT, CONTENT_DISPOSITION, CONTENT_TYPE};
+use http::HeaderMap;
+use rust_xlsxwriter::Workbook;
+use serde::Serialize;
+use serde_json::Value;
+
+const CSV_MIME: &str = "text/csv";
+const XLSX_MIME: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// response format picked from `?format=` or, when absent, the Accept header
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn negotiate(format: Option<&str>, headers: &HeaderMap) -> Result<Self, BullsEyeError> {
+        if let Some(name) = format {
+            return match name.trim().to_lowercase().as_str() {
+                "json" => Ok(ExportFormat::Json),
+                "csv" => Ok(ExportFormat::Csv),
+                "xlsx" => Ok(ExportFormat::Xlsx),
+                _ => Err(BullsEyeError::UnsupportedFormatError(name.to_string())),
+            };
+        }
+        let accept = headers
+            .get(ACCEPT)
+            .and_then(|val| val.to_str().ok())
+            .unwrap_or_default();
+        if accept.contains(XLSX_MIME) {
+            Ok(ExportFormat::Xlsx)
+        } else if accept.contains(CSV_MIME) {
+            Ok(ExportFormat::Csv)
+        } else {
+            Ok(ExportFormat::Json)
+        }
+    }
+}
+
+/// currencies used to label amount columns.
+/// generic labels are used when rows are in different currencies.
+#[derive(Default)]
+pub struct CurrencyLabels<'a> {
+    pub reporting: Option<&'a str>,
+    pub trading: Option<&'a str>,
+    pub converted: Option<&'a str>,
+}
+
+#[derive(Clone, Copy)]
+enum Unit {
+    Percent,
+    Multiple,
+    Score,
+    Shares,
+    PerShare,
+    Reporting,
+    Trading,
+}
+
+/// units of the numeric fields of the exported models, keyed by snake case field name.
+/// fields not listed here are descriptive and exported without a unit.
+const COLUMN_UNITS: &[(&str, Unit)] = &[
+    // earnings reports
+    ("net_interest_income", Unit::Reporting),
+    ("net_interest_growth_yoy", Unit::Percent),
+    ("net_interest_margin", Unit::Percent),
+    ("provision_for_loan_loss", Unit::Reporting),
+    ("cost_of_risk", Unit::Percent),
+    ("revenue", Unit::Reporting),
+    ("revenue_growth_yoy", Unit::Percent),
+    ("cost_of_revenue", Unit::Reporting),
+    ("gross_profit", Unit::Reporting),
+    ("gross_margin", Unit::Percent),
+    ("gross_profit_growth_yoy", Unit::Percent),
+    ("sga_expenses", Unit::Reporting),
+    ("sga_gp_ratio", Unit::Multiple),
+    ("rnd_expenses", Unit::Reporting),
+    ("rnd_gp_ratio", Unit::Multiple),
+    ("operating_expenses", Unit::Reporting),
+    ("operating_income", Unit::Reporting),
+    ("operating_margin", Unit::Percent),
+    ("interest_expenses", Unit::Reporting),
+    ("interest_expenses_op_income_ratio", Unit::Multiple),
+    ("goodwill_impairment", Unit::Reporting),
+    ("net_income", Unit::Reporting),
+    ("net_margin", Unit::Percent),
+    ("eps_basic", Unit::PerShare),
+    ("eps_diluted", Unit::PerShare),
+    ("shares_outstanding_basic", Unit::Shares),
+    ("shares_outstanding_diluted", Unit::Shares),
+    ("shares_change_yoy", Unit::Percent),
+    ("ffo", Unit::Reporting),
+    ("ffo_margin", Unit::Percent),
+    ("cash_and_equivalents", Unit::Reporting),
+    ("cash_and_short_term_investments", Unit::Reporting),
+    ("total_investments", Unit::Reporting),
+    ("gross_loans", Unit::Reporting),
+    ("accounts_receivable", Unit::Reporting),
+    ("inventory", Unit::Reporting),
+    ("total_current_assets", Unit::Reporting),
+    ("goodwill", Unit::Reporting),
+    ("total_assets", Unit::Reporting),
+    ("accounts_payable", Unit::Reporting),
+    ("total_current_liabilities", Unit::Reporting),
+    ("total_liabilities", Unit::Reporting),
+    ("retained_earnings", Unit::Reporting),
+    ("shareholders_equity", Unit::Reporting),
+    ("total_debt", Unit::Reporting),
+    ("net_cash", Unit::Reporting),
+    ("depreciation_and_amortization", Unit::Reporting),
+    ("stock_based_compensation", Unit::Reporting),
+    ("operating_cash_flow", Unit::Reporting),
+    ("operating_cash_flow_margin", Unit::Percent),
+    ("capital_expenditure", Unit::Reporting),
+    ("investing_cash_flow", Unit::Reporting),
+    ("financing_cash_flow", Unit::Reporting),
+    ("free_cash_flow", Unit::Reporting),
+    ("free_cash_flow_margin", Unit::Percent),
+    ("premiums_earned", Unit::Reporting),
+    ("premiums_growth_yoy", Unit::Percent),
+    ("policy_benefits", Unit::Reporting),
+    ("underwriting_expenses", Unit::Reporting),
+    ("loss_ratio", Unit::Percent),
+    ("combined_ratio", Unit::Percent),
+    ("split_factor", Unit::Multiple),
+    ("reported_revenue_growth_yoy", Unit::Percent),
+    ("reported_shares_change_yoy", Unit::Percent),
+    // screener metrics
+    ("net_interest_income_growth_yoy_ttm", Unit::Percent),
+    ("net_interest_income_growth_multi_year", Unit::Percent),
+    ("net_interest_margin_ttm", Unit::Percent),
+    ("cost_of_risk_ttm", Unit::Percent),
+    ("revenue_ttm", Unit::Reporting),
+    ("revenue_growth_yoy_ttm", Unit::Percent),
+    ("revenue_growth_multi_year", Unit::Percent),
+    ("gross_profit_growth_yoy_ttm", Unit::Percent),
+    ("gross_profit_growth_multi_year", Unit::Percent),
+    ("gross_margin_ttm", Unit::Percent),
+    ("sga_ratio_ttm", Unit::Multiple),
+    ("rnd_ratio_ttm", Unit::Multiple),
+    ("operating_margin_ttm", Unit::Percent),
+    ("interest_expense_ratio_ttm", Unit::Multiple),
+    ("net_margin_ttm", Unit::Percent),
+    ("theoretical_net_margin", Unit::Percent),
+    ("shares_outstanding_diluted_ttm", Unit::Shares),
+    ("shares_change_ttm", Unit::Percent),
+    ("shares_change_multi_year", Unit::Percent),
+    ("retained_earnings_ttm", Unit::Reporting),
+    ("net_cash_ttm", Unit::Reporting),
+    ("operating_cash_flow_ttm", Unit::Reporting),
+    ("operating_cash_flow_margin_ttm", Unit::Percent),
+    ("free_cash_flow_ttm", Unit::Reporting),
+    ("free_cash_flow_margin_ttm", Unit::Percent),
+    ("ffo_margin_ttm", Unit::Percent),
+    ("piotroski_f_score", Unit::Score),
+    ("altman_z_score", Unit::Score),
+    ("premiums_growth_yoy_ttm", Unit::Percent),
+    ("premiums_growth_multi_year", Unit::Percent),
+    ("loss_ratio_ttm", Unit::Percent),
+    ("combined_ratio_ttm", Unit::Percent),
+    ("latest_price", Unit::Trading),
+    ("revenue_next_year", Unit::Reporting),
+    ("revenue_growth_next_year", Unit::Percent),
+    ("price_current_revenue_growth", Unit::Trading),
+    ("price_current_gp_growth", Unit::Trading),
+    ("price_next_year_revenue_growth", Unit::Trading),
+    ("price_multi_year_revenue_growth", Unit::Trading),
+    ("price_multi_year_gp_growth", Unit::Trading),
+    ("pe_ratio", Unit::Multiple),
+    ("ps_ratio", Unit::Multiple),
+    ("pfcf_ratio", Unit::Multiple),
+    ("ev_ebit", Unit::Multiple),
+    ("ev_sales", Unit::Multiple),
+    ("pe_ratio_percentile", Unit::Percent),
+    ("ps_ratio_percentile", Unit::Percent),
+    // converted amounts
+    ("rate", Unit::Multiple),
+];
+
+enum Cell {
+    Empty,
+    Text(String),
+    Number(f64),
+}
+
+/// rows flattened from serialized models, ready to be written as CSV or XLSX
+pub struct ExportTable {
+    headers: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+fn column_unit(name: &str) -> Option<Unit> {
+    COLUMN_UNITS
+        .iter()
+        .find(|(field, _)| *field == name)
+        .map(|(_, unit)| *unit)
+}
+
+fn column_header(name: &str, is_converted: bool, labels: &CurrencyLabels) -> String {
+    let snake = helper::to_snake_case(name);
+    let reporting = labels.reporting.unwrap_or("reporting currency");
+    let trading = labels.trading.unwrap_or("trading currency");
+    let converted = labels.converted.unwrap_or("converted currency");
+    let unit = match column_unit(&snake) {
+        None => return snake,
+        Some(Unit::Percent) => "%".to_string(),
+        Some(Unit::Multiple) => "x".to_string(),
+        Some(Unit::Score) => "score".to_string(),
+        Some(Unit::Shares) => "shares".to_string(),
+        Some(Unit::PerShare) => format!("{} per share", reporting),
+        Some(Unit::Reporting) if is_converted => converted.to_string(),
+        Some(Unit::Trading) if is_converted => converted.to_string(),
+        Some(Unit::Reporting) => reporting.to_string(),
+        Some(Unit::Trading) => trading.to_string(),
+    };
+    format!("{} ({})", snake, unit)
+}
+
+fn to_cell(value: &Value) -> Cell {
+    match value {
+        Value::Null => Cell::Empty,
+        Value::Number(num) => num.as_f64().map(Cell::Number).unwrap_or(Cell::Empty),
+        Value::String(text) => Cell::Text(text.clone()),
+        Value::Bool(flag) => Cell::Text(flag.to_string()),
+        Value::Array(items) => Cell::Text(
+            items
+                .iter()
+                .map(|item| match item {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join(";"),
+        ),
+        Value::Object(_) => Cell::Text(value.to_string()),
+    }
+}
+
+/// flattens one level of nested objects into "parent.child" keys
+fn flatten_record(value: Value) -> Vec<(String, bool, Value)> {
+    let Value::Object(fields) = value else {
+        return Vec::new();
+    };
+    let mut flattened = Vec::new();
+    for (key, field) in fields {
+        match field {
+            Value::Object(children) => {
+                for (child_key, child) in children {
+                    flattened.push((format!("{}.{}", key, child_key), true, child));
+                }
+            }
+            other => flattened.push((key, false, other)),
+        }
+    }
+    flattened
+}
+
+impl ExportTable {
+    /// builds the table from serializable records. columns follow the field order of the model.
+    pub fn from_records<T: Serialize>(
+        records: &[T],
+        labels: &CurrencyLabels,
+    ) -> Result<Self, BullsEyeError> {
+        let mut keys: Vec<(String, bool)> = Vec::new();
+        let mut flattened_records = Vec::with_capacity(records.len());
+        for record in records {
+            let value = serde_json::to_value(record)
+                .map_err(|e| BullsEyeError::ExportError(e.to_string()))?;
+            let flattened = flatten_record(value);
+            for (key, is_converted, _) in &flattened {
+                if !keys.iter().any(|(existing, _)| existing == key) {
+                    keys.push((key.clone(), *is_converted));
+                }
+            }
+            flattened_records.push(flattened);
+        }
+        let headers = keys
+            .iter()
+            .map(|(key, is_converted)| {
+                let name = key.rsplit('.').next().unwrap_or(key);
+                let header = column_header(name, *is_converted, labels);
+                match key.split_once('.') {
+                    Some((parent, _)) => format!("{}.{}", helper::to_snake_case(parent), header),
+                    None => header,
+                }
+            })
+            .collect();
+        let rows = flattened_records
+            .into_iter()
+            .map(|flattened| {
+                keys.iter()
+                    .map(|(key, _)| {
+                        flattened
+                            .iter()
+                            .find(|(field_key, _, _)| field_key == key)
+                            .map(|(_, _, value)| to_cell(value))
+                            .unwrap_or(Cell::Empty)
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(ExportTable { headers, rows })
+    }
+
+    pub fn to_csv(&self) -> Result<Vec<u8>, BullsEyeError> {
+        let to_export_error = |e: csv::Error| BullsEyeError::ExportError(e.to_string());
+        let mut writer = csv::WriterBuilder::new()
+            .terminator(csv::Terminator::CRLF)
+            .from_writer(Vec::new());
+        writer
+            .write_record(&self.headers)
+            .map_err(to_export_error)?;
+        for row in &self.rows {
+            let fields = row.iter().map(|cell| match cell {
+                Cell::Empty => String::new(),
+                Cell::Text(text) => text.clone(),
+                Cell::Number(num) => num.to_string(),
+            });
+            writer.write_record(fields).map_err(to_export_error)?;
+        }
+        writer
+            .into_inner()
+            .map_err(|e| BullsEyeError::ExportError(e.to_string()))
+    }
+
+    pub fn to_xlsx(&self, sheet_name: &str) -> Result<Vec<u8>, BullsEyeError> {
+        let to_export_error =
+            |e: rust_xlsxwriter::XlsxError| BullsEyeError::ExportError(e.to_string());
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(sheet_name).map_err(to_export_error)?;
+        for (col, header) in self.headers.iter().enumerate() {
+            worksheet
+                .write_string(0, col as u16, header)
+                .map_err(to_export_error)?;
+        }
+        for (row_idx, row) in self.rows.iter().enumerate() {
+            let row_num = row_idx as u32 + 1;
+            for (col, cell) in row.iter().enumerate() {
+                match cell {
+                    Cell::Empty => {}
+                    Cell::Text(text) => {
+                        worksheet
+                            .write_string(row_num, col as u16, text)
+                            .map_err(to_export_error)?;
+                    }
+                    Cell::Number(num) => {
+                        worksheet
+                            .write_number(row_num, col as u16, *num)
+                            .map_err(to_export_error)?;
+                    }
+                }
+            }
+        }
+        workbook.save_to_buffer().map_err(to_export_error)
+    }
+
+    /// writes the table as a downloadable file in the given format
+    pub fn into_response(
+        self,
+        format: ExportFormat,
+        file_name: &str,
+    ) -> Result<Response, BullsEyeError> {
+        let (mime, extension, body) = match format {
+            ExportFormat::Xlsx => (XLSX_MIME, "xlsx", self.to_xlsx(file_name)?),
+            _ => ("text/csv; charset=utf-8", "csv", self.to_csv()?),
+        };
+        let disposition = format!("attachment; filename=\"{}.{}\"", file_name, extension);
+        Ok((
+            [
+                (CONTENT_TYPE, mime.to_string()),
+                (CONTENT_DISPOSITION, disposition),
+            ],
+            body,
+        )
+            .into_response())
+    }
+}
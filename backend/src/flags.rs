@@ -0,0 +1,88 @@
+use crate::models::earnings_model::EarningsReport;
+use crate::models::flags_model::RedFlag;
+use crate::models::metrics_model::CurrentMetrics;
+use std::env;
+
+/// set of red-flag checks and their thresholds.
+/// checks can be narrowed with RED_FLAG_CHECKS (comma separated flag names).
+pub struct RedFlagConfig {
+    pub enabled: Vec<RedFlag>,
+    pub max_dilution_pct: f64,
+    pub max_interest_expense_ratio: f64,
+}
+
+impl Default for RedFlagConfig {
+    fn default() -> Self {
+        RedFlagConfig {
+            enabled: RedFlag::ALL.to_vec(),
+            max_dilution_pct: 3.,
+            max_interest_expense_ratio: 0.3,
+        }
+    }
+}
+
+impl RedFlagConfig {
+    /// loads config from environment variables, falling back to the default values
+    pub fn from_env() -> Self {
+        let default_config = RedFlagConfig::default();
+        let enabled = env::var("RED_FLAG_CHECKS")
+            .map(|val| val.split(',').filter_map(RedFlag::from_name).collect())
+            .unwrap_or(default_config.enabled);
+        let max_dilution_pct = env::var("RED_FLAG_MAX_DILUTION_PCT")
+            .ok()
+            .and_then(|val| val.parse().ok())
+            .unwrap_or(default_config.max_dilution_pct);
+        let max_interest_expense_ratio = env::var("RED_FLAG_MAX_INTEREST_EXPENSE_RATIO")
+            .ok()
+            .and_then(|val| val.parse().ok())
+            .unwrap_or(default_config.max_interest_expense_ratio);
+        RedFlagConfig {
+            enabled,
+            max_dilution_pct,
+            max_interest_expense_ratio,
+        }
+    }
+}
+
+/// tells if the given check is triggered by the latest earnings and metrics
+pub fn is_flagged(
+    flag: RedFlag,
+    config: &RedFlagConfig,
+    stock_data: &EarningsReport,
+    metrics: &CurrentMetrics,
+) -> bool {
+    match flag {
+        RedFlag::UnhealthyNetCash => metrics.has_healthy_net_cash == Some(false),
+        RedFlag::Dilution => stock_data
+            .shares_change_yoy
+            .is_some_and(|change| change > config.max_dilution_pct),
+        RedFlag::HighInterestExpense => match stock_data.interest_expenses_op_income_ratio {
+            Some(ratio) => ratio > config.max_interest_expense_ratio,
+            // ratio is not calculated when operating income is not positive
+            None => {
+                stock_data.operating_income <= 0.
+                    && stock_data.interest_expenses.is_some_and(|val| val > 0.)
+            }
+        },
+        RedFlag::GoodwillImpairment => stock_data.goodwill_impairment.is_some_and(|val| val != 0.),
+        RedFlag::NegativeFreeCashFlow => stock_data.free_cash_flow.is_some_and(|val| val < 0.),
+        RedFlag::SbcExceedsFreeCashFlow => stock_data
+            .stock_based_compensation
+            .zip(stock_data.free_cash_flow)
+            .is_some_and(|(sbc, fcf)| sbc > fcf),
+    }
+}
+
+/// evaluates all enabled checks and returns triggered red flags
+pub fn evaluate_red_flags(
+    config: &RedFlagConfig,
+    stock_data: &EarningsReport,
+    metrics: &CurrentMetrics,
+) -> Vec<RedFlag> {
+    config
+        .enabled
+        .iter()
+        .copied()
+        .filter(|flag| is_flagged(*flag, config, stock_data, metrics))
+        .collect()
+}
@@ -0,0 +1,121 @@
+use crate::errors::BullsEyeError;
+use crate::models::fx_model::{FxRate, NewFxRate};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+
+/// currency used to bridge pairs without a direct rate
+const PIVOT_CURRENCY: &str = "USD";
+
+/// reference rates served by the stand-in source, quoted against USD
+const STAND_IN_RATES: [(&str, f64); 6] = [
+    ("JPY", 150.),
+    ("EUR", 0.92),
+    ("GBP", 0.79),
+    ("CAD", 1.37),
+    ("CHF", 0.88),
+    ("CNY", 7.2),
+];
+
+/// where exchange rates are loaded from at startup
+pub enum FxRateSource {
+    /// csv file with "base,quote,rate" on each line
+    Csv(String),
+    /// fixed reference rates for local development
+    StandIn,
+}
+
+impl FxRateSource {
+    /// picks the source from FX_RATES_FILE or FX_RATES_STAND_IN
+    pub fn from_env() -> Option<Self> {
+        if let Ok(path) = env::var("FX_RATES_FILE") {
+            return Some(FxRateSource::Csv(path));
+        }
+        match env::var("FX_RATES_STAND_IN").as_deref() {
+            Ok("true") | Ok("1") => Some(FxRateSource::StandIn),
+            _ => None,
+        }
+    }
+
+    pub fn load_rates(&self) -> Result<Vec<NewFxRate>, BullsEyeError> {
+        match self {
+            FxRateSource::Csv(path) => {
+                let content = fs::read_to_string(path)
+                    .map_err(|e| BullsEyeError::FxRateLoadError(format!("{}: {}", path, e)))?;
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with("base"))
+                    .map(parse_rate_line)
+                    .collect()
+            }
+            FxRateSource::StandIn => Ok(STAND_IN_RATES
+                .iter()
+                .map(|(quote, rate)| NewFxRate::create_new_entry(PIVOT_CURRENCY, quote, *rate))
+                .collect()),
+        }
+    }
+}
+
+fn parse_rate_line(line: &str) -> Result<NewFxRate, BullsEyeError> {
+    let columns: Vec<&str> = line.split(',').map(str::trim).collect();
+    match columns.as_slice() {
+        [base, quote, rate] if is_currency_code(base) && is_currency_code(quote) => {
+            let rate = rate
+                .parse::<f64>()
+                .ok()
+                .filter(|val| *val > 0.)
+                .ok_or_else(|| BullsEyeError::FxRateLoadError(line.to_string()))?;
+            Ok(NewFxRate::create_new_entry(base, quote, rate))
+        }
+        _ => Err(BullsEyeError::FxRateLoadError(line.to_string())),
+    }
+}
+
+/// tells if the given string looks like an ISO 4217 code
+pub fn is_currency_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// in-memory lookup of stored exchange rates
+pub struct FxTable {
+    rates: HashMap<(String, String), f64>,
+}
+
+impl FxTable {
+    pub fn new(stored_rates: Vec<FxRate>) -> Self {
+        let rates = stored_rates
+            .into_iter()
+            .map(|fx| ((fx.base_currency, fx.quote_currency), fx.rate))
+            .collect();
+        FxTable { rates }
+    }
+
+    fn direct_rate(&self, from: &str, to: &str) -> Option<f64> {
+        if from == to {
+            return Some(1.);
+        }
+        if let Some(rate) = self.rates.get(&(from.to_string(), to.to_string())) {
+            return Some(*rate);
+        }
+        self.rates
+            .get(&(to.to_string(), from.to_string()))
+            .map(|rate| 1. / rate)
+    }
+
+    /// rate to convert one unit of `from` into `to`, bridging through USD when needed
+    pub fn rate(&self, from: &str, to: &str) -> Option<f64> {
+        let from = from.to_uppercase();
+        let to = to.to_uppercase();
+        self.direct_rate(&from, &to).or_else(|| {
+            let to_pivot = self.direct_rate(&from, PIVOT_CURRENCY)?;
+            let from_pivot = self.direct_rate(PIVOT_CURRENCY, &to)?;
+            Some(to_pivot * from_pivot)
+        })
+    }
+
+    pub fn convert(&self, amount: Option<f64>, from: &str, to: &str) -> Option<f64> {
+        let rate = self.rate(from, to)?;
+        amount.map(|val| val * rate)
+    }
+}
@@ -0,0 +1,61 @@
+use crate::models::earnings_model::EarningsReport;
+use crate::validation;
+use std::collections::BTreeSet;
+
+/// number of TTM quarters the short-term trends are calculated from
+pub const SHORT_TERM_TREND_QUARTERS: i32 = 8;
+
+/// sorted period indices of the stored statements with the given duration
+fn stored_indices(reports: &[EarningsReport], curr_duration: &str) -> BTreeSet<i32> {
+    reports
+        .iter()
+        .filter(|report| report.duration == curr_duration)
+        .map(validation::period_index)
+        .collect()
+}
+
+fn quarter_label(idx: i32) -> String {
+    format!("Q{} {}", idx % 4 + 1, idx / 4)
+}
+
+/// quarters missing within the short-term trend window before the latest stored TTM statement.
+/// quarters before the first stored statement aren't requested as they may predate the listing.
+pub fn missing_quarters(reports: &[EarningsReport]) -> Vec<String> {
+    let stored = stored_indices(reports, "T");
+    let (Some(&first), Some(&latest)) = (stored.first(), stored.last()) else {
+        return Vec::new();
+    };
+    let window_start = first.max(latest - SHORT_TERM_TREND_QUARTERS + 1);
+    (window_start..=latest)
+        .filter(|idx| !stored.contains(idx))
+        .map(quarter_label)
+        .collect()
+}
+
+/// fiscal years missing between the first and the latest stored annual statement
+pub fn missing_fiscal_years(reports: &[EarningsReport]) -> Vec<String> {
+    let stored = stored_indices(reports, "Y");
+    let (Some(&first), Some(&latest)) = (stored.first(), stored.last()) else {
+        return Vec::new();
+    };
+    (first..=latest)
+        .filter(|year| !stored.contains(year))
+        .map(|year| format!("FY {}", year))
+        .collect()
+}
+
+/// tells if the latest TTM quarters cover the full short-term trend window without gaps
+pub fn has_short_term_history(reports: &[EarningsReport]) -> bool {
+    let stored = stored_indices(reports, "T");
+    stored.last().is_some_and(|&latest| {
+        (latest - SHORT_TERM_TREND_QUARTERS + 1..=latest).all(|idx| stored.contains(&idx))
+    })
+}
+
+/// tells if the quarter a year before the latest TTM quarter is stored, which YoY growth needs
+pub fn has_prev_year_quarter(reports: &[EarningsReport]) -> bool {
+    let stored = stored_indices(reports, "T");
+    stored
+        .last()
+        .is_some_and(|&latest| stored.contains(&(latest - 4)))
+}
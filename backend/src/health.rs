@@ -0,0 +1,134 @@
+use crate::models::earnings_model::EarningsReport;
+
+/// returns the ratio without rounding, or None if the denominator is not positive
+fn raw_ratio(value: Option<f64>, total: Option<f64>) -> Option<f64> {
+    value
+        .zip(total)
+        .filter(|(_, bottom)| *bottom > 0.)
+        .map(|(top, bottom)| top / bottom)
+}
+
+/// tells if the value improved (or declined if lower is better) between two periods.
+/// missing data never scores a point.
+fn is_improved(curr: Option<f64>, prev: Option<f64>, lower_is_better: bool) -> bool {
+    curr.zip(prev)
+        .map(|(c, p)| if lower_is_better { c <= p } else { c > p })
+        .unwrap_or(false)
+}
+
+/// latest two annual earnings if they are consecutive fiscal years, newest first
+pub fn consecutive_years(
+    annual_earnings: &[EarningsReport],
+) -> Option<(&EarningsReport, &EarningsReport)> {
+    match annual_earnings {
+        [curr, prev, ..] if curr.year_str - prev.year_str == 1 => Some((curr, prev)),
+        _ => None,
+    }
+}
+
+/// Piotroski F-score criteria and whether they are met between two consecutive annual earnings.
+/// criteria are profitability (4), leverage & liquidity (2), dilution (1) and efficiency (2).
+pub fn piotroski_criteria(
+    curr: &EarningsReport,
+    prev: &EarningsReport,
+) -> [(&'static str, bool); 9] {
+    let curr_roa = raw_ratio(Some(curr.net_income), Some(curr.total_assets));
+    let prev_roa = raw_ratio(Some(prev.net_income), Some(prev.total_assets));
+    let curr_leverage = raw_ratio(curr.total_debt, Some(curr.total_assets));
+    let prev_leverage = raw_ratio(prev.total_debt, Some(prev.total_assets));
+    let curr_current_ratio = raw_ratio(curr.total_current_assets, curr.total_current_liabilities);
+    let prev_current_ratio = raw_ratio(prev.total_current_assets, prev.total_current_liabilities);
+    let curr_turnover = raw_ratio(Some(curr.revenue), Some(curr.total_assets));
+    let prev_turnover = raw_ratio(Some(prev.revenue), Some(prev.total_assets));
+    [
+        ("positive_roa", curr_roa.is_some_and(|roa| roa > 0.)),
+        (
+            "positive_operating_cash_flow",
+            curr.operating_cash_flow.is_some_and(|ocf| ocf > 0.),
+        ),
+        ("improved_roa", is_improved(curr_roa, prev_roa, false)),
+        (
+            "cash_flow_exceeds_net_income",
+            curr.operating_cash_flow
+                .is_some_and(|ocf| ocf > curr.net_income),
+        ),
+        (
+            "lower_leverage",
+            is_improved(curr_leverage, prev_leverage, true),
+        ),
+        (
+            "improved_current_ratio",
+            is_improved(curr_current_ratio, prev_current_ratio, false),
+        ),
+        (
+            "no_dilution",
+            curr.shares_change_yoy.is_some_and(|change| change <= 0.),
+        ),
+        (
+            "improved_gross_margin",
+            is_improved(curr.gross_margin, prev.gross_margin, false),
+        ),
+        (
+            "improved_asset_turnover",
+            is_improved(curr_turnover, prev_turnover, false),
+        ),
+    ]
+}
+
+/// calculates Piotroski F-score (0-9) from two consecutive annual earnings
+pub fn calculate_piotroski_f_score(curr: &EarningsReport, prev: &EarningsReport) -> i16 {
+    piotroski_criteria(curr, prev)
+        .iter()
+        .filter(|(_, passed)| *passed)
+        .count() as i16
+}
+
+/// weighted terms of Altman Z-score with market value of equity as (name, weight, ratio).
+/// returns None when working capital or the stock price is not available.
+pub fn altman_z_score_terms(
+    stock_data: &EarningsReport,
+    price: Option<f64>,
+) -> Option<[(&'static str, f64, f64); 5]> {
+    if stock_data.total_assets <= 0. || stock_data.total_liabilities <= 0. {
+        return None;
+    }
+    let working_capital = stock_data
+        .total_current_assets
+        .zip(stock_data.total_current_liabilities)
+        .map(|(assets, liabilities)| assets - liabilities)?;
+    let market_cap = price.map(|p| p * stock_data.shares_outstanding_diluted)?;
+    let total_assets = stock_data.total_assets;
+    Some([
+        (
+            "working_capital_to_assets",
+            1.2,
+            working_capital / total_assets,
+        ),
+        (
+            "retained_earnings_to_assets",
+            1.4,
+            stock_data.retained_earnings / total_assets,
+        ),
+        (
+            "operating_income_to_assets",
+            3.3,
+            stock_data.operating_income / total_assets,
+        ),
+        (
+            "market_cap_to_liabilities",
+            0.6,
+            market_cap / stock_data.total_liabilities,
+        ),
+        ("revenue_to_assets", 1.0, stock_data.revenue / total_assets),
+    ])
+}
+
+/// calculates Altman Z-score with market value of equity.
+/// returns None when working capital or the stock price is not available.
+pub fn calculate_altman_z_score(stock_data: &EarningsReport, price: Option<f64>) -> Option<f64> {
+    let z_score: f64 = altman_z_score_terms(stock_data, price)?
+        .iter()
+        .map(|(_, weight, ratio)| weight * ratio)
+        .sum();
+    Some((z_score * 100.).round() / 100.)
+}
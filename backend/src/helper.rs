@@ -1,5 +1,6 @@
+use bullseye_api::model::Exchange;
 use chrono::format::ParseError;
-use chrono::NaiveDate;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc, Weekday};
 
 pub fn convert_date_from_string(date_str: &str) -> Result<NaiveDate, ParseError> {
     NaiveDate::parse_from_str(date_str, "%b %d, %Y")
@@ -32,3 +33,92 @@ pub fn process_fiscal_string(fiscal_str: &str) -> Option<(i16, i16)> {
     };
     Some((fiscal_y, fiscal_q))
 }
+
+/// converts exchange code (e.g. "NYSE") into the exchange enum
+pub fn parse_exchange(exchange_str: &str) -> Option<Exchange> {
+    match exchange_str.trim().to_uppercase().as_str() {
+        "NASDAQ" => Some(Exchange::NASDAQ),
+        "NYSE" => Some(Exchange::NYSE),
+        "AMEX" => Some(Exchange::AMEX),
+        "TSE" => Some(Exchange::TSE),
+        "LSE" => Some(Exchange::LSE),
+        _ => None,
+    }
+}
+
+/// converts a ticker symbol or ISIN into the upper case form listings are stored under
+pub fn normalize_ticker(ticker: &str) -> String {
+    ticker.trim().to_uppercase()
+}
+
+/// escapes the wildcards of LIKE patterns so that the text is matched literally
+pub fn escape_like_pattern(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// currency the shares are quoted in on the given exchange
+pub fn trading_currency(exchange_str: &str) -> Option<&'static str> {
+    match exchange_str.trim().to_uppercase().as_str() {
+        "NASDAQ" | "NYSE" | "AMEX" => Some("USD"),
+        "TSE" => Some("JPY"),
+        "LSE" => Some("GBP"),
+        _ => None,
+    }
+}
+
+/// time of day in UTC by which the regular session of the exchange has closed,
+/// in summer and winter time alike
+fn market_close_utc(exchange_str: &str) -> Option<NaiveTime> {
+    match exchange_str.trim().to_uppercase().as_str() {
+        "NASDAQ" | "NYSE" | "AMEX" => NaiveTime::from_hms_opt(21, 0, 0),
+        "TSE" => NaiveTime::from_hms_opt(6, 30, 0),
+        "LSE" => NaiveTime::from_hms_opt(16, 30, 0),
+        _ => None,
+    }
+}
+
+/// trading day whose session has closed at the given time.
+/// returns None on weekends and before the close, when a scraped price isn't a close.
+pub fn closed_trading_day(exchange_str: &str, now: DateTime<Utc>) -> Option<NaiveDate> {
+    let close = market_close_utc(exchange_str)?;
+    let is_weekend = matches!(now.weekday(), Weekday::Sat | Weekday::Sun);
+    (!is_weekend && now.time() >= close).then(|| now.date_naive())
+}
+
+/// converts a camelCase field name into snake_case. snake_case names are returned as they are.
+pub fn to_snake_case(name: &str) -> String {
+    let mut converted = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_uppercase() {
+            converted.push('_');
+            converted.push(c.to_ascii_lowercase());
+        } else {
+            converted.push(c);
+        }
+    }
+    converted
+}
+
+/// converts a snake_case field name into camelCase as used in the JSON responses
+pub fn to_camel_case(name: &str) -> String {
+    let mut converted = String::new();
+    let mut is_upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            is_upper = true;
+        } else if is_upper {
+            converted.push(c.to_ascii_uppercase());
+            is_upper = false;
+        } else {
+            converted.push(c);
+        }
+    }
+    converted
+}
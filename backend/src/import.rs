@@ -0,0 +1,249 @@
+//! Bulk import of statement data for companies the scraper can't reach.
+//!
+//! JSON layout: an object (or an array of objects) such as
+//! ```json
+//! {
+//!   "exchange": "NASDAQ",
+//!   "ticker": "AAPL",
+//!   "company_name": "Apple Inc.",
+//!   "industry": "Consumer Electronics",
+//!   "isin": "US0378331005",
+//!   "currency": "USD",
+//!   "statement_type": "nominal",
+//!   "ttm": [{ "term": "T", "fiscal_quarter": "Q1 2025", "period_ending": "Dec 28, 2024", ... }],
+//!   "annual": [{ "term": "Y", "fiscal_quarter": "FY 2024", "period_ending": "Sep 28, 2024", ... }]
+//! }
+//! ```
+//! where `statement_type` is one of nominal, bank, reits, other or insurance and each row holds
+//! the fields of the matching statement (`NominalStatement`, `BankStatement`, ...).
+//!
+//! CSV layout: one statement per line. The header starts with
+//! `exchange,ticker,company_name,industry,isin,currency,statement_type,period`
+//! (`period` is ttm or annual) followed by the statement field names.
+//! Empty cells are read as missing values.
+//!
+//! Price CSV layout: one daily price per line with the header
+//! `exchange,ticker,date,open,high,low,close,volume`, where `date` is YYYY-MM-DD and prices
+//! are in the trading currency, adjusted for splits like the stored share counts.
+//! Only `close` is required; the open, high, low and volume columns can be empty or left out.
+
+use crate::errors::BullsEyeError;
+use bullseye_api::model::{
+    BankStatement, Earnings, InsuranceStatement, NominalStatement, OtherStatement, ReitsStatement,
+};
+use chrono::NaiveDate;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+const METADATA_COLUMNS: [&str; 8] = [
+    "exchange",
+    "ticker",
+    "company_name",
+    "industry",
+    "isin",
+    "currency",
+    "statement_type",
+    "period",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementType {
+    Nominal,
+    Bank,
+    Reits,
+    Other,
+    Insurance,
+}
+
+impl StatementType {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "nominal" => Some(StatementType::Nominal),
+            "bank" => Some(StatementType::Bank),
+            "reits" => Some(StatementType::Reits),
+            "other" => Some(StatementType::Other),
+            "insurance" => Some(StatementType::Insurance),
+            _ => None,
+        }
+    }
+}
+
+/// statements of a single company in the documented import layout
+#[derive(Deserialize)]
+pub struct ImportBatch {
+    pub exchange: String,
+    pub ticker: String,
+    pub company_name: String,
+    pub industry: String,
+    pub isin: String,
+    pub currency: String,
+    pub statement_type: StatementType,
+    #[serde(default)]
+    pub ttm: Vec<Value>,
+    #[serde(default)]
+    pub annual: Vec<Value>,
+}
+
+fn deserialize_rows<T: DeserializeOwned>(rows: Vec<Value>) -> Result<Vec<T>, BullsEyeError> {
+    rows.into_iter()
+        .map(|row| {
+            serde_json::from_value(row).map_err(|e| BullsEyeError::ImportError(e.to_string()))
+        })
+        .collect()
+}
+
+impl ImportBatch {
+    /// converts raw rows into the earnings enum used by the scraper
+    pub fn to_earnings(&self, rows: Vec<Value>) -> Result<Earnings, BullsEyeError> {
+        let earnings = match self.statement_type {
+            StatementType::Nominal => {
+                Earnings::Nominal(deserialize_rows::<NominalStatement>(rows)?)
+            }
+            StatementType::Bank => Earnings::Bank(deserialize_rows::<BankStatement>(rows)?),
+            StatementType::Reits => Earnings::Reits(deserialize_rows::<ReitsStatement>(rows)?),
+            StatementType::Other => Earnings::Other(deserialize_rows::<OtherStatement>(rows)?),
+            StatementType::Insurance => {
+                Earnings::Insurance(deserialize_rows::<InsuranceStatement>(rows)?)
+            }
+        };
+        Ok(earnings)
+    }
+}
+
+/// parses a JSON document holding one batch or an array of batches
+pub fn parse_json(content: &str) -> Result<Vec<ImportBatch>, BullsEyeError> {
+    let value: Value =
+        serde_json::from_str(content).map_err(|e| BullsEyeError::ImportError(e.to_string()))?;
+    let batches = match value {
+        Value::Array(items) => items,
+        single => vec![single],
+    };
+    deserialize_rows(batches)
+}
+
+/// reads numbers as numbers, empty cells as missing values and everything else as text
+fn to_json_cell(cell: &str) -> Value {
+    let trimmed = cell.trim();
+    if trimmed.is_empty() {
+        return Value::Null;
+    }
+    match trimmed.parse::<f64>() {
+        Ok(num) => serde_json::Number::from_f64(num)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        Err(_) => Value::String(trimmed.to_string()),
+    }
+}
+
+/// parses CSV rows, grouping them into one batch per listing and statement type
+pub fn parse_csv(content: &str) -> Result<Vec<ImportBatch>, BullsEyeError> {
+    let to_import_error = |e: csv::Error| BullsEyeError::ImportError(e.to_string());
+    let mut reader = csv::Reader::from_reader(content.as_bytes());
+    let headers = reader.headers().map_err(to_import_error)?.clone();
+    let has_metadata = METADATA_COLUMNS
+        .iter()
+        .enumerate()
+        .all(|(idx, name)| headers.get(idx) == Some(*name));
+    if !has_metadata {
+        return Err(BullsEyeError::ImportError(format!(
+            "header must start with {}",
+            METADATA_COLUMNS.join(",")
+        )));
+    }
+    let mut batches: Vec<ImportBatch> = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(to_import_error)?;
+        let field = |idx: usize| record.get(idx).unwrap_or_default().trim().to_string();
+        let statement_type = StatementType::from_name(&field(6)).ok_or_else(|| {
+            BullsEyeError::ImportError(format!("unknown statement type: {}", field(6)))
+        })?;
+        let row: Map<String, Value> = headers
+            .iter()
+            .zip(record.iter())
+            .skip(METADATA_COLUMNS.len())
+            .map(|(name, cell)| (name.to_string(), to_json_cell(cell)))
+            .collect();
+        let position = batches.iter().position(|batch| {
+            batch.exchange == field(0)
+                && batch.ticker == field(1)
+                && batch.statement_type == statement_type
+        });
+        let batch = match position {
+            Some(idx) => &mut batches[idx],
+            None => {
+                batches.push(ImportBatch {
+                    exchange: field(0),
+                    ticker: field(1),
+                    company_name: field(2),
+                    industry: field(3),
+                    isin: field(4),
+                    currency: field(5),
+                    statement_type,
+                    ttm: Vec::new(),
+                    annual: Vec::new(),
+                });
+                batches.last_mut().unwrap()
+            }
+        };
+        match field(7).to_lowercase().as_str() {
+            "ttm" => batch.ttm.push(Value::Object(row)),
+            "annual" => batch.annual.push(Value::Object(row)),
+            other => {
+                return Err(BullsEyeError::ImportError(format!(
+                    "unknown period: {}",
+                    other
+                )))
+            }
+        }
+    }
+    Ok(batches)
+}
+
+/// reads an import file, picking the layout from its extension
+pub fn read_file(path: &str) -> Result<Vec<ImportBatch>, BullsEyeError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| BullsEyeError::ImportError(format!("{}: {}", path, e)))?;
+    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("csv") => parse_csv(&content),
+        _ => parse_json(&content),
+    }
+}
+
+/// daily price in the price CSV layout
+#[derive(Deserialize)]
+pub struct PriceRow {
+    pub exchange: String,
+    pub ticker: String,
+    pub date: NaiveDate,
+    #[serde(default)]
+    pub open: Option<f64>,
+    #[serde(default)]
+    pub high: Option<f64>,
+    #[serde(default)]
+    pub low: Option<f64>,
+    pub close: f64,
+    #[serde(default)]
+    pub volume: Option<i64>,
+}
+
+/// parses daily prices in the price CSV layout
+pub fn parse_price_csv(content: &str) -> Result<Vec<PriceRow>, BullsEyeError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+    reader
+        .deserialize()
+        .map(|row| row.map_err(|e: csv::Error| BullsEyeError::ImportError(e.to_string())))
+        .collect()
+}
+
+/// reads a price file in the price CSV layout
+pub fn read_price_file(path: &str) -> Result<Vec<PriceRow>, BullsEyeError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| BullsEyeError::ImportError(format!("{}: {}", path, e)))?;
+    parse_price_csv(&content)
+}
@@ -0,0 +1,21 @@
+pub mod backtest;
+pub mod calculate;
+pub mod db;
+pub mod errors;
+pub mod explain;
+pub mod export;
+pub mod flags;
+pub mod fx;
+pub mod gaps;
+pub mod health;
+pub mod helper;
+pub mod import;
+pub mod metrics;
+pub mod models;
+pub mod query;
+pub mod schema;
+pub mod search;
+pub mod sec;
+pub mod services;
+pub mod validation;
+pub mod valuation;
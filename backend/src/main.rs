@@ -1,78 +1,395 @@
 // use axum::http::StatusCode;
 use axum::{
     extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    extract::FromRequestParts,
     extract::Path,
+    extract::Query,
     extract::State,
-    response::Response,
-    routing::get,
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
     Json, Router,
 };
+use bullseye::{db, errors, export, helper, import, models, services};
+use bullseye_api::model::Exchange;
+use chrono::NaiveDate;
 use db::{establish_connection_pool, lookup_exchange};
 use diesel::pg::PgConnection;
 use diesel::r2d2::{ConnectionManager, Pool};
 use errors::BullsEyeError;
-use http::Method;
+use export::{CurrencyLabels, ExportFormat, ExportTable};
+use helper::{normalize_ticker, parse_exchange};
+use http::header::{AUTHORIZATION, CONTENT_TYPE};
+use http::request::Parts;
+use http::{HeaderMap, Method, Uri};
+use models::backfill_model::BackfillRequest;
+use models::companies_model::Company;
+use models::data_quality_model::DataQualityIssue;
 use models::earnings_model::EarningsReport;
+use models::flags_model::{CompanyRedFlag, RedFlag};
 use models::forecast_models::Forecasts;
+use models::industry_model::{IndustryDetail, IndustrySummary};
 use models::metrics_model::CurrentMetrics;
+use models::peers_model::PeersModel;
 use models::returning_model::ReturningModel;
+use models::search_model::SearchResult;
 use rand::Rng;
+use serde::Deserialize;
+use services::{DataGapReport, ImportSummary, MetricExplanation, PriceSeries, ValuationHistory};
+use std::collections::HashMap;
+use std::env;
 use tokio::time::{self, Duration};
 use tower_http::cors::CorsLayer;
 
-mod calculate;
-mod db;
-mod errors;
-mod helper;
-mod metrics;
-mod models;
-mod query;
-mod schema;
-mod services;
+/// listing addressed by "{symbol}" or by "{exchange}" and "{ticker}" in the request path.
+/// old symbols are answered with a redirect to the same resource of the current listing.
+struct Listing {
+    ticker: String,
+    exchange: Exchange,
+}
+
+impl FromRequestParts<Pool<ConnectionManager<PgConnection>>> for Listing {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        pool: &Pool<ConnectionManager<PgConnection>>,
+    ) -> Result<Self, Self::Rejection> {
+        let Path(params) = Path::<HashMap<String, String>>::from_request_parts(parts, pool)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let conn = &mut pool.get().unwrap();
+        let listing = match (params.get("exchange"), params.get("ticker")) {
+            (Some(exchange_str), Some(ticker)) => {
+                let exchange = parse_exchange(exchange_str).ok_or_else(|| {
+                    BullsEyeError::UnsupportedExchangeError(exchange_str.clone()).into_response()
+                })?;
+                Listing {
+                    ticker: normalize_ticker(ticker),
+                    exchange,
+                }
+            }
+            _ => {
+                let symbol = params.get("symbol").map(String::as_str).unwrap_or_default();
+                let (ticker, exchange) =
+                    lookup_exchange(symbol, conn).map_err(IntoResponse::into_response)?;
+                Listing { ticker, exchange }
+            }
+        };
+        let renamed = services::find_renamed_company(&listing.ticker, &listing.exchange, conn)
+            .map_err(IntoResponse::into_response)?;
+        match renamed {
+            Some(company) => {
+                Err(Redirect::permanent(&listing_path(&company, &parts.uri)).into_response())
+            }
+            None => Ok(listing),
+        }
+    }
+}
+
+/// builds the exchange-qualified path of the current listing,
+/// keeping the resource and query of the requested path
+fn listing_path(company: &Company, requested: &Uri) -> String {
+    let resource: String = requested
+        .path()
+        .split('/')
+        .skip_while(|segment| *segment != "companies")
+        .skip(2)
+        .flat_map(|segment| ["/", segment])
+        .collect();
+    let query = requested
+        .query()
+        .map(|query| format!("?{}", query))
+        .unwrap_or_default();
+    format!(
+        "/exchanges/{}/companies/{}{}{}",
+        company.exchange, company.ticker, resource, query
+    )
+}
+
+#[derive(Deserialize)]
+struct CompanyParams {
+    /// ISO currency code to convert absolute amounts into
+    currency: Option<String>,
+}
 
+/// refreshes stale data for the given listing and builds the response.
+/// delisted companies are no longer refreshed.
 async fn search(
     State(pool): State<Pool<ConnectionManager<PgConnection>>>,
-    Path(ticker): Path<String>,
+    listing: Listing,
+    Query(params): Query<CompanyParams>,
 ) -> Result<Json<ReturningModel>, BullsEyeError> {
-    let exchange = lookup_exchange(&ticker);
+    let currency = services::parse_currency(params.currency)?;
     let conn = &mut pool.get().unwrap();
-    let company = services::get_company(&ticker, &exchange, conn).await?;
-    let forecast = Forecasts::load_by_id(company.id, conn)?;
+    let (ticker, exchange) = (listing.ticker.as_str(), &listing.exchange);
+    let company = services::get_company(ticker, exchange, conn).await?;
+    if !company.delisted {
+        refresh_company(company.id, ticker, exchange, conn).await?;
+    }
+    let all_metrics = CurrentMetrics::load_by_id(company.id, conn)?;
+    let all_forecasts = Forecasts::load_by_id(company.id, conn)?;
+    let all_flags = CompanyRedFlag::load_by_id(company.id, conn)?;
+    let returning = ReturningModel::new(company, all_metrics, all_forecasts, all_flags);
+    let returning = match currency {
+        Some(code) => returning.with_currency(&code, &services::load_fx_table(conn)?),
+        None => returning,
+    };
+    Ok(Json(returning))
+}
+
+async fn refresh_company(
+    company_id: i32,
+    ticker: &str,
+    exchange: &Exchange,
+    conn: &mut PgConnection,
+) -> Result<(), BullsEyeError> {
+    services::update_outdated_calculations(company_id, conn)?;
+    let forecast = Forecasts::load_by_id(company_id, conn)?;
     let earnings_update_needed = forecast.is_earnings_update_needed();
     if earnings_update_needed {
-        let latest_earnings = EarningsReport::latest_quarter_data_if_existed(company.id, conn)?;
+        let latest_earnings = EarningsReport::latest_quarter_data_if_existed(company_id, conn)?;
         let all_earnings = match latest_earnings {
             Some(earnings) => earnings.quarter_str == 3,
             None => true,
         };
         if all_earnings {
-            services::update_earnings_all(company.id, &ticker, &exchange, conn).await?;
-            services::update_metrics_annual(company.id, conn)?;
+            services::update_earnings_all(company_id, ticker, exchange, conn).await?;
+            services::update_metrics_annual(company_id, conn)?;
         } else {
-            services::update_earnings_ttm(company.id, &ticker, &exchange, conn).await?;
-            services::update_metrics_ttm(company.id, conn)?;
+            services::update_earnings_ttm(company_id, ticker, exchange, conn).await?;
+            services::update_metrics_ttm(company_id, conn)?;
         }
+    } else if BackfillRequest::is_due(company_id, conn)? {
+        services::update_earnings_all(company_id, ticker, exchange, conn).await?;
+        services::update_metrics_annual(company_id, conn)?;
     } else {
         let regular_update_needed = forecast.is_regular_update_needed();
         if regular_update_needed {
-            services::update_regular(company.id, &ticker, &exchange, conn).await?;
+            services::update_regular(company_id, ticker, exchange, conn).await?;
         }
-        services::update_metrics_annual(company.id, conn)?;
+        services::update_metrics_annual(company_id, conn)?;
     }
-    let all_metrics = CurrentMetrics::load_by_id(company.id, conn)?;
-    let all_forecasts = Forecasts::load_by_id(company.id, conn)?;
-    Ok(Json(ReturningModel::new(
-        company,
-        all_metrics,
-        all_forecasts,
-    )))
+    Ok(())
 }
+
+async fn get_peers(
+    State(pool): State<Pool<ConnectionManager<PgConnection>>>,
+    listing: Listing,
+) -> Result<Json<PeersModel>, BullsEyeError> {
+    let conn = &mut pool.get().unwrap();
+    let peers = services::get_peers(&listing.ticker, &listing.exchange, conn)?;
+    Ok(Json(peers))
+}
+
+/// reports missing quarters and fiscal years along with the scheduled backfills
+async fn get_data_gaps(
+    State(pool): State<Pool<ConnectionManager<PgConnection>>>,
+    listing: Listing,
+) -> Result<Json<DataGapReport>, BullsEyeError> {
+    let conn = &mut pool.get().unwrap();
+    let report = services::get_data_gaps(&listing.ticker, &listing.exchange, conn)?;
+    Ok(Json(report))
+}
+
+/// lists stored values that disagree with the values derived from the other stored data
+async fn get_data_quality(
+    State(pool): State<Pool<ConnectionManager<PgConnection>>>,
+    listing: Listing,
+) -> Result<Json<Vec<DataQualityIssue>>, BullsEyeError> {
+    let conn = &mut pool.get().unwrap();
+    let issues = services::get_data_quality_issues(&listing.ticker, &listing.exchange, conn)?;
+    Ok(Json(issues))
+}
+
+#[derive(Deserialize)]
+struct PriceParams {
+    /// first day of the series as YYYY-MM-DD
+    from: Option<NaiveDate>,
+    /// last day of the series as YYYY-MM-DD
+    to: Option<NaiveDate>,
+}
+
+/// returns the daily price series of the listing
+async fn get_prices(
+    State(pool): State<Pool<ConnectionManager<PgConnection>>>,
+    listing: Listing,
+    Query(params): Query<PriceParams>,
+) -> Result<Json<PriceSeries>, BullsEyeError> {
+    let conn = &mut pool.get().unwrap();
+    let (ticker, exchange) = (listing.ticker.as_str(), &listing.exchange);
+    let series = services::get_price_history(ticker, exchange, params.from, params.to, conn)?;
+    Ok(Json(series))
+}
+
+/// returns the historical P/E and P/S of the listing with their bands
+async fn get_valuation(
+    State(pool): State<Pool<ConnectionManager<PgConnection>>>,
+    listing: Listing,
+) -> Result<Json<ValuationHistory>, BullsEyeError> {
+    let conn = &mut pool.get().unwrap();
+    let valuation = services::get_valuation_history(&listing.ticker, &listing.exchange, conn)?;
+    Ok(Json(valuation))
+}
+
+#[derive(Deserialize)]
+struct ExplainPath {
+    /// field of the company response to explain
+    field: String,
+}
+
+/// returns the inputs, intermediate values and thresholds behind a value of the company response
+async fn explain_metric(
+    State(pool): State<Pool<ConnectionManager<PgConnection>>>,
+    listing: Listing,
+    Path(path): Path<ExplainPath>,
+) -> Result<Json<MetricExplanation>, BullsEyeError> {
+    let conn = &mut pool.get().unwrap();
+    let (ticker, exchange) = (listing.ticker.as_str(), &listing.exchange);
+    let explanation = services::get_metric_explanation(ticker, exchange, &path.field, conn)?;
+    Ok(Json(explanation))
+}
+
+#[derive(Deserialize)]
+struct ExportParams {
+    /// json, csv or xlsx. falls back to the Accept header
+    format: Option<String>,
+}
+
+async fn get_earnings(
+    State(pool): State<Pool<ConnectionManager<PgConnection>>>,
+    listing: Listing,
+    Query(params): Query<ExportParams>,
+    headers: HeaderMap,
+) -> Result<Response, BullsEyeError> {
+    let format = ExportFormat::negotiate(params.format.as_deref(), &headers)?;
+    let conn = &mut pool.get().unwrap();
+    let (company, history) =
+        services::get_earnings_history(&listing.ticker, &listing.exchange, conn)?;
+    if format == ExportFormat::Json {
+        return Ok(Json(history).into_response());
+    }
+    let forecast = Forecasts::load_by_id(company.id, conn)?;
+    let labels = CurrencyLabels {
+        reporting: history.first().map(|earnings| earnings.currency.as_str()),
+        trading: forecast.trading_currency.as_deref(),
+        converted: None,
+    };
+    ExportTable::from_records(&history, &labels)?
+        .into_response(format, &format!("{}_earnings", company.ticker))
+}
+
+async fn list_industries(
+    State(pool): State<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<Json<Vec<IndustrySummary>>, BullsEyeError> {
+    let conn = &mut pool.get().unwrap();
+    let industries = services::get_all_industries(conn)?;
+    Ok(Json(industries))
+}
+
+async fn get_industry(
+    State(pool): State<Pool<ConnectionManager<PgConnection>>>,
+    Path(name): Path<String>,
+) -> Result<Json<IndustryDetail>, BullsEyeError> {
+    let conn = &mut pool.get().unwrap();
+    let industry = services::get_industry(&name, conn)?;
+    Ok(Json(industry))
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    /// ticker, company name or ISIN fragment
+    q: String,
+}
+
+async fn search_symbols(
+    State(pool): State<Pool<ConnectionManager<PgConnection>>>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Vec<SearchResult>>, BullsEyeError> {
+    let conn = &mut pool.get().unwrap();
+    let results = services::search_companies(&params.q, conn)?;
+    Ok(Json(results))
+}
+
+#[derive(Deserialize)]
+struct ScreenerParams {
+    /// excludes companies with any red flag
+    exclude_flagged: Option<bool>,
+    /// comma separated red flags to exclude
+    exclude_flags: Option<String>,
+    /// ISO currency code to convert absolute amounts into
+    currency: Option<String>,
+    /// json, csv or xlsx. falls back to the Accept header
+    format: Option<String>,
+}
+
 async fn list_all(
     State(pool): State<Pool<ConnectionManager<PgConnection>>>,
-) -> Result<Json<Vec<ReturningModel>>, BullsEyeError> {
+    Query(params): Query<ScreenerParams>,
+    headers: HeaderMap,
+) -> Result<Response, BullsEyeError> {
+    let format = ExportFormat::negotiate(params.format.as_deref(), &headers)?;
+    let currency = services::parse_currency(params.currency)?;
     let conn = &mut pool.get().unwrap();
-    let all_companies: Vec<ReturningModel> = services::get_all_companies(conn)?;
-    Ok(Json(all_companies))
+    let excluded_flags: Vec<RedFlag> = match (params.exclude_flagged, params.exclude_flags) {
+        (Some(true), _) => RedFlag::ALL.to_vec(),
+        (_, Some(flag_names)) => services::parse_red_flags(&flag_names)?,
+        _ => Vec::new(),
+    };
+    let all_companies: Vec<ReturningModel> =
+        services::get_all_companies(&excluded_flags, currency.as_deref(), conn)?;
+    if format == ExportFormat::Json {
+        return Ok(Json(all_companies).into_response());
+    }
+    let labels = CurrencyLabels {
+        converted: currency.as_deref(),
+        ..Default::default()
+    };
+    ExportTable::from_records(&all_companies, &labels)?.into_response(format, "screener")
+}
+
+/// compares secrets without stopping at the first differing byte,
+/// so the response time doesn't tell how much of a guessed token is right
+fn is_same_secret(given: &str, expected: &str) -> bool {
+    let (given, expected) = (given.as_bytes(), expected.as_bytes());
+    let difference = given
+        .iter()
+        .zip(expected)
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+    given.len() == expected.len() && std::hint::black_box(difference) == 0
+}
+
+/// accepts statements in the documented import layout.
+/// requires "Authorization: Bearer <ADMIN_API_TOKEN>" and is disabled when the token is unset.
+async fn import_statements(
+    State(pool): State<Pool<ConnectionManager<PgConnection>>>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<Vec<ImportSummary>>, BullsEyeError> {
+    let admin_token = env::var("ADMIN_API_TOKEN").map_err(|_| BullsEyeError::UnauthorizedError)?;
+    let is_authorized = headers
+        .get(AUTHORIZATION)
+        .and_then(|val| val.to_str().ok())
+        .and_then(|val| val.strip_prefix("Bearer "))
+        .is_some_and(|token| !admin_token.is_empty() && is_same_secret(token, &admin_token));
+    if !is_authorized {
+        return Err(BullsEyeError::UnauthorizedError);
+    }
+    let is_csv = headers
+        .get(CONTENT_TYPE)
+        .and_then(|val| val.to_str().ok())
+        .is_some_and(|val| val.starts_with("text/csv"));
+    let batches = if is_csv {
+        import::parse_csv(&body)?
+    } else {
+        import::parse_json(&body)?
+    };
+    let conn = &mut pool.get().unwrap();
+    let summaries = batches
+        .into_iter()
+        .map(|batch| services::import_statements(batch, conn))
+        .collect::<Result<Vec<ImportSummary>, BullsEyeError>>()?;
+    services::update_all_peer_ranks(conn)?;
+    Ok(Json(summaries))
 }
 
 async fn get_stock_price(ws: WebSocketUpgrade) -> Response {
@@ -108,10 +425,61 @@ async fn main() {
         .allow_origin(allowed_origins)
         .allow_methods([Method::GET]);
     let pool = establish_connection_pool().unwrap();
+    // stale or missing rates only leave converted amounts empty, so the server still starts
+    if let Err(e) = services::refresh_fx_rates(&mut pool.get().unwrap()) {
+        eprintln!("skipped refreshing FX rates: {}", e);
+    }
+    if let Err(e) = services::import_corporate_actions(&mut pool.get().unwrap()) {
+        eprintln!("skipped importing corporate actions: {}", e);
+    }
+    // companies refreshed by requests are ranked against their peers on the next start or batch
+    if let Err(e) = services::update_all_peer_ranks(&mut pool.get().unwrap()) {
+        eprintln!("skipped updating peer ranks: {}", e);
+    }
     let app = Router::new()
         .route("/screener", get(list_all))
-        .route("/companies/{ticker}", get(search))
+        .route("/search", get(search_symbols))
+        .route("/companies/{symbol}", get(search))
+        .route("/companies/{symbol}/peers", get(get_peers))
+        .route("/companies/{symbol}/earnings", get(get_earnings))
+        .route("/companies/{symbol}/gaps", get(get_data_gaps))
+        .route("/companies/{symbol}/data-quality", get(get_data_quality))
+        .route("/companies/{symbol}/prices", get(get_prices))
+        .route("/companies/{symbol}/valuation", get(get_valuation))
+        .route("/companies/{symbol}/explain/{field}", get(explain_metric))
+        .route("/exchanges/{exchange}/companies/{ticker}", get(search))
+        .route(
+            "/exchanges/{exchange}/companies/{ticker}/peers",
+            get(get_peers),
+        )
+        .route(
+            "/exchanges/{exchange}/companies/{ticker}/earnings",
+            get(get_earnings),
+        )
+        .route(
+            "/exchanges/{exchange}/companies/{ticker}/gaps",
+            get(get_data_gaps),
+        )
+        .route(
+            "/exchanges/{exchange}/companies/{ticker}/data-quality",
+            get(get_data_quality),
+        )
+        .route(
+            "/exchanges/{exchange}/companies/{ticker}/prices",
+            get(get_prices),
+        )
+        .route(
+            "/exchanges/{exchange}/companies/{ticker}/valuation",
+            get(get_valuation),
+        )
+        .route(
+            "/exchanges/{exchange}/companies/{ticker}/explain/{field}",
+            get(explain_metric),
+        )
+        .route("/industries", get(list_industries))
+        .route("/industries/{name}", get(get_industry))
         .route("/ws", get(get_stock_price))
+        .route("/admin/import", post(import_statements))
         .with_state(pool)
         .layer(cors);
 
@@ -1,8 +1,284 @@
 use crate::db;
 use crate::models::metrics_model::Trend;
 use crate::{calculate, models::earnings_model::EarningsReport};
+use serde::Serialize;
 // use chrono::NaiveDate;
 
+/// number of latest quarters voting on a short-term trend
+pub const SHORT_TERM_TREND_LENGTH: usize = 4;
+/// votes one direction needs to become the short-term trend
+pub const SHORT_TERM_TREND_VOTES: usize = 2;
+/// number of fiscal years the long-term trends are calculated from
+pub const LONG_TERM_TREND_YEARS: i64 = 6;
+/// number of fiscal years the multi-year growth rates are averaged over
+pub const MULTI_YEAR_GROWTH_YEARS: i64 = 4;
+/// days between the end of a quarter and the day its earnings are assumed to be public
+pub const REPORT_LAG_DAYS: i64 = 45;
+
+/// earnings field a trend in the metrics table is calculated from and its flat threshold
+#[derive(Clone, Copy)]
+pub struct TrendSpec {
+    pub metric: &'static str,
+    pub source: &'static str,
+    pub value: fn(&EarningsReport) -> Option<f64>,
+    pub ignore_none: bool,
+    pub flat_threshold: f64,
+}
+
+/// short-term trends in the order they are stored
+pub const SHORT_TERM_TRENDS: [TrendSpec; 8] = [
+    TrendSpec {
+        metric: "net_interest_margin_short_term_trend",
+        source: "net_interest_margin",
+        value: |f| f.net_interest_margin,
+        ignore_none: true,
+        flat_threshold: 0.5,
+    },
+    TrendSpec {
+        metric: "cost_of_risk_short_term_trend",
+        source: "cost_of_risk",
+        value: |f| f.cost_of_risk,
+        ignore_none: true,
+        flat_threshold: 0.5,
+    },
+    TrendSpec {
+        metric: "loss_ratio_short_term_trend",
+        source: "loss_ratio",
+        value: |f| f.loss_ratio,
+        ignore_none: true,
+        flat_threshold: 0.5,
+    },
+    TrendSpec {
+        metric: "combined_ratio_short_term_trend",
+        source: "combined_ratio",
+        value: |f| f.combined_ratio,
+        ignore_none: true,
+        flat_threshold: 0.5,
+    },
+    TrendSpec {
+        metric: "gross_margin_short_term_trend",
+        source: "gross_margin",
+        value: |f| f.gross_margin,
+        ignore_none: true,
+        flat_threshold: 0.5,
+    },
+    TrendSpec {
+        metric: "sga_short_term_trend",
+        source: "sga_gp_ratio",
+        value: |f| f.sga_gp_ratio,
+        ignore_none: true,
+        flat_threshold: 0.01,
+    },
+    TrendSpec {
+        metric: "rnd_short_term_trend",
+        source: "rnd_gp_ratio",
+        value: |f| f.rnd_gp_ratio,
+        ignore_none: true,
+        flat_threshold: 0.01,
+    },
+    TrendSpec {
+        metric: "operating_margin_short_term_trend",
+        source: "operating_margin",
+        value: |f| Some(f.operating_margin),
+        ignore_none: false,
+        flat_threshold: 0.5,
+    },
+];
+
+/// long-term trends in the order they are stored
+pub const LONG_TERM_TRENDS: [TrendSpec; 13] = [
+    TrendSpec {
+        metric: "net_interest_margin_long_term_trend",
+        source: "net_interest_margin",
+        value: |f| f.net_interest_margin,
+        ignore_none: false,
+        flat_threshold: 1.,
+    },
+    TrendSpec {
+        metric: "cost_of_risk_long_term_trend",
+        source: "cost_of_risk",
+        value: |f| f.cost_of_risk,
+        ignore_none: false,
+        flat_threshold: 1.,
+    },
+    TrendSpec {
+        metric: "loss_ratio_long_term_trend",
+        source: "loss_ratio",
+        value: |f| f.loss_ratio,
+        ignore_none: false,
+        flat_threshold: 1.,
+    },
+    TrendSpec {
+        metric: "combined_ratio_long_term_trend",
+        source: "combined_ratio",
+        value: |f| f.combined_ratio,
+        ignore_none: false,
+        flat_threshold: 1.,
+    },
+    TrendSpec {
+        metric: "gross_margin_long_term_trend",
+        source: "gross_margin",
+        value: |f| f.gross_margin,
+        ignore_none: false,
+        flat_threshold: 1.,
+    },
+    TrendSpec {
+        metric: "sga_long_term_trend",
+        source: "sga_gp_ratio",
+        value: |f| f.sga_gp_ratio,
+        ignore_none: false,
+        flat_threshold: 0.02,
+    },
+    TrendSpec {
+        metric: "rnd_long_term_trend",
+        source: "rnd_gp_ratio",
+        value: |f| f.rnd_gp_ratio,
+        ignore_none: false,
+        flat_threshold: 0.02,
+    },
+    TrendSpec {
+        metric: "operating_margin_long_term_trend",
+        source: "operating_margin",
+        value: |f| Some(f.operating_margin),
+        ignore_none: false,
+        flat_threshold: 1.,
+    },
+    TrendSpec {
+        metric: "shares_change_trend",
+        source: "shares_change_yoy",
+        value: |f| f.shares_change_yoy,
+        ignore_none: false,
+        flat_threshold: 2.,
+    },
+    TrendSpec {
+        metric: "retained_earnings_trend",
+        source: "retained_earnings",
+        value: |f| Some(f.retained_earnings),
+        ignore_none: false,
+        flat_threshold: 100.,
+    },
+    TrendSpec {
+        metric: "net_cash_trend",
+        source: "net_cash",
+        value: |f| Some(f.net_cash),
+        ignore_none: false,
+        flat_threshold: 100.,
+    },
+    TrendSpec {
+        metric: "operating_cash_flow_margin_trend",
+        source: "operating_cash_flow_margin",
+        value: |f| f.operating_cash_flow_margin,
+        ignore_none: false,
+        flat_threshold: 1.,
+    },
+    TrendSpec {
+        metric: "ffo_margin_trend",
+        source: "ffo_margin",
+        value: |f| f.ffo_margin,
+        ignore_none: false,
+        flat_threshold: 1.,
+    },
+];
+
+/// metrics copied from the latest TTM earnings and the earnings field they come from,
+/// in the order they are stored
+pub const LATEST_QUARTER_FIELDS: [(&str, &str, fn(&EarningsReport) -> Option<f64>); 24] = [
+    (
+        "net_interest_income_growth_yoy_ttm",
+        "net_interest_growth_yoy",
+        |f| f.net_interest_growth_yoy,
+    ),
+    ("net_interest_margin_ttm", "net_interest_margin", |f| {
+        f.net_interest_margin
+    }),
+    ("cost_of_risk_ttm", "cost_of_risk", |f| f.cost_of_risk),
+    ("premiums_growth_yoy_ttm", "premiums_growth_yoy", |f| {
+        f.premiums_growth_yoy
+    }),
+    ("loss_ratio_ttm", "loss_ratio", |f| f.loss_ratio),
+    ("combined_ratio_ttm", "combined_ratio", |f| f.combined_ratio),
+    ("revenue_ttm", "revenue", |f| Some(f.revenue)),
+    ("revenue_growth_yoy_ttm", "revenue_growth_yoy", |f| {
+        f.revenue_growth_yoy
+    }),
+    (
+        "gross_profit_growth_yoy_ttm",
+        "gross_profit_growth_yoy",
+        |f| f.gross_profit_growth_yoy,
+    ),
+    ("gross_margin_ttm", "gross_margin", |f| f.gross_margin),
+    ("sga_ratio_ttm", "sga_gp_ratio", |f| f.sga_gp_ratio),
+    ("rnd_ratio_ttm", "rnd_gp_ratio", |f| f.rnd_gp_ratio),
+    ("operating_margin_ttm", "operating_margin", |f| {
+        Some(f.operating_margin)
+    }),
+    (
+        "interest_expense_ratio_ttm",
+        "interest_expenses_op_income_ratio",
+        |f| f.interest_expenses_op_income_ratio,
+    ),
+    ("net_margin_ttm", "net_margin", |f| Some(f.net_margin)),
+    (
+        "shares_outstanding_diluted_ttm",
+        "shares_outstanding_diluted",
+        |f| Some(f.shares_outstanding_diluted),
+    ),
+    ("shares_change_ttm", "shares_change_yoy", |f| {
+        f.shares_change_yoy
+    }),
+    ("retained_earnings_ttm", "retained_earnings", |f| {
+        Some(f.retained_earnings)
+    }),
+    ("net_cash_ttm", "net_cash", |f| Some(f.net_cash)),
+    ("operating_cash_flow_ttm", "operating_cash_flow", |f| {
+        f.operating_cash_flow
+    }),
+    (
+        "operating_cash_flow_margin_ttm",
+        "operating_cash_flow_margin",
+        |f| f.operating_cash_flow_margin,
+    ),
+    ("free_cash_flow_ttm", "free_cash_flow", |f| f.free_cash_flow),
+    ("free_cash_flow_margin_ttm", "free_cash_flow_margin", |f| {
+        f.free_cash_flow_margin
+    }),
+    ("ffo_margin_ttm", "ffo_margin", |f| f.ffo_margin),
+];
+
+/// theoretical EPS in the trading currency and the figures it is derived from
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TheoreticalEps {
+    pub revenue: Option<f64>,
+    pub net_margin: Option<f64>,
+    pub net_income: Option<f64>,
+    pub shares_outstanding_diluted: Option<f64>,
+    pub fx_rate: Option<f64>,
+    pub eps: Option<f64>,
+}
+
+impl TheoreticalEps {
+    pub fn new(
+        revenue: Option<f64>,
+        net_margin: Option<f64>,
+        shares_outstanding_diluted: Option<f64>,
+        fx_rate: Option<f64>,
+    ) -> Self {
+        let net_income = calculate::calculate_margin_portion(revenue, net_margin);
+        let eps = calculate::calculate_per_share(net_income, shares_outstanding_diluted)
+            .zip(fx_rate)
+            .map(|(eps, rate)| eps * rate);
+        TheoreticalEps {
+            revenue,
+            net_margin,
+            net_income,
+            shares_outstanding_diluted,
+            fx_rate,
+            eps,
+        }
+    }
+}
+
 /// returns the oretical net margin calculated based on the current gross margin and their industry
 pub fn is_net_margin_optimized(stock_data: &EarningsReport, margin_factor: f64) -> (f64, bool) {
     let curr_gross_margin = stock_data.gross_margin.unwrap_or(100.);
@@ -21,22 +297,6 @@ pub fn has_healthy_cash_position(stock_data: &EarningsReport) -> bool {
     curr_net_cash >= 0. || (-curr_net_cash / curr_net_income < 2. && curr_net_income > 0.)
 }
 
-pub fn get_short_term_trend<F>(
-    target: &[EarningsReport],
-    field: F,
-    length: usize,
-    flat_threshold: f64,
-    count_threshold: usize,
-) -> Trend
-where
-    F: Fn(&EarningsReport) -> f64,
-{
-    let values = db::extract_field(&target, field);
-    let trend_vec = calculate::calculate_short_term_trend(&values, length, flat_threshold);
-    let short_term_trend = calculate::concat_trend(trend_vec, count_threshold);
-    short_term_trend
-}
-
 pub fn get_short_term_trend_option<F>(
     target: &[EarningsReport],
     field: F,
@@ -55,13 +315,21 @@ where
     short_term_trend
 }
 
-pub fn get_long_term_trend<F>(target: &[EarningsReport], field: F, flat_threshold: f64) -> Trend
-where
-    F: Fn(&EarningsReport) -> f64,
-{
-    let values = db::extract_field(&target, field);
-    let long_term_trend = calculate::calculate_long_term_trend(&values, flat_threshold);
-    long_term_trend
+/// outputs short-term trend of the latest TTM earnings for the given spec
+pub fn get_short_term_trend_spec(target: &[EarningsReport], spec: &TrendSpec) -> Trend {
+    get_short_term_trend_option(
+        target,
+        spec.value,
+        SHORT_TERM_TREND_LENGTH,
+        spec.ignore_none,
+        spec.flat_threshold,
+        SHORT_TERM_TREND_VOTES,
+    )
+}
+
+/// outputs long-term trend of the latest annual earnings for the given spec
+pub fn get_long_term_trend_spec(target: &[EarningsReport], spec: &TrendSpec) -> Trend {
+    get_long_term_trend_option(target, spec.value, spec.ignore_none, spec.flat_threshold)
 }
 
 /// outputs long-term trend for the given metrics
@@ -0,0 +1,117 @@
+use crate::schema::backfill_requests;
+use chrono::{Local, NaiveDate};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::Serialize;
+
+/// number of scrapes after which a period is assumed to be unavailable at the data source
+pub const MAX_BACKFILL_ATTEMPTS: i32 = 3;
+
+/// period missing from the stored statements that is requested from the data source again
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = backfill_requests)]
+#[serde(rename_all = "camelCase")]
+pub struct BackfillRequest {
+    id: i32,
+    pub company_id: i32,
+    pub period: String,
+    pub attempts: i32,
+    pub requested_on: NaiveDate,
+    pub last_attempted: Option<NaiveDate>,
+}
+
+impl BackfillRequest {
+    /// retrieves all backfill requests for the given company id
+    pub fn load_by_id(comp_id: i32, conn: &mut PgConnection) -> Result<Vec<Self>, DieselError> {
+        use crate::schema::backfill_requests::dsl::*;
+        backfill_requests
+            .filter(company_id.eq(comp_id))
+            .order(period.asc())
+            .load::<BackfillRequest>(conn)
+    }
+
+    /// tells if the given company has requests that were not attempted today
+    /// and have not used up their attempts
+    pub fn is_due(comp_id: i32, conn: &mut PgConnection) -> Result<bool, DieselError> {
+        use crate::schema::backfill_requests::dsl::*;
+        let today = Local::now().date_naive();
+        diesel::select(diesel::dsl::exists(
+            backfill_requests
+                .filter(company_id.eq(comp_id))
+                .filter(attempts.lt(MAX_BACKFILL_ATTEMPTS))
+                .filter(last_attempted.is_null().or(last_attempted.lt(today))),
+        ))
+        .get_result(conn)
+    }
+
+    /// retrieves ids of all companies with due backfill requests
+    pub fn load_due_company_ids(conn: &mut PgConnection) -> Result<Vec<i32>, DieselError> {
+        use crate::schema::backfill_requests::dsl::*;
+        let today = Local::now().date_naive();
+        backfill_requests
+            .filter(attempts.lt(MAX_BACKFILL_ATTEMPTS))
+            .filter(last_attempted.is_null().or(last_attempted.lt(today)))
+            .select(company_id)
+            .distinct()
+            .load::<i32>(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = backfill_requests)]
+pub struct NewBackfillRequest<'a> {
+    company_id: i32,
+    period: &'a str,
+    requested_on: NaiveDate,
+}
+
+impl<'a> NewBackfillRequest<'a> {
+    pub fn create_new_entry(company_id: i32, period: &'a str) -> Self {
+        NewBackfillRequest {
+            company_id,
+            period,
+            requested_on: Local::now().date_naive(),
+        }
+    }
+}
+
+/// syncs backfill requests of the given company with the currently missing periods.
+/// filled periods are dropped and, when the full history was just scraped,
+/// the remaining requests count it as an attempt.
+pub fn replace_backfill_requests(
+    comp_id: i32,
+    missing_periods: &[String],
+    is_attempted: bool,
+    conn: &mut PgConnection,
+) -> Result<(), DieselError> {
+    use crate::schema::backfill_requests::dsl::*;
+    let entries: Vec<NewBackfillRequest> = missing_periods
+        .iter()
+        .map(|curr_period| NewBackfillRequest::create_new_entry(comp_id, curr_period))
+        .collect();
+    conn.transaction(|conn| {
+        diesel::delete(
+            backfill_requests
+                .filter(company_id.eq(comp_id))
+                .filter(period.ne_all(missing_periods.to_vec())),
+        )
+        .execute(conn)?;
+        diesel::insert_into(backfill_requests)
+            .values(&entries)
+            .on_conflict((company_id, period))
+            .do_nothing()
+            .execute(conn)?;
+        // periods found missing right after a full scrape are not due again until tomorrow
+        if is_attempted {
+            diesel::update(backfill_requests.filter(company_id.eq(comp_id)))
+                .set((
+                    attempts.eq(attempts + 1),
+                    last_attempted.eq(Local::now().date_naive()),
+                ))
+                .execute(conn)?;
+        }
+        Ok(())
+    })
+}
@@ -1,3 +1,4 @@
+use crate::helper;
 use crate::query;
 use crate::schema::companies;
 use bullseye_api::model::get_exchange_string;
@@ -7,8 +8,17 @@ use chrono::{Duration, Local, NaiveDate};
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel::result::Error as DieselError;
+use diesel::sql_types::{Float4, Text};
 use serde::Serialize;
 
+/// trigram similarity from which a ticker or name is considered a typo of the search query
+const TYPO_SIMILARITY: f32 = 0.3;
+
+define_sql_function! {
+    /// greatest trigram similarity between the first string and any part of the second (pg_trgm)
+    fn word_similarity(query: Text, target: Text) -> Float4;
+}
+
 #[derive(Queryable, Selectable, Serialize)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[diesel(table_name = companies)]
@@ -21,6 +31,10 @@ pub struct Company {
     pub exchange: String,
     pub ticker: String,
     last_updated: NaiveDate,
+    pub delisted: bool,
+    /// days on which the ticker failed to resolve since it last resolved
+    ticker_failures: i32,
+    last_ticker_failure: Option<NaiveDate>,
 }
 impl Company {
     /// loads campany data if existed
@@ -34,6 +48,44 @@ impl Company {
             query::load_first_row(companies.filter(isin.eq(curr_isin)), conn).optional()?;
         Ok(target)
     }
+    /// loads campany data by id
+    pub fn load_by_id(comp_id: i32, conn: &mut PgConnection) -> Result<Self, DieselError> {
+        use crate::schema::companies::dsl::*;
+        query::load_first_row(companies.filter(id.eq(comp_id)), conn)
+    }
+    /// loads all campany data
+    pub fn load_all(conn: &mut PgConnection) -> Result<Vec<Self>, DieselError> {
+        use crate::schema::companies::dsl::*;
+        companies.load::<Company>(conn)
+    }
+    /// loads the distinct industries of the stored companies
+    pub fn load_all_industries(conn: &mut PgConnection) -> Result<Vec<String>, DieselError> {
+        use crate::schema::companies::dsl::*;
+        companies
+            .select(industry)
+            .distinct()
+            .order(industry.asc())
+            .load::<String>(conn)
+    }
+    /// loads companies whose ticker, ISIN or name contains the search query,
+    /// or whose ticker or name is similar enough to it to be a typo
+    pub fn load_search_candidates(
+        search_query: &str,
+        conn: &mut PgConnection,
+    ) -> Result<Vec<Self>, DieselError> {
+        use crate::schema::companies::dsl::*;
+        let pattern = format!("%{}%", helper::escape_like_pattern(search_query));
+        companies
+            .filter(
+                ticker
+                    .ilike(&pattern)
+                    .or(isin.ilike(&pattern))
+                    .or(company_name.ilike(&pattern))
+                    .or(word_similarity(search_query, ticker).ge(TYPO_SIMILARITY))
+                    .or(word_similarity(search_query, company_name).ge(TYPO_SIMILARITY)),
+            )
+            .load::<Company>(conn)
+    }
     /// loads campany data by ticker symbol if existed
     pub fn load_by_ticker_if_existed(
         curr_ticker: &str,
@@ -50,6 +102,10 @@ impl Company {
         .optional()?;
         Ok(target)
     }
+    /// checks whether the given listing differs from the current one
+    pub fn is_listed_as(&self, curr_ticker: &str, curr_exchange: &str) -> bool {
+        self.ticker.eq_ignore_ascii_case(curr_ticker) && self.exchange == curr_exchange
+    }
     pub fn ticker_check_needed(&self) -> bool {
         Local::now().date_naive() - self.last_updated >= Duration::days(90)
     }
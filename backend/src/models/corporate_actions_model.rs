@@ -0,0 +1,125 @@
+use crate::schema::corporate_actions;
+use chrono::NaiveDate;
+use diesel::deserialize::{FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgConnection, PgValue};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::serialize::{Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::upsert::excluded;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum CorporateActionType {
+    Split,
+    ReverseSplit,
+}
+
+impl CorporateActionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CorporateActionType::Split => "split",
+            CorporateActionType::ReverseSplit => "reverse_split",
+        }
+    }
+
+    /// parses action name used in the database and import files
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "split" => Some(CorporateActionType::Split),
+            "reverse_split" => Some(CorporateActionType::ReverseSplit),
+            _ => None,
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for CorporateActionType
+where
+    str: ToSql<Text, Pg>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> diesel::serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for CorporateActionType {
+    fn from_sql(bytes: PgValue<'_>) -> diesel::deserialize::Result<Self> {
+        let s = std::str::from_utf8(bytes.as_bytes())?;
+        CorporateActionType::from_name(s)
+            .ok_or_else(|| format!("Invalid corporate action detected: {}", s).into())
+    }
+}
+
+/// split or reverse split. ratio is always given as the larger side,
+/// e.g. 10 for both a 10:1 split and a 1:10 reverse split.
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = corporate_actions)]
+#[serde(rename_all = "camelCase")]
+pub struct CorporateAction {
+    id: i32,
+    pub company_id: i32,
+    pub action_type: CorporateActionType,
+    pub ratio: f64,
+    pub effective_date: NaiveDate,
+}
+
+impl CorporateAction {
+    /// retrieves all corporate actions for the given company id
+    pub fn load_by_id(comp_id: i32, conn: &mut PgConnection) -> Result<Vec<Self>, DieselError> {
+        use crate::schema::corporate_actions::dsl::*;
+        corporate_actions
+            .filter(company_id.eq(comp_id))
+            .order(effective_date.asc())
+            .load::<CorporateAction>(conn)
+    }
+
+    /// number of shares after the action for each share before it
+    pub fn share_factor(&self) -> f64 {
+        match self.action_type {
+            CorporateActionType::Split => self.ratio,
+            CorporateActionType::ReverseSplit => 1. / self.ratio,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = corporate_actions)]
+pub struct NewCorporateAction {
+    company_id: i32,
+    action_type: CorporateActionType,
+    ratio: f64,
+    effective_date: NaiveDate,
+}
+
+impl NewCorporateAction {
+    pub fn create_new_entry(
+        company_id: i32,
+        action_type: CorporateActionType,
+        ratio: f64,
+        effective_date: NaiveDate,
+    ) -> Self {
+        NewCorporateAction {
+            company_id,
+            action_type,
+            ratio,
+            effective_date,
+        }
+    }
+    pub fn upsert(&self, conn: &mut PgConnection) -> Result<(), DieselError> {
+        use crate::schema::corporate_actions::dsl::*;
+        diesel::insert_into(corporate_actions)
+            .values(self)
+            .on_conflict((company_id, effective_date))
+            .do_update()
+            .set((
+                action_type.eq(excluded(action_type)),
+                ratio.eq(excluded(ratio)),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+}
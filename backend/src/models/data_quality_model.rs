@@ -0,0 +1,94 @@
+use crate::schema::data_quality_issues;
+use chrono::{Local, NaiveDate};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::upsert::excluded;
+use serde::Serialize;
+
+/// stored value that disagrees with the value derived from other stored data
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = data_quality_issues)]
+#[serde(rename_all = "camelCase")]
+pub struct DataQualityIssue {
+    id: i32,
+    pub company_id: i32,
+    pub earnings_id: i32,
+    pub field: String,
+    pub computed_value: Option<f64>,
+    pub reported_value: Option<f64>,
+    pub detected_on: NaiveDate,
+}
+
+impl DataQualityIssue {
+    /// retrieves all open issues for the given company id
+    pub fn load_by_id(comp_id: i32, conn: &mut PgConnection) -> Result<Vec<Self>, DieselError> {
+        use crate::schema::data_quality_issues::dsl::*;
+        data_quality_issues
+            .filter(company_id.eq(comp_id))
+            .order((earnings_id.asc(), field.asc()))
+            .load::<DataQualityIssue>(conn)
+    }
+
+    /// removes the issue once the given field is consistent again
+    pub fn resolve(
+        curr_earnings_id: i32,
+        curr_field: &str,
+        conn: &mut PgConnection,
+    ) -> Result<(), DieselError> {
+        use crate::schema::data_quality_issues::dsl::*;
+        diesel::delete(
+            data_quality_issues
+                .filter(earnings_id.eq(curr_earnings_id))
+                .filter(field.eq(curr_field)),
+        )
+        .execute(conn)?;
+        Ok(())
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = data_quality_issues)]
+pub struct NewDataQualityIssue<'a> {
+    company_id: i32,
+    earnings_id: i32,
+    field: &'a str,
+    computed_value: Option<f64>,
+    reported_value: Option<f64>,
+    detected_on: NaiveDate,
+}
+
+impl<'a> NewDataQualityIssue<'a> {
+    pub fn create_new_entry(
+        company_id: i32,
+        earnings_id: i32,
+        field: &'a str,
+        computed_value: Option<f64>,
+        reported_value: Option<f64>,
+    ) -> Self {
+        NewDataQualityIssue {
+            company_id,
+            earnings_id,
+            field,
+            computed_value,
+            reported_value,
+            detected_on: Local::now().date_naive(),
+        }
+    }
+    /// records the issue or refreshes the values of an already recorded one
+    pub fn upsert(&self, conn: &mut PgConnection) -> Result<(), DieselError> {
+        use crate::schema::data_quality_issues::dsl::*;
+        diesel::insert_into(data_quality_issues)
+            .values(self)
+            .on_conflict((earnings_id, field))
+            .do_update()
+            .set((
+                computed_value.eq(excluded(computed_value)),
+                reported_value.eq(excluded(reported_value)),
+                detected_on.eq(excluded(detected_on)),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+}
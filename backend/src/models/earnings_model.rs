@@ -1,9 +1,12 @@
 use crate::calculate;
 use crate::helper;
+use crate::models::data_quality_model::{DataQualityIssue, NewDataQualityIssue};
 use crate::query;
 use crate::schema::earnings_report;
+use crate::sec::SecStatement;
 use bullseye_api::model::BankStatement;
 use bullseye_api::model::Earnings;
+use bullseye_api::model::InsuranceStatement;
 use bullseye_api::model::NominalStatement;
 use bullseye_api::model::OtherStatement;
 use bullseye_api::model::ReitsStatement;
@@ -13,6 +16,9 @@ use diesel::prelude::*;
 use diesel::result::Error as DieselError;
 use serde::{Deserialize, Serialize};
 
+/// allowed gap in percentage points between computed and scraped growth rates
+const GROWTH_TOLERANCE_PCT: f64 = 1.;
+
 #[derive(Queryable, Selectable, Serialize)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[diesel(table_name = earnings_report)]
@@ -44,14 +50,14 @@ pub struct EarningsReport {
     pub operating_margin: f64,
     pub interest_expenses: Option<f64>,
     pub interest_expenses_op_income_ratio: Option<f64>,
-    pub goodwill_impairment: f64,
+    pub goodwill_impairment: Option<f64>,
     pub net_income: f64,
     pub net_margin: f64,
     pub eps_basic: f64,
     pub eps_diluted: f64,
     pub shares_outstanding_basic: f64,
     pub shares_outstanding_diluted: f64,
-    pub shares_change_yoy: f64,
+    pub shares_change_yoy: Option<f64>,
     pub ffo: Option<f64>,
     pub ffo_margin: Option<f64>,
     pub cash_and_equivalents: f64,
@@ -81,6 +87,17 @@ pub struct EarningsReport {
     pub free_cash_flow_margin: Option<f64>,
     pub ratio_calculated: bool,
     pub growth_calculated: bool,
+    pub premiums_earned: Option<f64>,
+    pub premiums_growth_yoy: Option<f64>,
+    pub policy_benefits: Option<f64>,
+    pub underwriting_expenses: Option<f64>,
+    pub loss_ratio: Option<f64>,
+    pub combined_ratio: Option<f64>,
+    pub split_factor: f64,
+    #[serde(skip_serializing)]
+    pub calculation_version: i32,
+    pub reported_revenue_growth_yoy: Option<f64>,
+    pub reported_shares_change_yoy: Option<f64>,
 }
 impl EarningsReport {
     /// retrieves the lastest quarterly(TTM) earnings data for the given ticker
@@ -122,26 +139,23 @@ impl EarningsReport {
         )
     }
 
-    /// retrieves the same quarter earnings data from the prvious year for the given ticker
-    pub fn same_quarter_prev_year_data(
-        &self,
-        conn: &mut PgConnection,
-    ) -> Result<Option<Self>, DieselError> {
+    /// retrieves every earnings data (TTM & Annual) for the given ticker
+    pub fn load_all_by_id(comp_id: i32, conn: &mut PgConnection) -> Result<Vec<Self>, DieselError> {
         use crate::schema::earnings_report::dsl::*;
-        let prev_year_result = query::load_first_row(
+        earnings_report
+            .filter(company_id.eq(comp_id))
+            .load::<EarningsReport>(conn)
+    }
+
+    /// tells if any earnings data of the given company was derived with older formulas
+    pub fn is_outdated(comp_id: i32, conn: &mut PgConnection) -> Result<bool, DieselError> {
+        use crate::schema::earnings_report::dsl::*;
+        diesel::select(diesel::dsl::exists(
             earnings_report
-                .filter(company_id.eq(&self.company_id))
-                .filter(duration.eq(&self.duration))
-                .filter(year_str.eq(self.year_str - 1))
-                .filter(quarter_str.eq(self.quarter_str)),
-            conn,
-        );
-        let prev_year = match prev_year_result {
-            Ok(data) => Ok(Some(data)),
-            Err(DieselError::NotFound) => Ok(None),
-            Err(e) => Err(e),
-        };
-        prev_year
+                .filter(company_id.eq(comp_id))
+                .filter(calculation_version.lt(calculate::CALCULATION_VERSION)),
+        ))
+        .get_result(conn)
     }
 
     /// updates missing ratios and margins for the selected earnings
@@ -160,6 +174,14 @@ impl EarningsReport {
             self.provision_for_loan_loss,
             self.gross_loans,
         );
+        let loss =
+            calculate::calculate_ratio_as_pct_option(self.policy_benefits, self.premiums_earned);
+        let underwriting_losses = self
+            .policy_benefits
+            .zip(self.underwriting_expenses)
+            .map(|(benefits, expenses)| benefits + expenses);
+        let combined =
+            calculate::calculate_ratio_as_pct_option(underwriting_losses, self.premiums_earned);
         let sga_ratio = calculate::calculate_ratio_option(self.sga_expenses, self.gross_profit);
         let rnd_ratio = calculate::calculate_ratio_option(self.rnd_expenses, self.gross_profit);
         let interest_ratio =
@@ -173,6 +195,8 @@ impl EarningsReport {
             (
                 net_interest_margin.eq(nim),
                 cost_of_risk.eq(cor),
+                loss_ratio.eq(loss),
+                combined_ratio.eq(combined),
                 sga_gp_ratio.eq(sga_ratio),
                 rnd_gp_ratio.eq(rnd_ratio),
                 interest_expenses_op_income_ratio.eq(interest_ratio),
@@ -187,11 +211,24 @@ impl EarningsReport {
         Ok(())
     }
 
+    /// finds the same quarter earnings data from the previous year among the given earnings
+    pub fn find_prev_year_data<'b>(&self, reports: &'b [EarningsReport]) -> Option<&'b Self> {
+        reports.iter().find(|report| {
+            report.company_id == self.company_id
+                && report.duration == self.duration
+                && report.year_str == self.year_str - 1
+                && report.quarter_str == self.quarter_str
+        })
+    }
+
     /// updates missing growth rate for the selected earnings
-    pub fn update_yoy_growth(&self, conn: &mut PgConnection) -> Result<(), DieselError> {
+    pub fn update_yoy_growth(
+        &self,
+        prev_year_data: Option<&EarningsReport>,
+        conn: &mut PgConnection,
+    ) -> Result<(), DieselError> {
         use crate::schema::earnings_report::dsl::*;
         let curr_id = self.id;
-        let prev_year_data = self.same_quarter_prev_year_data(conn)?;
         let prev_gross_profit = prev_year_data
             .as_ref()
             .map(|data| data.gross_profit)
@@ -200,26 +237,109 @@ impl EarningsReport {
             .as_ref()
             .map(|data| data.net_interest_income)
             .flatten();
+        let prev_premiums = prev_year_data
+            .as_ref()
+            .map(|data| data.premiums_earned)
+            .flatten();
         let gp_growth =
             calculate::calculate_yoy_growth_option(self.gross_profit, prev_gross_profit);
+        let premiums_growth =
+            calculate::calculate_yoy_growth_option(self.premiums_earned, prev_premiums);
         let net_interest_income_growth = calculate::calculate_yoy_growth_option(
             self.net_interest_income,
             prev_net_interest_income,
         );
+        let revenue_growth = calculate::calculate_yoy_growth_option(
+            Some(self.revenue),
+            prev_year_data.map(|data| data.revenue),
+        );
+        self.check_reported_growth(
+            "revenue_growth_yoy",
+            revenue_growth,
+            self.reported_revenue_growth_yoy,
+            conn,
+        )?;
+        let shares_change = calculate::calculate_yoy_growth_option(
+            Some(self.shares_outstanding_diluted),
+            prev_year_data.map(|data| data.shares_outstanding_diluted),
+        );
+        self.check_reported_growth(
+            "shares_change_yoy",
+            shares_change,
+            self.reported_shares_change_yoy,
+            conn,
+        )?;
+        // the reported change is kept only when the previous year isn't stored
+        let shares_change = shares_change
+            .or(self.reported_shares_change_yoy)
+            .or(self.shares_change_yoy);
         query::update_earnings_table(
             curr_id,
             (
                 net_interest_growth_yoy.eq(net_interest_income_growth),
+                revenue_growth_yoy.eq(revenue_growth),
+                shares_change_yoy.eq(shares_change),
                 gross_profit_growth_yoy.eq(gp_growth),
+                premiums_growth_yoy.eq(premiums_growth),
                 growth_calculated.eq(true),
             ),
             conn,
         )?;
         Ok(())
     }
+
+    /// logs a data-quality issue when the scraped growth deviates from the computed one
+    fn check_reported_growth(
+        &self,
+        field_name: &str,
+        computed: Option<f64>,
+        reported: Option<f64>,
+        conn: &mut PgConnection,
+    ) -> Result<(), DieselError> {
+        let is_deviated = match (computed, reported) {
+            (Some(computed_val), Some(reported_val)) => {
+                (computed_val - reported_val).abs() > GROWTH_TOLERANCE_PCT
+            }
+            _ => false,
+        };
+        if is_deviated {
+            NewDataQualityIssue::create_new_entry(
+                self.company_id,
+                self.id,
+                field_name,
+                computed,
+                reported,
+            )
+            .upsert(conn)
+        } else {
+            DataQualityIssue::resolve(self.id, field_name, conn)
+        }
+    }
+
+    /// restates share counts and EPS with the given cumulative split factor
+    pub fn update_split_factor(
+        &self,
+        factor: f64,
+        conn: &mut PgConnection,
+    ) -> Result<(), DieselError> {
+        use crate::schema::earnings_report::dsl::*;
+        let adjustment = factor / self.split_factor;
+        query::update_earnings_table(
+            self.id,
+            (
+                shares_outstanding_basic.eq(self.shares_outstanding_basic * adjustment),
+                shares_outstanding_diluted.eq(self.shares_outstanding_diluted * adjustment),
+                eps_basic.eq(self.eps_basic / adjustment),
+                eps_diluted.eq(self.eps_diluted / adjustment),
+                split_factor.eq(factor),
+            ),
+            conn,
+        )?;
+        Ok(())
+    }
 }
 
-#[derive(Deserialize, Insertable)]
+#[derive(Deserialize, Insertable, AsChangeset)]
 #[diesel(table_name = earnings_report)]
 pub struct NewEarningsReport<'a> {
     company_id: i32,
@@ -248,14 +368,14 @@ pub struct NewEarningsReport<'a> {
     operating_margin: f64,
     interest_expenses: Option<f64>,
     interest_expenses_op_income_ratio: Option<f64>,
-    goodwill_impairment: f64,
+    goodwill_impairment: Option<f64>,
     net_income: f64,
     net_margin: f64,
     eps_basic: f64,
     eps_diluted: f64,
     shares_outstanding_basic: f64,
     shares_outstanding_diluted: f64,
-    shares_change_yoy: f64,
+    shares_change_yoy: Option<f64>,
     ffo: Option<f64>,
     ffo_margin: Option<f64>,
     cash_and_equivalents: f64,
@@ -285,16 +405,32 @@ pub struct NewEarningsReport<'a> {
     free_cash_flow_margin: Option<f64>,
     ratio_calculated: bool,
     growth_calculated: bool,
+    premiums_earned: Option<f64>,
+    premiums_growth_yoy: Option<f64>,
+    policy_benefits: Option<f64>,
+    underwriting_expenses: Option<f64>,
+    loss_ratio: Option<f64>,
+    combined_ratio: Option<f64>,
+    reported_revenue_growth_yoy: Option<f64>,
+    reported_shares_change_yoy: Option<f64>,
 }
 
 impl<'a> NewEarningsReport<'a> {
-    /// adds new earnings data
-    pub fn create_new_entry(comp_id: i32, currency: &'a str, earnings_enum: Earnings) -> Vec<Self> {
+    /// adds new earnings data.
+    /// statements whose period can't be parsed are left out and returned by their fiscal quarter.
+    pub fn create_new_entry(
+        comp_id: i32,
+        currency: &'a str,
+        earnings_enum: Earnings,
+    ) -> (Vec<Self>, Vec<String>) {
+        let mut rejected_periods: Vec<String> = Vec::new();
         let statement: Vec<NewEarningsReport> = match earnings_enum {
             Earnings::Nominal(val_vec) => val_vec
                 .into_iter()
                 .filter_map(|val| {
+                    let fiscal_quarter = val.fiscal_quarter.clone();
                     NewEarningsReport::from_nominal(comp_id, currency, val)
+                        .map_err(|_| rejected_periods.push(fiscal_quarter))
                         .ok()
                         .flatten()
                 })
@@ -302,7 +438,9 @@ impl<'a> NewEarningsReport<'a> {
             Earnings::Bank(val_vec) => val_vec
                 .into_iter()
                 .filter_map(|val| {
+                    let fiscal_quarter = val.fiscal_quarter.clone();
                     NewEarningsReport::from_bank(comp_id, currency, val)
+                        .map_err(|_| rejected_periods.push(fiscal_quarter))
                         .ok()
                         .flatten()
                 })
@@ -310,7 +448,9 @@ impl<'a> NewEarningsReport<'a> {
             Earnings::Reits(val_vec) => val_vec
                 .into_iter()
                 .filter_map(|val| {
+                    let fiscal_quarter = val.fiscal_quarter.clone();
                     NewEarningsReport::from_reits(comp_id, currency, val)
+                        .map_err(|_| rejected_periods.push(fiscal_quarter))
                         .ok()
                         .flatten()
                 })
@@ -318,13 +458,25 @@ impl<'a> NewEarningsReport<'a> {
             Earnings::Other(val_vec) => val_vec
                 .into_iter()
                 .filter_map(|val| {
+                    let fiscal_quarter = val.fiscal_quarter.clone();
                     NewEarningsReport::from_other(comp_id, currency, val)
+                        .map_err(|_| rejected_periods.push(fiscal_quarter))
+                        .ok()
+                        .flatten()
+                })
+                .collect(),
+            Earnings::Insurance(val_vec) => val_vec
+                .into_iter()
+                .filter_map(|val| {
+                    let fiscal_quarter = val.fiscal_quarter.clone();
+                    NewEarningsReport::from_insurance(comp_id, currency, val)
+                        .map_err(|_| rejected_periods.push(fiscal_quarter))
                         .ok()
                         .flatten()
                 })
                 .collect(),
         };
-        statement
+        (statement, rejected_periods)
     }
     fn from_nominal(
         comp_id: i32,
@@ -347,7 +499,7 @@ impl<'a> NewEarningsReport<'a> {
                 provision_for_loan_loss: None,
                 cost_of_risk: None,
                 revenue: nominal_statement.revenue,
-                revenue_growth_yoy: Some(nominal_statement.revenue_growth_yoy),
+                revenue_growth_yoy: None,
                 cost_of_revenue: Some(nominal_statement.cost_of_revenue),
                 gross_profit: Some(nominal_statement.gross_profit),
                 gross_margin: Some(nominal_statement.gross_margin),
@@ -361,14 +513,14 @@ impl<'a> NewEarningsReport<'a> {
                 operating_margin: nominal_statement.operating_margin,
                 interest_expenses: Some(nominal_statement.interest_expenses),
                 interest_expenses_op_income_ratio: None,
-                goodwill_impairment: nominal_statement.goodwill_impairment,
+                goodwill_impairment: Some(nominal_statement.goodwill_impairment),
                 net_income: nominal_statement.net_income,
                 net_margin: nominal_statement.net_margin,
                 eps_basic: nominal_statement.eps_basic,
                 eps_diluted: nominal_statement.eps_diluted,
                 shares_outstanding_basic: nominal_statement.shares_outstanding_basic,
                 shares_outstanding_diluted: nominal_statement.shares_outstanding_diluted,
-                shares_change_yoy: nominal_statement.shares_change_yoy,
+                shares_change_yoy: Some(nominal_statement.shares_change_yoy),
                 ffo: None,
                 ffo_margin: None,
                 cash_and_equivalents: nominal_statement.cash_and_equivalents,
@@ -402,6 +554,14 @@ impl<'a> NewEarningsReport<'a> {
                 free_cash_flow_margin: Some(nominal_statement.free_cash_flow_margin),
                 ratio_calculated: false,
                 growth_calculated: false,
+                premiums_earned: None,
+                premiums_growth_yoy: None,
+                policy_benefits: None,
+                underwriting_expenses: None,
+                loss_ratio: None,
+                combined_ratio: None,
+                reported_revenue_growth_yoy: Some(nominal_statement.revenue_growth_yoy),
+                reported_shares_change_yoy: Some(nominal_statement.shares_change_yoy),
             }))
         } else {
             Ok(None)
@@ -428,7 +588,7 @@ impl<'a> NewEarningsReport<'a> {
                 provision_for_loan_loss: Some(bank_statement.provision_for_loan_loss),
                 cost_of_risk: None,
                 revenue: bank_statement.revenue,
-                revenue_growth_yoy: Some(bank_statement.revenue_growth_yoy),
+                revenue_growth_yoy: None,
                 cost_of_revenue: None,
                 gross_profit: None,
                 gross_margin: None,
@@ -442,14 +602,14 @@ impl<'a> NewEarningsReport<'a> {
                 operating_margin: bank_statement.adjusted_operating_margin,
                 interest_expenses: None,
                 interest_expenses_op_income_ratio: None,
-                goodwill_impairment: bank_statement.goodwill_impairment,
+                goodwill_impairment: Some(bank_statement.goodwill_impairment),
                 net_income: bank_statement.net_income,
                 net_margin: bank_statement.net_margin,
                 eps_basic: bank_statement.eps_basic,
                 eps_diluted: bank_statement.eps_diluted,
                 shares_outstanding_basic: bank_statement.shares_outstanding_basic,
                 shares_outstanding_diluted: bank_statement.shares_outstanding_diluted,
-                shares_change_yoy: bank_statement.shares_change_yoy,
+                shares_change_yoy: Some(bank_statement.shares_change_yoy),
                 ffo: None,
                 ffo_margin: None,
                 cash_and_equivalents: bank_statement.cash_and_equivalents,
@@ -479,6 +639,14 @@ impl<'a> NewEarningsReport<'a> {
                 free_cash_flow_margin: None,
                 ratio_calculated: false,
                 growth_calculated: false,
+                premiums_earned: None,
+                premiums_growth_yoy: None,
+                policy_benefits: None,
+                underwriting_expenses: None,
+                loss_ratio: None,
+                combined_ratio: None,
+                reported_revenue_growth_yoy: Some(bank_statement.revenue_growth_yoy),
+                reported_shares_change_yoy: Some(bank_statement.shares_change_yoy),
             }))
         } else {
             Ok(None)
@@ -505,7 +673,7 @@ impl<'a> NewEarningsReport<'a> {
                 provision_for_loan_loss: None,
                 cost_of_risk: None,
                 revenue: reits_statement.revenue,
-                revenue_growth_yoy: Some(reits_statement.revenue_growth_yoy),
+                revenue_growth_yoy: None,
                 cost_of_revenue: None,
                 gross_profit: None,
                 gross_margin: None,
@@ -519,14 +687,14 @@ impl<'a> NewEarningsReport<'a> {
                 operating_margin: reits_statement.operating_margin,
                 interest_expenses: Some(reits_statement.interest_expenses),
                 interest_expenses_op_income_ratio: None,
-                goodwill_impairment: reits_statement.goodwill_impairment,
+                goodwill_impairment: Some(reits_statement.goodwill_impairment),
                 net_income: reits_statement.net_income,
                 net_margin: reits_statement.net_margin,
                 eps_basic: reits_statement.eps_basic,
                 eps_diluted: reits_statement.eps_diluted,
                 shares_outstanding_basic: reits_statement.shares_outstanding_basic,
                 shares_outstanding_diluted: reits_statement.shares_outstanding_diluted,
-                shares_change_yoy: reits_statement.shares_change_yoy,
+                shares_change_yoy: Some(reits_statement.shares_change_yoy),
                 ffo: Some(reits_statement.ffo),
                 ffo_margin: None,
                 cash_and_equivalents: reits_statement.cash_and_equivalents,
@@ -556,6 +724,14 @@ impl<'a> NewEarningsReport<'a> {
                 free_cash_flow_margin: None,
                 ratio_calculated: false,
                 growth_calculated: false,
+                premiums_earned: None,
+                premiums_growth_yoy: None,
+                policy_benefits: None,
+                underwriting_expenses: None,
+                loss_ratio: None,
+                combined_ratio: None,
+                reported_revenue_growth_yoy: Some(reits_statement.revenue_growth_yoy),
+                reported_shares_change_yoy: Some(reits_statement.shares_change_yoy),
             }))
         } else {
             Ok(None)
@@ -582,7 +758,7 @@ impl<'a> NewEarningsReport<'a> {
                 provision_for_loan_loss: None,
                 cost_of_risk: None,
                 revenue: other_statement.revenue,
-                revenue_growth_yoy: Some(other_statement.revenue_growth_yoy),
+                revenue_growth_yoy: None,
                 cost_of_revenue: None,
                 gross_profit: None,
                 gross_margin: None,
@@ -596,14 +772,14 @@ impl<'a> NewEarningsReport<'a> {
                 operating_margin: other_statement.operating_margin,
                 interest_expenses: Some(other_statement.interest_expenses),
                 interest_expenses_op_income_ratio: None,
-                goodwill_impairment: other_statement.goodwill_impairment,
+                goodwill_impairment: Some(other_statement.goodwill_impairment),
                 net_income: other_statement.net_income,
                 net_margin: other_statement.net_margin,
                 eps_basic: other_statement.eps_basic,
                 eps_diluted: other_statement.eps_diluted,
                 shares_outstanding_basic: other_statement.shares_outstanding_basic,
                 shares_outstanding_diluted: other_statement.shares_outstanding_diluted,
-                shares_change_yoy: other_statement.shares_change_yoy,
+                shares_change_yoy: Some(other_statement.shares_change_yoy),
                 ffo: None,
                 ffo_margin: None,
                 cash_and_equivalents: other_statement.cash_and_equivalents,
@@ -633,23 +809,352 @@ impl<'a> NewEarningsReport<'a> {
                 free_cash_flow_margin: Some(other_statement.free_cash_flow_margin),
                 ratio_calculated: false,
                 growth_calculated: false,
+                premiums_earned: None,
+                premiums_growth_yoy: None,
+                policy_benefits: None,
+                underwriting_expenses: None,
+                loss_ratio: None,
+                combined_ratio: None,
+                reported_revenue_growth_yoy: Some(other_statement.revenue_growth_yoy),
+                reported_shares_change_yoy: Some(other_statement.shares_change_yoy),
             }))
         } else {
             Ok(None)
         }
     }
+    fn from_insurance(
+        comp_id: i32,
+        currency: &'a str,
+        insurance_statement: InsuranceStatement,
+    ) -> Result<Option<Self>, ParseError> {
+        if let Some((fiscal_y, fiscal_q)) =
+            helper::process_fiscal_string(&insurance_statement.fiscal_quarter)
+        {
+            Ok(Some(NewEarningsReport {
+                company_id: comp_id,
+                duration: insurance_statement.term,
+                quarter_str: fiscal_q,
+                year_str: fiscal_y,
+                period_ending: helper::convert_period_ending_str(
+                    &insurance_statement.period_ending,
+                )?,
+                currency,
+                net_interest_income: None,
+                net_interest_growth_yoy: None,
+                net_interest_margin: None,
+                provision_for_loan_loss: None,
+                cost_of_risk: None,
+                revenue: insurance_statement.revenue,
+                revenue_growth_yoy: None,
+                cost_of_revenue: None,
+                gross_profit: None,
+                gross_margin: None,
+                gross_profit_growth_yoy: None,
+                sga_expenses: None,
+                sga_gp_ratio: None,
+                rnd_expenses: None,
+                rnd_gp_ratio: None,
+                operating_expenses: insurance_statement.operating_expenses,
+                operating_income: insurance_statement.operating_income,
+                operating_margin: insurance_statement.operating_margin,
+                interest_expenses: Some(insurance_statement.interest_expenses),
+                interest_expenses_op_income_ratio: None,
+                goodwill_impairment: Some(insurance_statement.goodwill_impairment),
+                net_income: insurance_statement.net_income,
+                net_margin: insurance_statement.net_margin,
+                eps_basic: insurance_statement.eps_basic,
+                eps_diluted: insurance_statement.eps_diluted,
+                shares_outstanding_basic: insurance_statement.shares_outstanding_basic,
+                shares_outstanding_diluted: insurance_statement.shares_outstanding_diluted,
+                shares_change_yoy: Some(insurance_statement.shares_change_yoy),
+                ffo: None,
+                ffo_margin: None,
+                cash_and_equivalents: insurance_statement.cash_and_equivalents,
+                cash_and_short_term_investments: None,
+                total_investments: Some(insurance_statement.total_investments),
+                gross_loans: None,
+                accounts_receivable: None,
+                inventory: None,
+                total_current_assets: None,
+                goodwill: Some(insurance_statement.goodwill),
+                total_assets: insurance_statement.total_assets,
+                accounts_payable: None,
+                total_current_liabilities: None,
+                total_liabilities: insurance_statement.total_liabilities,
+                retained_earnings: insurance_statement.retained_earnings,
+                shareholders_equity: insurance_statement.shareholders_equity,
+                total_debt: Some(insurance_statement.total_debt),
+                net_cash: insurance_statement.net_cash,
+                depreciation_and_amortization: Some(
+                    insurance_statement.depreciation_and_amortization,
+                ),
+                stock_based_compensation: Some(insurance_statement.stock_based_compensation),
+                operating_cash_flow: Some(insurance_statement.operating_cash_flow),
+                operating_cash_flow_margin: None,
+                capital_expenditure: None,
+                investing_cash_flow: Some(insurance_statement.investing_cash_flow),
+                financing_cash_flow: Some(insurance_statement.financing_cash_flow),
+                free_cash_flow: Some(insurance_statement.free_cash_flow),
+                free_cash_flow_margin: Some(insurance_statement.free_cash_flow_margin),
+                ratio_calculated: false,
+                growth_calculated: false,
+                premiums_earned: Some(insurance_statement.premiums_and_annuity_revenue),
+                premiums_growth_yoy: None,
+                policy_benefits: Some(insurance_statement.policy_benefits),
+                underwriting_expenses: Some(
+                    insurance_statement.policy_acquisition_and_underwriting_costs,
+                ),
+                loss_ratio: None,
+                combined_ratio: None,
+                reported_revenue_growth_yoy: Some(insurance_statement.revenue_growth_yoy),
+                reported_shares_change_yoy: Some(insurance_statement.shares_change_yoy),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+    /// adds earnings data parsed from SEC companyfacts.
+    /// statements missing any figure the earnings table requires, such as the share counts, are skipped.
+    pub fn create_from_sec(
+        comp_id: i32,
+        currency: &'a str,
+        statements: Vec<SecStatement>,
+    ) -> Vec<Self> {
+        statements
+            .into_iter()
+            .filter_map(|statement| NewEarningsReport::from_sec(comp_id, currency, statement))
+            .collect()
+    }
+    /// restates share counts and EPS as filed with the split factor of the stored period
+    fn restate_split(&mut self, factor: f64) {
+        self.shares_outstanding_basic *= factor;
+        self.shares_outstanding_diluted *= factor;
+        self.eps_basic /= factor;
+        self.eps_diluted /= factor;
+    }
+    fn from_sec(comp_id: i32, currency: &'a str, sec_statement: SecStatement) -> Option<Self> {
+        let revenue = sec_statement.revenue?;
+        let operating_income = sec_statement.operating_income?;
+        let net_income = sec_statement.net_income?;
+        let total_assets = sec_statement.total_assets?;
+        let shareholders_equity = sec_statement.shareholders_equity?;
+        let cash_and_equivalents = sec_statement.cash_and_equivalents?;
+        let operating_expenses = sec_statement.operating_expenses?;
+        let retained_earnings = sec_statement.retained_earnings?;
+        let eps_basic = sec_statement.eps_basic?;
+        let eps_diluted = sec_statement.eps_diluted?;
+        let shares_outstanding_basic = sec_statement.shares_outstanding_basic?;
+        let shares_outstanding_diluted = sec_statement.shares_outstanding_diluted?;
+        let operating_margin = calculate::calculate_ratio_as_pct(Some(operating_income), revenue)?;
+        let net_margin = calculate::calculate_ratio_as_pct(Some(net_income), revenue)?;
+        let net_cash = sec_statement
+            .cash_and_short_term_investments
+            .unwrap_or(cash_and_equivalents)
+            - sec_statement.total_debt.unwrap_or(0.);
+        let free_cash_flow = sec_statement
+            .operating_cash_flow
+            .map(|ocf| ocf + sec_statement.capital_expenditure.unwrap_or(0.));
+        Some(NewEarningsReport {
+            company_id: comp_id,
+            duration: sec_statement.term,
+            quarter_str: sec_statement.quarter,
+            year_str: sec_statement.year,
+            period_ending: sec_statement.period_ending,
+            currency,
+            net_interest_income: None,
+            net_interest_growth_yoy: None,
+            net_interest_margin: None,
+            provision_for_loan_loss: None,
+            cost_of_risk: None,
+            revenue,
+            revenue_growth_yoy: None,
+            cost_of_revenue: sec_statement.cost_of_revenue,
+            gross_profit: sec_statement.gross_profit,
+            gross_margin: calculate::calculate_ratio_as_pct(sec_statement.gross_profit, revenue),
+            gross_profit_growth_yoy: None,
+            sga_expenses: sec_statement.sga_expenses,
+            sga_gp_ratio: None,
+            rnd_expenses: sec_statement.rnd_expenses,
+            rnd_gp_ratio: None,
+            operating_expenses,
+            operating_income,
+            operating_margin,
+            interest_expenses: sec_statement.interest_expenses,
+            interest_expenses_op_income_ratio: None,
+            goodwill_impairment: sec_statement.goodwill_impairment,
+            net_income,
+            net_margin,
+            eps_basic,
+            eps_diluted,
+            shares_outstanding_basic,
+            shares_outstanding_diluted,
+            shares_change_yoy: sec_statement.shares_change_yoy,
+            ffo: None,
+            ffo_margin: None,
+            cash_and_equivalents,
+            cash_and_short_term_investments: sec_statement.cash_and_short_term_investments,
+            total_investments: None,
+            gross_loans: None,
+            accounts_receivable: sec_statement.accounts_receivable,
+            inventory: sec_statement.inventory,
+            total_current_assets: sec_statement.total_current_assets,
+            goodwill: sec_statement.goodwill,
+            total_assets,
+            accounts_payable: sec_statement.accounts_payable,
+            total_current_liabilities: sec_statement.total_current_liabilities,
+            total_liabilities: sec_statement
+                .total_liabilities
+                .unwrap_or(total_assets - shareholders_equity),
+            retained_earnings,
+            shareholders_equity,
+            total_debt: sec_statement.total_debt,
+            net_cash,
+            depreciation_and_amortization: sec_statement.depreciation_and_amortization,
+            stock_based_compensation: sec_statement.stock_based_compensation,
+            operating_cash_flow: sec_statement.operating_cash_flow,
+            operating_cash_flow_margin: None,
+            capital_expenditure: sec_statement.capital_expenditure,
+            investing_cash_flow: sec_statement.investing_cash_flow,
+            financing_cash_flow: sec_statement.financing_cash_flow,
+            free_cash_flow,
+            free_cash_flow_margin: calculate::calculate_ratio_as_pct(free_cash_flow, revenue),
+            ratio_calculated: false,
+            growth_calculated: false,
+            premiums_earned: None,
+            premiums_growth_yoy: None,
+            policy_benefits: None,
+            underwriting_expenses: None,
+            loss_ratio: None,
+            combined_ratio: None,
+            reported_revenue_growth_yoy: None,
+            reported_shares_change_yoy: None,
+        })
+    }
 }
 
-/// inserts multiple earnings to the database
+/// inserts multiple earnings to the database and returns the number of new rows.
+/// periods that are already stored are left as they are.
 pub fn insert_earnings_report_batch(
     earnings_entries: Vec<NewEarningsReport>,
     conn: &mut PgConnection,
-) -> Result<bool, DieselError> {
+) -> Result<usize, DieselError> {
     use crate::schema::earnings_report::dsl::*;
-    let update_count = diesel::insert_into(earnings_report)
+    diesel::insert_into(earnings_report)
         .values(&earnings_entries)
         .on_conflict((company_id, duration, quarter_str, year_str))
         .do_nothing()
-        .execute(conn)?;
-    Ok(update_count > 0)
+        .execute(conn)
+}
+
+/// stores statements from SEC filings and returns the number of rows written.
+/// periods already stored from the scraper only get the columns the SEC reported and keep
+/// their split factor, so the filed share counts and EPS are restated with it.
+pub fn upsert_earnings_report_batch(
+    mut earnings_entries: Vec<NewEarningsReport>,
+    conn: &mut PgConnection,
+) -> Result<usize, DieselError> {
+    use crate::schema::earnings_report::dsl::*;
+    conn.transaction(|conn| {
+        let mut written_rows = 0;
+        for entry in earnings_entries.iter_mut() {
+            let stored_split_factor = earnings_report
+                .filter(company_id.eq(entry.company_id))
+                .filter(duration.eq(&entry.duration))
+                .filter(quarter_str.eq(entry.quarter_str))
+                .filter(year_str.eq(entry.year_str))
+                .select(split_factor)
+                .first::<f64>(conn)
+                .optional()?;
+            if let Some(factor) = stored_split_factor {
+                entry.restate_split(factor);
+            }
+            written_rows += diesel::insert_into(earnings_report)
+                .values(&*entry)
+                .on_conflict((company_id, duration, quarter_str, year_str))
+                .do_update()
+                .set(&*entry)
+                .execute(conn)?;
+        }
+        Ok(written_rows)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sec_statement() -> SecStatement {
+        SecStatement {
+            term: "Y".to_string(),
+            quarter: 0,
+            year: 2023,
+            period_ending: NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(),
+            revenue: Some(1000.),
+            cost_of_revenue: Some(600.),
+            gross_profit: Some(400.),
+            sga_expenses: Some(150.),
+            rnd_expenses: Some(50.),
+            operating_expenses: Some(200.),
+            operating_income: Some(200.),
+            interest_expenses: Some(10.),
+            goodwill_impairment: None,
+            net_income: Some(110.),
+            eps_basic: Some(1.1),
+            eps_diluted: Some(1.),
+            shares_outstanding_basic: Some(100.),
+            shares_outstanding_diluted: Some(110.),
+            shares_change_yoy: None,
+            cash_and_equivalents: Some(300.),
+            cash_and_short_term_investments: Some(350.),
+            accounts_receivable: Some(80.),
+            inventory: Some(40.),
+            total_current_assets: Some(500.),
+            goodwill: None,
+            total_assets: Some(2000.),
+            accounts_payable: Some(60.),
+            total_current_liabilities: Some(250.),
+            total_liabilities: Some(1200.),
+            retained_earnings: Some(500.),
+            shareholders_equity: Some(800.),
+            total_debt: Some(400.),
+            depreciation_and_amortization: Some(30.),
+            stock_based_compensation: Some(20.),
+            operating_cash_flow: Some(180.),
+            capital_expenditure: Some(-60.),
+            investing_cash_flow: Some(-70.),
+            financing_cash_flow: Some(-40.),
+        }
+    }
+
+    #[test]
+    fn sec_statement_without_share_counts_is_skipped() {
+        let statement = SecStatement {
+            shares_outstanding_diluted: None,
+            ..sec_statement()
+        };
+        assert!(NewEarningsReport::from_sec(1, "USD", statement).is_none());
+    }
+
+    #[test]
+    fn sec_upsert_over_scraped_row_keeps_its_split_adjustment() {
+        let mut entry = NewEarningsReport::from_sec(1, "USD", sec_statement()).unwrap();
+        // the scraped row was restated for a 2-for-1 split
+        entry.restate_split(2.);
+        assert_eq!(entry.shares_outstanding_basic, 200.);
+        assert_eq!(entry.shares_outstanding_diluted, 220.);
+        assert_eq!(entry.eps_basic, 0.55);
+        assert_eq!(entry.eps_diluted, 0.5);
+
+        let sql = diesel::debug_query::<diesel::pg::Pg, _>(
+            &diesel::update(earnings_report::table).set(&entry),
+        )
+        .to_string();
+        assert!(sql.contains("\"revenue\""));
+        assert!(sql.contains("\"shares_outstanding_diluted\""));
+        assert!(!sql.contains("\"split_factor\""));
+        // figures the SEC didn't report keep the scraped values
+        assert!(!sql.contains("\"goodwill_impairment\""));
+        assert!(!sql.contains("\"shares_change_yoy\""));
+        assert!(!sql.contains("\"net_interest_income\""));
+    }
 }
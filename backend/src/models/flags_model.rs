@@ -0,0 +1,150 @@
+use crate::schema::red_flags;
+use chrono::{Local, NaiveDate};
+use diesel::deserialize::{FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgConnection, PgValue};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::serialize::{Output, ToSql};
+use diesel::sql_types::Text;
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum RedFlag {
+    UnhealthyNetCash,
+    Dilution,
+    HighInterestExpense,
+    GoodwillImpairment,
+    NegativeFreeCashFlow,
+    SbcExceedsFreeCashFlow,
+}
+
+impl RedFlag {
+    pub const ALL: [RedFlag; 6] = [
+        RedFlag::UnhealthyNetCash,
+        RedFlag::Dilution,
+        RedFlag::HighInterestExpense,
+        RedFlag::GoodwillImpairment,
+        RedFlag::NegativeFreeCashFlow,
+        RedFlag::SbcExceedsFreeCashFlow,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RedFlag::UnhealthyNetCash => "unhealthy_net_cash",
+            RedFlag::Dilution => "dilution",
+            RedFlag::HighInterestExpense => "high_interest_expense",
+            RedFlag::GoodwillImpairment => "goodwill_impairment",
+            RedFlag::NegativeFreeCashFlow => "negative_free_cash_flow",
+            RedFlag::SbcExceedsFreeCashFlow => "sbc_exceeds_free_cash_flow",
+        }
+    }
+
+    /// parses flag name used in the database and query parameters
+    pub fn from_name(name: &str) -> Option<Self> {
+        RedFlag::ALL
+            .into_iter()
+            .find(|flag| flag.as_str() == name.trim())
+    }
+}
+
+impl ToSql<Text, Pg> for RedFlag
+where
+    str: ToSql<Text, Pg>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> diesel::serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for RedFlag {
+    fn from_sql(bytes: PgValue<'_>) -> diesel::deserialize::Result<Self> {
+        let s = std::str::from_utf8(bytes.as_bytes())?;
+        RedFlag::from_name(s)
+            .ok_or_else(|| format!("Invalid red flag value detected: {}", s).into())
+    }
+}
+
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = red_flags)]
+#[serde(rename_all = "camelCase")]
+pub struct CompanyRedFlag {
+    id: i32,
+    pub company_id: i32,
+    pub flag: RedFlag,
+    pub detected_on: NaiveDate,
+}
+
+impl CompanyRedFlag {
+    /// retrieves all active red flags for the given company id
+    pub fn load_by_id(comp_id: i32, conn: &mut PgConnection) -> Result<Vec<RedFlag>, DieselError> {
+        use crate::schema::red_flags::dsl::*;
+        let target = red_flags
+            .filter(company_id.eq(comp_id))
+            .select(flag)
+            .load::<RedFlag>(conn)?;
+        Ok(target)
+    }
+
+    /// retrieves all active red flags grouped by company id
+    pub fn load_all(conn: &mut PgConnection) -> Result<HashMap<i32, Vec<RedFlag>>, DieselError> {
+        use crate::schema::red_flags::dsl::*;
+        let target = red_flags
+            .select((company_id, flag))
+            .load::<(i32, RedFlag)>(conn)?;
+        let mut grouped: HashMap<i32, Vec<RedFlag>> = HashMap::new();
+        for (comp_id, curr_flag) in target {
+            grouped.entry(comp_id).or_default().push(curr_flag);
+        }
+        Ok(grouped)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = red_flags)]
+pub struct NewCompanyRedFlag {
+    company_id: i32,
+    flag: RedFlag,
+    detected_on: NaiveDate,
+}
+
+impl NewCompanyRedFlag {
+    pub fn create_new_entry(company_id: i32, flag: RedFlag) -> Self {
+        NewCompanyRedFlag {
+            company_id,
+            flag,
+            detected_on: Local::now().date_naive(),
+        }
+    }
+}
+
+/// replaces red flags for the given company while keeping the original detection date
+pub fn replace_red_flags(
+    comp_id: i32,
+    current_flags: &[RedFlag],
+    conn: &mut PgConnection,
+) -> Result<(), DieselError> {
+    use crate::schema::red_flags::dsl::*;
+    let entries: Vec<NewCompanyRedFlag> = current_flags
+        .iter()
+        .map(|curr_flag| NewCompanyRedFlag::create_new_entry(comp_id, *curr_flag))
+        .collect();
+    conn.transaction(|conn| {
+        diesel::delete(
+            red_flags
+                .filter(company_id.eq(comp_id))
+                .filter(flag.ne_all(current_flags.to_vec())),
+        )
+        .execute(conn)?;
+        diesel::insert_into(red_flags)
+            .values(&entries)
+            .on_conflict((company_id, flag))
+            .do_nothing()
+            .execute(conn)?;
+        Ok(())
+    })
+}
@@ -25,6 +25,13 @@ pub struct Forecasts {
     pub price_next_year_revenue_growth: Option<f64>,
     pub price_multi_year_revenue_growth: Option<f64>,
     pub price_multi_year_gp_growth: Option<f64>,
+    pub pe_ratio: Option<f64>,
+    pub ps_ratio: Option<f64>,
+    pub pfcf_ratio: Option<f64>,
+    pub ev_ebit: Option<f64>,
+    pub ev_sales: Option<f64>,
+    pub trading_currency: Option<String>,
+    pub price_target_available: Option<bool>,
 }
 
 impl Forecasts {
@@ -62,6 +69,13 @@ pub struct NewForecasts {
     price_next_year_revenue_growth: Option<f64>,
     price_multi_year_revenue_growth: Option<f64>,
     price_multi_year_gp_growth: Option<f64>,
+    pe_ratio: Option<f64>,
+    ps_ratio: Option<f64>,
+    pfcf_ratio: Option<f64>,
+    ev_ebit: Option<f64>,
+    ev_sales: Option<f64>,
+    trading_currency: Option<String>,
+    price_target_available: Option<bool>,
 }
 impl NewForecasts {
     pub fn create_empty(company_id: i32) -> Self {
@@ -77,6 +91,13 @@ impl NewForecasts {
             price_next_year_revenue_growth: None,
             price_multi_year_revenue_growth: None,
             price_multi_year_gp_growth: None,
+            pe_ratio: None,
+            ps_ratio: None,
+            pfcf_ratio: None,
+            ev_ebit: None,
+            ev_sales: None,
+            trading_currency: None,
+            price_target_available: None,
         }
     }
     pub fn create_new_entry(
@@ -98,6 +119,13 @@ impl NewForecasts {
             price_next_year_revenue_growth: None,
             price_multi_year_revenue_growth: None,
             price_multi_year_gp_growth: None,
+            pe_ratio: None,
+            ps_ratio: None,
+            pfcf_ratio: None,
+            ev_ebit: None,
+            ev_sales: None,
+            trading_currency: None,
+            price_target_available: None,
         })
     }
     pub fn insert_new_forecast(&self, conn: &mut PgConnection) -> Result<bool, DieselError> {
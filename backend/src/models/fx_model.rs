@@ -0,0 +1,64 @@
+use crate::schema::fx_rates;
+use chrono::{Local, NaiveDate};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::upsert::excluded;
+
+#[derive(Queryable, Selectable)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = fx_rates)]
+pub struct FxRate {
+    id: i32,
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub rate: f64,
+    pub last_updated: NaiveDate,
+}
+
+impl FxRate {
+    /// retrieves all stored exchange rates
+    pub fn load_all(conn: &mut PgConnection) -> Result<Vec<Self>, DieselError> {
+        use crate::schema::fx_rates::dsl::*;
+        fx_rates.load::<FxRate>(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = fx_rates)]
+pub struct NewFxRate {
+    base_currency: String,
+    quote_currency: String,
+    rate: f64,
+    last_updated: NaiveDate,
+}
+
+impl NewFxRate {
+    /// rate is the amount of quote currency for one unit of base currency
+    pub fn create_new_entry(base_currency: &str, quote_currency: &str, rate: f64) -> Self {
+        NewFxRate {
+            base_currency: base_currency.to_uppercase(),
+            quote_currency: quote_currency.to_uppercase(),
+            rate,
+            last_updated: Local::now().date_naive(),
+        }
+    }
+}
+
+/// inserts or replaces the given exchange rates
+pub fn upsert_fx_rates(
+    entries: Vec<NewFxRate>,
+    conn: &mut PgConnection,
+) -> Result<(), DieselError> {
+    use crate::schema::fx_rates::dsl::*;
+    diesel::insert_into(fx_rates)
+        .values(&entries)
+        .on_conflict((base_currency, quote_currency))
+        .do_update()
+        .set((
+            rate.eq(excluded(rate)),
+            last_updated.eq(excluded(last_updated)),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
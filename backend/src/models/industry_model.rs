@@ -0,0 +1,122 @@
+use crate::calculate;
+use crate::models::companies_model::Company;
+use crate::models::metrics_model::{CurrentMetrics, Trend};
+use crate::models::peers_model::{IndustryDistribution, PeerSummary};
+use serde::Serialize;
+
+/// quartiles of a metric across the industry
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricDistribution {
+    count: usize,
+    lower_quartile: Option<f64>,
+    median: Option<f64>,
+    upper_quartile: Option<f64>,
+}
+
+impl MetricDistribution {
+    pub fn new(values: &[f64]) -> Self {
+        MetricDistribution {
+            count: values.len(),
+            lower_quartile: calculate::calculate_quantile(values, 0.25),
+            median: calculate::calculate_quantile(values, 0.5),
+            upper_quartile: calculate::calculate_quantile(values, 0.75),
+        }
+    }
+}
+
+/// number of companies in each trend
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendDistribution {
+    uptrend: usize,
+    downtrend: usize,
+    flat: usize,
+    irrelevant: usize,
+    not_calculated: usize,
+}
+
+impl TrendDistribution {
+    pub fn new<'a, I>(trends: I) -> Self
+    where
+        I: Iterator<Item = Option<&'a Trend>>,
+    {
+        let mut distribution = TrendDistribution::default();
+        for trend in trends {
+            match trend {
+                Some(Trend::Uptrend) => distribution.uptrend += 1,
+                Some(Trend::Downtrend) => distribution.downtrend += 1,
+                Some(Trend::Flat) => distribution.flat += 1,
+                Some(Trend::Irrelevant) => distribution.irrelevant += 1,
+                None => distribution.not_calculated += 1,
+            }
+        }
+        distribution
+    }
+}
+
+/// aggregated metrics over all companies in the industry
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndustrySummary {
+    industry: String,
+    company_count: usize,
+    gross_margin: MetricDistribution,
+    operating_margin: MetricDistribution,
+    revenue_growth_yoy: MetricDistribution,
+    revenue_growth_multi_year: MetricDistribution,
+    free_cash_flow_margin: MetricDistribution,
+    shares_change: MetricDistribution,
+    gross_margin_short_term_trend: TrendDistribution,
+    gross_margin_long_term_trend: TrendDistribution,
+    operating_margin_short_term_trend: TrendDistribution,
+    operating_margin_long_term_trend: TrendDistribution,
+}
+
+impl IndustrySummary {
+    pub fn new(industry: String, members: &[(Company, CurrentMetrics)]) -> Self {
+        let distribution = IndustryDistribution::new(members);
+        let trends = |field: fn(&CurrentMetrics) -> Option<&Trend>| {
+            TrendDistribution::new(members.iter().map(|(_, metrics)| field(metrics)))
+        };
+        IndustrySummary {
+            industry,
+            company_count: distribution.peer_count,
+            gross_margin: MetricDistribution::new(&distribution.gross_margin),
+            operating_margin: MetricDistribution::new(&distribution.operating_margin),
+            revenue_growth_yoy: MetricDistribution::new(&distribution.revenue_growth_yoy),
+            revenue_growth_multi_year: MetricDistribution::new(
+                &distribution.revenue_growth_multi_year,
+            ),
+            free_cash_flow_margin: MetricDistribution::new(&distribution.free_cash_flow_margin),
+            shares_change: MetricDistribution::new(&distribution.shares_change),
+            gross_margin_short_term_trend: trends(|m| m.gross_margin_short_term_trend.as_ref()),
+            gross_margin_long_term_trend: trends(|m| m.gross_margin_long_term_trend.as_ref()),
+            operating_margin_short_term_trend: trends(|m| {
+                m.operating_margin_short_term_trend.as_ref()
+            }),
+            operating_margin_long_term_trend: trends(|m| {
+                m.operating_margin_long_term_trend.as_ref()
+            }),
+        }
+    }
+}
+
+/// industry aggregates with the list of member companies
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndustryDetail {
+    summary: IndustrySummary,
+    members: Vec<PeerSummary>,
+}
+
+impl IndustryDetail {
+    pub fn new(industry: String, members: Vec<(Company, CurrentMetrics)>) -> Self {
+        let summary = IndustrySummary::new(industry, &members);
+        let members = members
+            .into_iter()
+            .map(|(company, metrics)| PeerSummary::new(company, metrics))
+            .collect();
+        IndustryDetail { summary, members }
+    }
+}
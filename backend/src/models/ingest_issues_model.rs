@@ -0,0 +1,162 @@
+use crate::schema::ingest_issues;
+use chrono::{Local, NaiveDate};
+use diesel::deserialize::{FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgConnection, PgValue};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::serialize::{Output, ToSql};
+use diesel::sql_types::Text;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    Warning,
+    Critical,
+}
+
+impl IssueSeverity {
+    pub const ALL: [IssueSeverity; 2] = [IssueSeverity::Warning, IssueSeverity::Critical];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IssueSeverity::Warning => "warning",
+            IssueSeverity::Critical => "critical",
+        }
+    }
+
+    /// parses severity name used in the database
+    pub fn from_name(name: &str) -> Option<Self> {
+        IssueSeverity::ALL
+            .into_iter()
+            .find(|severity| severity.as_str() == name.trim())
+    }
+}
+
+impl ToSql<Text, Pg> for IssueSeverity
+where
+    str: ToSql<Text, Pg>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> diesel::serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for IssueSeverity {
+    fn from_sql(bytes: PgValue<'_>) -> diesel::deserialize::Result<Self> {
+        let s = std::str::from_utf8(bytes.as_bytes())?;
+        IssueSeverity::from_name(s)
+            .ok_or_else(|| format!("Invalid issue severity value detected: {}", s).into())
+    }
+}
+
+/// problem found while validating ingested statements
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = ingest_issues)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestIssue {
+    id: i32,
+    pub company_id: i32,
+    pub period: String,
+    pub check_name: String,
+    pub severity: IssueSeverity,
+    pub message: String,
+    pub detected_on: NaiveDate,
+    /// reviewed and accepted by an admin, so it no longer blocks the metrics
+    pub accepted: bool,
+}
+
+impl IngestIssue {
+    /// retrieves all issues of the latest ingest for the given company id
+    pub fn load_by_id(comp_id: i32, conn: &mut PgConnection) -> Result<Vec<Self>, DieselError> {
+        use crate::schema::ingest_issues::dsl::*;
+        ingest_issues
+            .filter(company_id.eq(comp_id))
+            .order((period.asc(), check_name.asc()))
+            .load::<IngestIssue>(conn)
+    }
+
+    /// marks the issue found by the given check in the given period as accepted.
+    /// returns the number of issues marked.
+    pub fn accept(
+        comp_id: i32,
+        issue_period: &str,
+        issue_check: &str,
+        conn: &mut PgConnection,
+    ) -> Result<usize, DieselError> {
+        use crate::schema::ingest_issues::dsl::*;
+        diesel::update(
+            ingest_issues
+                .filter(company_id.eq(comp_id))
+                .filter(period.eq(issue_period))
+                .filter(check_name.eq(issue_check)),
+        )
+        .set(accepted.eq(true))
+        .execute(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = ingest_issues)]
+pub struct NewIngestIssue {
+    company_id: i32,
+    period: String,
+    check_name: &'static str,
+    severity: IssueSeverity,
+    message: String,
+    detected_on: NaiveDate,
+}
+
+impl NewIngestIssue {
+    pub fn create_new_entry(
+        company_id: i32,
+        period: String,
+        check_name: &'static str,
+        severity: IssueSeverity,
+        message: String,
+    ) -> Self {
+        NewIngestIssue {
+            company_id,
+            period,
+            check_name,
+            severity,
+            message,
+            detected_on: Local::now().date_naive(),
+        }
+    }
+}
+
+/// replaces the issues of the given company with the ones found by the latest ingest.
+/// issues of the kept checks weren't re-checked by the ingest and are left as they are.
+/// issues accepted before stay accepted when the same check fails again in the same period.
+pub fn replace_ingest_issues(
+    comp_id: i32,
+    entries: &[NewIngestIssue],
+    kept_checks: &[&str],
+    conn: &mut PgConnection,
+) -> Result<(), DieselError> {
+    use crate::schema::ingest_issues::dsl::*;
+    conn.transaction(|conn| {
+        let accepted_issues: Vec<(String, String)> = ingest_issues
+            .filter(company_id.eq(comp_id))
+            .filter(accepted.eq(true))
+            .select((period, check_name))
+            .load(conn)?;
+        diesel::delete(
+            ingest_issues
+                .filter(company_id.eq(comp_id))
+                .filter(check_name.ne_all(kept_checks.iter().copied())),
+        )
+        .execute(conn)?;
+        diesel::insert_into(ingest_issues)
+            .values(entries)
+            .execute(conn)?;
+        for (accepted_period, accepted_check) in &accepted_issues {
+            IngestIssue::accept(comp_id, accepted_period, accepted_check, conn)?;
+        }
+        Ok(())
+    })
+}
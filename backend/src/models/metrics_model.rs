@@ -99,6 +99,20 @@ pub struct CurrentMetrics {
     pub free_cash_flow_margin_ttm: Option<f64>,
     pub ffo_margin_ttm: Option<f64>,
     pub ffo_margin_trend: Option<Trend>,
+    pub piotroski_f_score: Option<i16>,
+    pub altman_z_score: Option<f64>,
+    pub premiums_growth_yoy_ttm: Option<f64>,
+    pub premiums_growth_multi_year: Option<f64>,
+    pub loss_ratio_ttm: Option<f64>,
+    pub loss_ratio_short_term_trend: Option<Trend>,
+    pub loss_ratio_long_term_trend: Option<Trend>,
+    pub combined_ratio_ttm: Option<f64>,
+    pub combined_ratio_short_term_trend: Option<Trend>,
+    pub combined_ratio_long_term_trend: Option<Trend>,
+    #[serde(skip_serializing)]
+    pub calculation_version: i32,
+    pub pe_ratio_percentile: Option<f64>,
+    pub ps_ratio_percentile: Option<f64>,
 }
 impl CurrentMetrics {
     /// retrieve metric data for the given company id
@@ -150,7 +164,7 @@ pub struct NewCurrentMetrics<'a> {
     retained_earnings_ttm: Option<f64>,
     retained_earnings_trend: Option<Trend>,
     net_cash_ttm: Option<f64>,
-    has_healthy_net_cash: Option<bool>, //yet
+    has_healthy_net_cash: Option<bool>,
     net_cash_trend: Option<Trend>,
     operating_cash_flow_ttm: Option<f64>,
     operating_cash_flow_margin_ttm: Option<f64>,
@@ -159,6 +173,16 @@ pub struct NewCurrentMetrics<'a> {
     free_cash_flow_margin_ttm: Option<f64>,
     ffo_margin_ttm: Option<f64>,
     ffo_margin_trend: Option<Trend>,
+    piotroski_f_score: Option<i16>,
+    altman_z_score: Option<f64>,
+    premiums_growth_yoy_ttm: Option<f64>,
+    premiums_growth_multi_year: Option<f64>,
+    loss_ratio_ttm: Option<f64>,
+    loss_ratio_short_term_trend: Option<Trend>,
+    loss_ratio_long_term_trend: Option<Trend>,
+    combined_ratio_ttm: Option<f64>,
+    combined_ratio_short_term_trend: Option<Trend>,
+    combined_ratio_long_term_trend: Option<Trend>,
 }
 
 impl<'a> NewCurrentMetrics<'a> {
@@ -212,6 +236,16 @@ impl<'a> NewCurrentMetrics<'a> {
             free_cash_flow_margin_ttm: None,
             ffo_margin_ttm: None,
             ffo_margin_trend: None,
+            piotroski_f_score: None,
+            altman_z_score: None,
+            premiums_growth_yoy_ttm: None,
+            premiums_growth_multi_year: None,
+            loss_ratio_ttm: None,
+            loss_ratio_short_term_trend: None,
+            loss_ratio_long_term_trend: None,
+            combined_ratio_ttm: None,
+            combined_ratio_short_term_trend: None,
+            combined_ratio_long_term_trend: None,
         })
     }
     /// inserts new ticker data to the metrics database
@@ -1,5 +1,17 @@
+pub mod backfill_model;
 pub mod companies_model;
+pub mod corporate_actions_model;
+pub mod data_quality_model;
 pub mod earnings_model;
+pub mod flags_model;
 pub mod forecast_models;
+pub mod fx_model;
+pub mod industry_model;
+pub mod ingest_issues_model;
 pub mod metrics_model;
+pub mod peers_model;
+pub mod price_history_model;
 pub mod returning_model;
+pub mod search_model;
+pub mod symbols_model;
+pub mod ticker_history_model;
@@ -0,0 +1,219 @@
+use crate::calculate;
+use crate::models::companies_model::Company;
+use crate::models::metrics_model::CurrentMetrics;
+use crate::query;
+use crate::schema::peer_ranks;
+use chrono::{Local, NaiveDate};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::upsert::excluded;
+use serde::Serialize;
+
+/// all available values of key metrics within the same industry
+pub struct IndustryDistribution {
+    pub peer_count: usize,
+    pub gross_margin: Vec<f64>,
+    pub operating_margin: Vec<f64>,
+    pub revenue_growth_yoy: Vec<f64>,
+    pub revenue_growth_multi_year: Vec<f64>,
+    pub free_cash_flow_margin: Vec<f64>,
+    pub shares_change: Vec<f64>,
+}
+
+impl IndustryDistribution {
+    /// available values of the given metric among the peers
+    pub fn collect_values(
+        peers: &[(Company, CurrentMetrics)],
+        field: fn(&CurrentMetrics) -> Option<f64>,
+    ) -> Vec<f64> {
+        peers
+            .iter()
+            .filter_map(|(_, metrics)| field(metrics))
+            .collect()
+    }
+
+    pub fn new(peers: &[(Company, CurrentMetrics)]) -> Self {
+        let collect_values =
+            |field: fn(&CurrentMetrics) -> Option<f64>| Self::collect_values(peers, field);
+        IndustryDistribution {
+            peer_count: peers.len(),
+            gross_margin: collect_values(|m| m.gross_margin_ttm),
+            operating_margin: collect_values(|m| m.operating_margin_ttm),
+            revenue_growth_yoy: collect_values(|m| m.revenue_growth_yoy_ttm),
+            revenue_growth_multi_year: collect_values(|m| m.revenue_growth_multi_year),
+            free_cash_flow_margin: collect_values(|m| m.free_cash_flow_margin_ttm),
+            shares_change: collect_values(|m| m.shares_change_ttm),
+        }
+    }
+}
+
+/// percentile ranks (0-100) of each metric within the same industry
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = peer_ranks)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerRanks {
+    id: i32,
+    company_id: i32,
+    pub industry: String,
+    pub peer_count: i32,
+    pub gross_margin_rank: Option<f64>,
+    pub operating_margin_rank: Option<f64>,
+    pub revenue_growth_yoy_rank: Option<f64>,
+    pub revenue_growth_multi_year_rank: Option<f64>,
+    pub free_cash_flow_margin_rank: Option<f64>,
+    pub shares_change_rank: Option<f64>,
+    pub last_updated: NaiveDate,
+}
+
+impl PeerRanks {
+    /// retrieves peer ranks for the given company id if calculated
+    pub fn load_by_id_if_existed(
+        comp_id: i32,
+        conn: &mut PgConnection,
+    ) -> Result<Option<Self>, DieselError> {
+        use crate::schema::peer_ranks::dsl::*;
+        let target =
+            query::load_first_row(peer_ranks.filter(company_id.eq(comp_id)), conn).optional()?;
+        Ok(target)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = peer_ranks)]
+pub struct NewPeerRanks<'a> {
+    company_id: i32,
+    industry: &'a str,
+    peer_count: i32,
+    gross_margin_rank: Option<f64>,
+    operating_margin_rank: Option<f64>,
+    revenue_growth_yoy_rank: Option<f64>,
+    revenue_growth_multi_year_rank: Option<f64>,
+    free_cash_flow_margin_rank: Option<f64>,
+    shares_change_rank: Option<f64>,
+    last_updated: NaiveDate,
+}
+
+impl<'a> NewPeerRanks<'a> {
+    /// creates ranks of the given metrics within the industry distribution
+    pub fn create_new_entry(
+        company_id: i32,
+        industry: &'a str,
+        metrics: &CurrentMetrics,
+        distribution: &IndustryDistribution,
+    ) -> Self {
+        NewPeerRanks {
+            company_id,
+            industry,
+            peer_count: distribution.peer_count as i32,
+            gross_margin_rank: calculate::calculate_percentile_rank(
+                metrics.gross_margin_ttm,
+                &distribution.gross_margin,
+            ),
+            operating_margin_rank: calculate::calculate_percentile_rank(
+                metrics.operating_margin_ttm,
+                &distribution.operating_margin,
+            ),
+            revenue_growth_yoy_rank: calculate::calculate_percentile_rank(
+                metrics.revenue_growth_yoy_ttm,
+                &distribution.revenue_growth_yoy,
+            ),
+            revenue_growth_multi_year_rank: calculate::calculate_percentile_rank(
+                metrics.revenue_growth_multi_year,
+                &distribution.revenue_growth_multi_year,
+            ),
+            free_cash_flow_margin_rank: calculate::calculate_percentile_rank(
+                metrics.free_cash_flow_margin_ttm,
+                &distribution.free_cash_flow_margin,
+            ),
+            shares_change_rank: calculate::calculate_percentile_rank(
+                metrics.shares_change_ttm,
+                &distribution.shares_change,
+            ),
+            last_updated: Local::now().date_naive(),
+        }
+    }
+}
+
+/// inserts or replaces peer ranks for multiple companies
+pub fn upsert_peer_ranks_batch(
+    entries: &[NewPeerRanks],
+    conn: &mut PgConnection,
+) -> Result<usize, DieselError> {
+    use crate::schema::peer_ranks::dsl::*;
+    diesel::insert_into(peer_ranks)
+        .values(entries)
+        .on_conflict(company_id)
+        .do_update()
+        .set((
+            industry.eq(excluded(industry)),
+            peer_count.eq(excluded(peer_count)),
+            gross_margin_rank.eq(excluded(gross_margin_rank)),
+            operating_margin_rank.eq(excluded(operating_margin_rank)),
+            revenue_growth_yoy_rank.eq(excluded(revenue_growth_yoy_rank)),
+            revenue_growth_multi_year_rank.eq(excluded(revenue_growth_multi_year_rank)),
+            free_cash_flow_margin_rank.eq(excluded(free_cash_flow_margin_rank)),
+            shares_change_rank.eq(excluded(shares_change_rank)),
+            last_updated.eq(excluded(last_updated)),
+        ))
+        .execute(conn)
+}
+
+/// key metrics of a company in the same industry
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerSummary {
+    company_name: String,
+    exchange: String,
+    ticker: String,
+    currency: String,
+    gross_margin_ttm: Option<f64>,
+    operating_margin_ttm: Option<f64>,
+    revenue_growth_yoy_ttm: Option<f64>,
+    revenue_growth_multi_year: Option<f64>,
+    free_cash_flow_margin_ttm: Option<f64>,
+    shares_change_ttm: Option<f64>,
+}
+
+impl PeerSummary {
+    pub fn new(company: Company, metrics: CurrentMetrics) -> Self {
+        PeerSummary {
+            company_name: company.company_name,
+            exchange: company.exchange,
+            ticker: company.ticker,
+            currency: metrics.currency,
+            gross_margin_ttm: metrics.gross_margin_ttm,
+            operating_margin_ttm: metrics.operating_margin_ttm,
+            revenue_growth_yoy_ttm: metrics.revenue_growth_yoy_ttm,
+            revenue_growth_multi_year: metrics.revenue_growth_multi_year,
+            free_cash_flow_margin_ttm: metrics.free_cash_flow_margin_ttm,
+            shares_change_ttm: metrics.shares_change_ttm,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeersModel {
+    ticker: String,
+    industry: String,
+    ranks: Option<PeerRanks>,
+    peers: Vec<PeerSummary>,
+}
+
+impl PeersModel {
+    pub fn new(
+        ticker: String,
+        industry: String,
+        ranks: Option<PeerRanks>,
+        peers: Vec<PeerSummary>,
+    ) -> Self {
+        PeersModel {
+            ticker,
+            industry,
+            ranks,
+            peers,
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::schema::price_history;
+use chrono::NaiveDate;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::upsert::excluded;
+use serde::Serialize;
+
+/// daily prices in the trading currency.
+/// open, high, low and volume are only known for imported days.
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = price_history)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceHistory {
+    #[serde(skip_serializing)]
+    id: i32,
+    #[serde(skip_serializing)]
+    pub company_id: i32,
+    pub price_date: NaiveDate,
+    pub close: f64,
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    pub volume: Option<i64>,
+}
+
+impl PriceHistory {
+    /// retrieves every stored price of the given company id, oldest first
+    pub fn load_by_id(comp_id: i32, conn: &mut PgConnection) -> Result<Vec<Self>, DieselError> {
+        use crate::schema::price_history::dsl::*;
+        price_history
+            .filter(company_id.eq(comp_id))
+            .order(price_date.asc())
+            .load::<PriceHistory>(conn)
+    }
+
+    /// retrieves stored prices of the given company id between the given days, oldest first
+    pub fn load_range(
+        comp_id: i32,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        conn: &mut PgConnection,
+    ) -> Result<Vec<Self>, DieselError> {
+        use crate::schema::price_history::dsl::*;
+        let mut query = price_history.filter(company_id.eq(comp_id)).into_boxed();
+        if let Some(from) = from {
+            query = query.filter(price_date.ge(from));
+        }
+        if let Some(to) = to {
+            query = query.filter(price_date.le(to));
+        }
+        query.order(price_date.asc()).load::<PriceHistory>(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = price_history)]
+pub struct NewPriceHistory {
+    company_id: i32,
+    price_date: NaiveDate,
+    close: f64,
+    open: Option<f64>,
+    high: Option<f64>,
+    low: Option<f64>,
+    volume: Option<i64>,
+}
+
+impl NewPriceHistory {
+    pub fn create_new_entry(
+        company_id: i32,
+        price_date: NaiveDate,
+        open: Option<f64>,
+        high: Option<f64>,
+        low: Option<f64>,
+        close: f64,
+        volume: Option<i64>,
+    ) -> Self {
+        NewPriceHistory {
+            company_id,
+            price_date,
+            close,
+            open,
+            high,
+            low,
+            volume,
+        }
+    }
+}
+
+/// inserts the given prices, replacing the ones already stored for the same day
+pub fn upsert_price_history(
+    entries: &[NewPriceHistory],
+    conn: &mut PgConnection,
+) -> Result<usize, DieselError> {
+    use crate::schema::price_history::dsl::*;
+    diesel::insert_into(price_history)
+        .values(entries)
+        .on_conflict((company_id, price_date))
+        .do_update()
+        .set((
+            close.eq(excluded(close)),
+            open.eq(excluded(open)),
+            high.eq(excluded(high)),
+            low.eq(excluded(low)),
+            volume.eq(excluded(volume)),
+        ))
+        .execute(conn)
+}
+
+/// stores a price scraped after the close of the given trading day.
+/// closes already stored for the day, e.g. from an import, are kept.
+pub fn insert_closing_price(
+    comp_id: i32,
+    trading_day: NaiveDate,
+    price: f64,
+    conn: &mut PgConnection,
+) -> Result<usize, DieselError> {
+    use crate::schema::price_history::dsl::*;
+    diesel::insert_into(price_history)
+        .values(NewPriceHistory::create_new_entry(
+            comp_id,
+            trading_day,
+            None,
+            None,
+            None,
+            price,
+            None,
+        ))
+        .on_conflict((company_id, price_date))
+        .do_nothing()
+        .execute(conn)
+}
@@ -1,4 +1,6 @@
+use crate::fx::FxTable;
 use crate::models::companies_model::Company;
+use crate::models::flags_model::RedFlag;
 use crate::models::forecast_models::Forecasts;
 use crate::models::metrics_model::{CurrentMetrics, Trend};
 use chrono::NaiveDate;
@@ -11,6 +13,7 @@ pub struct ReturningModel {
     industry: String,
     exchange: String,
     ticker: String,
+    delisted: bool,
     currency: String,
     net_interest_income_growth_yoy_ttm: Option<f64>,
     net_interest_income_growth_multi_year: Option<f64>,
@@ -57,6 +60,17 @@ pub struct ReturningModel {
     free_cash_flow_margin_ttm: Option<f64>,
     ffo_margin_ttm: Option<f64>,
     ffo_margin_trend: Option<Trend>,
+    piotroski_f_score: Option<i16>,
+    altman_z_score: Option<f64>,
+    premiums_growth_yoy_ttm: Option<f64>,
+    premiums_growth_multi_year: Option<f64>,
+    loss_ratio_ttm: Option<f64>,
+    loss_ratio_short_term_trend: Option<Trend>,
+    loss_ratio_long_term_trend: Option<Trend>,
+    combined_ratio_ttm: Option<f64>,
+    combined_ratio_short_term_trend: Option<Trend>,
+    combined_ratio_long_term_trend: Option<Trend>,
+    red_flags: Vec<RedFlag>,
     next_earnings_date: Option<NaiveDate>,
     latest_price: Option<f64>,
     last_updated: Option<NaiveDate>,
@@ -67,15 +81,31 @@ pub struct ReturningModel {
     price_next_year_revenue_growth: Option<f64>,
     price_multi_year_revenue_growth: Option<f64>,
     price_multi_year_gp_growth: Option<f64>,
+    pe_ratio: Option<f64>,
+    ps_ratio: Option<f64>,
+    pfcf_ratio: Option<f64>,
+    ev_ebit: Option<f64>,
+    ev_sales: Option<f64>,
+    pe_ratio_percentile: Option<f64>,
+    ps_ratio_percentile: Option<f64>,
+    trading_currency: Option<String>,
+    price_target_available: Option<bool>,
+    converted: Option<ConvertedAmounts>,
 }
 
 impl ReturningModel {
-    pub fn new(company: Company, metrics: CurrentMetrics, forecasts: Forecasts) -> Self {
+    pub fn new(
+        company: Company,
+        metrics: CurrentMetrics,
+        forecasts: Forecasts,
+        red_flags: Vec<RedFlag>,
+    ) -> Self {
         ReturningModel {
             company_name: company.company_name,
             industry: company.industry,
             exchange: company.exchange,
             ticker: company.ticker,
+            delisted: company.delisted,
             currency: metrics.currency,
             net_interest_income_growth_yoy_ttm: metrics.net_interest_income_growth_yoy_ttm,
             net_interest_income_growth_multi_year: metrics.net_interest_income_growth_multi_year,
@@ -122,6 +152,17 @@ impl ReturningModel {
             free_cash_flow_margin_ttm: metrics.free_cash_flow_margin_ttm,
             ffo_margin_ttm: metrics.ffo_margin_ttm,
             ffo_margin_trend: metrics.ffo_margin_trend,
+            piotroski_f_score: metrics.piotroski_f_score,
+            altman_z_score: metrics.altman_z_score,
+            premiums_growth_yoy_ttm: metrics.premiums_growth_yoy_ttm,
+            premiums_growth_multi_year: metrics.premiums_growth_multi_year,
+            loss_ratio_ttm: metrics.loss_ratio_ttm,
+            loss_ratio_short_term_trend: metrics.loss_ratio_short_term_trend,
+            loss_ratio_long_term_trend: metrics.loss_ratio_long_term_trend,
+            combined_ratio_ttm: metrics.combined_ratio_ttm,
+            combined_ratio_short_term_trend: metrics.combined_ratio_short_term_trend,
+            combined_ratio_long_term_trend: metrics.combined_ratio_long_term_trend,
+            red_flags,
             next_earnings_date: forecasts.next_earnings_date,
             latest_price: forecasts.latest_price,
             last_updated: forecasts.last_updated,
@@ -132,6 +173,62 @@ impl ReturningModel {
             price_next_year_revenue_growth: forecasts.price_next_year_revenue_growth,
             price_multi_year_revenue_growth: forecasts.price_multi_year_revenue_growth,
             price_multi_year_gp_growth: forecasts.price_multi_year_gp_growth,
+            pe_ratio: forecasts.pe_ratio,
+            ps_ratio: forecasts.ps_ratio,
+            pfcf_ratio: forecasts.pfcf_ratio,
+            ev_ebit: forecasts.ev_ebit,
+            ev_sales: forecasts.ev_sales,
+            pe_ratio_percentile: metrics.pe_ratio_percentile,
+            ps_ratio_percentile: metrics.ps_ratio_percentile,
+            trading_currency: forecasts.trading_currency,
+            price_target_available: forecasts.price_target_available,
+            converted: None,
         }
     }
+    /// attaches absolute amounts converted into the requested currency
+    pub fn with_currency(mut self, target_currency: &str, fx_table: &FxTable) -> Self {
+        let from = self.currency.as_str();
+        let price_from = self.trading_currency.as_deref().unwrap_or(from);
+        let convert = |amount: Option<f64>| fx_table.convert(amount, from, target_currency);
+        let convert_price =
+            |amount: Option<f64>| fx_table.convert(amount, price_from, target_currency);
+        self.converted = Some(ConvertedAmounts {
+            currency: target_currency.to_uppercase(),
+            rate: fx_table.rate(from, target_currency),
+            revenue_ttm: convert(self.revenue_ttm),
+            retained_earnings_ttm: convert(self.retained_earnings_ttm),
+            net_cash_ttm: convert(self.net_cash_ttm),
+            operating_cash_flow_ttm: convert(self.operating_cash_flow_ttm),
+            free_cash_flow_ttm: convert(self.free_cash_flow_ttm),
+            revenue_next_year: convert(self.revenue_next_year),
+            latest_price: convert_price(self.latest_price),
+            price_current_revenue_growth: convert_price(self.price_current_revenue_growth),
+            price_current_gp_growth: convert_price(self.price_current_gp_growth),
+            price_next_year_revenue_growth: convert_price(self.price_next_year_revenue_growth),
+            price_multi_year_revenue_growth: convert_price(self.price_multi_year_revenue_growth),
+            price_multi_year_gp_growth: convert_price(self.price_multi_year_gp_growth),
+        });
+        self
+    }
+}
+
+/// absolute amounts converted into the currency requested by the client.
+/// amounts are left empty when no exchange rate is available.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertedAmounts {
+    currency: String,
+    rate: Option<f64>,
+    revenue_ttm: Option<f64>,
+    retained_earnings_ttm: Option<f64>,
+    net_cash_ttm: Option<f64>,
+    operating_cash_flow_ttm: Option<f64>,
+    free_cash_flow_ttm: Option<f64>,
+    revenue_next_year: Option<f64>,
+    latest_price: Option<f64>,
+    price_current_revenue_growth: Option<f64>,
+    price_current_gp_growth: Option<f64>,
+    price_next_year_revenue_growth: Option<f64>,
+    price_multi_year_revenue_growth: Option<f64>,
+    price_multi_year_gp_growth: Option<f64>,
 }
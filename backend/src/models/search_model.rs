@@ -0,0 +1,40 @@
+use crate::models::companies_model::Company;
+use serde::Serialize;
+
+/// listing suggested by the symbol search
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub ticker: String,
+    pub company_name: String,
+    pub isin: String,
+    pub exchange: String,
+    pub ingested: bool,
+}
+
+impl SearchResult {
+    pub fn new(
+        ticker: String,
+        company_name: String,
+        isin: String,
+        exchange: String,
+        ingested: bool,
+    ) -> Self {
+        SearchResult {
+            ticker,
+            company_name,
+            isin,
+            exchange,
+            ingested,
+        }
+    }
+    pub fn from_company(company: Company) -> Self {
+        SearchResult {
+            ticker: company.ticker.to_uppercase(),
+            company_name: company.company_name,
+            isin: company.isin,
+            exchange: company.exchange,
+            ingested: true,
+        }
+    }
+}
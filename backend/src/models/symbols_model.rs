@@ -0,0 +1,61 @@
+use crate::helper;
+use crate::schema::symbol_directory;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::upsert::excluded;
+use serde::Serialize;
+
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = symbol_directory)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolEntry {
+    id: i32,
+    pub ticker: String,
+    pub isin: Option<String>,
+    pub exchange: String,
+}
+
+impl SymbolEntry {
+    /// retrieves every listing matching the given ticker or ISIN, ignoring case
+    pub fn load_by_symbol(symbol: &str, conn: &mut PgConnection) -> Result<Vec<Self>, DieselError> {
+        use crate::schema::symbol_directory::dsl::*;
+        // tickers and ISINs are stored in upper case
+        let symbol = helper::normalize_ticker(symbol);
+        let target = symbol_directory
+            .filter(ticker.eq(&symbol).or(isin.eq(&symbol)))
+            .order(exchange.asc())
+            .load::<SymbolEntry>(conn)?;
+        Ok(target)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = symbol_directory)]
+pub struct NewSymbolEntry<'a> {
+    ticker: &'a str,
+    isin: Option<&'a str>,
+    exchange: &'a str,
+}
+
+impl<'a> NewSymbolEntry<'a> {
+    pub fn create_new_entry(ticker: &'a str, isin: Option<&'a str>, exchange: &'a str) -> Self {
+        NewSymbolEntry {
+            ticker,
+            isin,
+            exchange,
+        }
+    }
+    /// registers the listing or refreshes its ISIN
+    pub fn upsert(&self, conn: &mut PgConnection) -> Result<(), DieselError> {
+        use crate::schema::symbol_directory::dsl::*;
+        diesel::insert_into(symbol_directory)
+            .values(self)
+            .on_conflict((ticker, exchange))
+            .do_update()
+            .set(isin.eq(excluded(isin)))
+            .execute(conn)?;
+        Ok(())
+    }
+}
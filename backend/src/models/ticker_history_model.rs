@@ -0,0 +1,65 @@
+use crate::helper;
+use crate::schema::ticker_history;
+use chrono::{Local, NaiveDate};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::Serialize;
+
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = ticker_history)]
+#[serde(rename_all = "camelCase")]
+pub struct TickerHistory {
+    id: i32,
+    pub company_id: i32,
+    pub ticker: String,
+    pub exchange: String,
+    pub replaced_on: NaiveDate,
+}
+
+impl TickerHistory {
+    /// retrieves the company id that was most recently listed under the given symbol
+    pub fn load_company_id_if_existed(
+        prev_ticker: &str,
+        prev_exchange: &str,
+        conn: &mut PgConnection,
+    ) -> Result<Option<i32>, DieselError> {
+        use crate::schema::ticker_history::dsl::*;
+        let target = ticker_history
+            .filter(ticker.eq(helper::normalize_ticker(prev_ticker)))
+            .filter(exchange.eq(prev_exchange))
+            .order(replaced_on.desc())
+            .select(company_id)
+            .first::<i32>(conn)
+            .optional()?;
+        Ok(target)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = ticker_history)]
+pub struct NewTickerHistory<'a> {
+    company_id: i32,
+    ticker: &'a str,
+    exchange: &'a str,
+    replaced_on: NaiveDate,
+}
+
+impl<'a> NewTickerHistory<'a> {
+    pub fn create_new_entry(company_id: i32, ticker: &'a str, exchange: &'a str) -> Self {
+        NewTickerHistory {
+            company_id,
+            ticker,
+            exchange,
+            replaced_on: Local::now().date_naive(),
+        }
+    }
+    pub fn insert_new_history(&self, conn: &mut PgConnection) -> Result<(), DieselError> {
+        use crate::schema::ticker_history::dsl::*;
+        diesel::insert_into(ticker_history)
+            .values(self)
+            .execute(conn)?;
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use crate::models::earnings_model::EarningsReport;
-use crate::schema::{current_metrics, earnings_report, forecasts};
-use chrono::Local;
+use crate::schema::{companies, current_metrics, earnings_report, forecasts};
+use chrono::{Local, NaiveDate};
 use diesel::associations::HasTable;
 use diesel::helper_types::Limit;
 use diesel::pg::{Pg, PgConnection};
@@ -113,12 +113,118 @@ where
     diesel::update(table).set(updates).get_result(conn)
 }
 
-/// updates specific earnings data for the given earnings
+/// records that the ticker of the given company resolved today
 pub fn update_company_table(curr_id: i32, conn: &mut PgConnection) -> Result<usize, DieselError> {
     use crate::schema::companies::dsl::*;
     update_table(
         companies.filter(id.eq(curr_id)),
-        last_updated.eq(Local::now().date_naive()),
+        (
+            last_updated.eq(Local::now().date_naive()),
+            ticker_failures.eq(0),
+            last_ticker_failure.eq(None::<NaiveDate>),
+        ),
+        conn,
+    )
+}
+
+/// replaces the listed ticker and exchange for the given company
+pub fn update_company_listing(
+    curr_id: i32,
+    new_ticker: &str,
+    new_exchange: &str,
+    conn: &mut PgConnection,
+) -> Result<usize, DieselError> {
+    use crate::schema::companies::dsl::*;
+    update_table(
+        companies.filter(id.eq(curr_id)),
+        (
+            ticker.eq(new_ticker),
+            exchange.eq(new_exchange),
+            delisted.eq(false),
+            last_updated.eq(Local::now().date_naive()),
+            ticker_failures.eq(0),
+            last_ticker_failure.eq(None::<NaiveDate>),
+        ),
+        conn,
+    )
+}
+
+/// counts a failed ticker check of the given company, at most once a day.
+/// returns the number of days the ticker failed, or None when it already failed today.
+pub fn update_company_ticker_failure(
+    curr_id: i32,
+    conn: &mut PgConnection,
+) -> Result<Option<i32>, DieselError> {
+    use crate::schema::companies::dsl::*;
+    let today = Local::now().date_naive();
+    diesel::update(
+        companies.filter(id.eq(curr_id)).filter(
+            last_ticker_failure
+                .is_null()
+                .or(last_ticker_failure.lt(today)),
+        ),
+    )
+    .set((
+        ticker_failures.eq(ticker_failures + 1),
+        last_ticker_failure.eq(today),
+    ))
+    .returning(ticker_failures)
+    .get_result(conn)
+    .optional()
+}
+
+/// marks the given company as delisted
+pub fn update_company_delisted(
+    curr_id: i32,
+    conn: &mut PgConnection,
+) -> Result<usize, DieselError> {
+    use crate::schema::companies::dsl::*;
+    update_table(
+        companies.filter(id.eq(curr_id)),
+        (
+            delisted.eq(true),
+            last_updated.eq(Local::now().date_naive()),
+        ),
+        conn,
+    )
+}
+
+/// clears the delisted flag and the failed ticker checks of the given company
+pub fn update_company_relisted(
+    curr_id: i32,
+    conn: &mut PgConnection,
+) -> Result<usize, DieselError> {
+    use crate::schema::companies::dsl::*;
+    update_table(
+        companies.filter(id.eq(curr_id)),
+        (
+            delisted.eq(false),
+            ticker_failures.eq(0),
+            last_ticker_failure.eq(None::<NaiveDate>),
+        ),
+        conn,
+    )
+}
+
+/// removes the given company with its earnings, metrics and forecast rows.
+/// other tables referencing the company are cleared by cascading deletes.
+pub fn delete_company(curr_id: i32, conn: &mut PgConnection) -> Result<usize, DieselError> {
+    conn.transaction(|conn| {
+        diesel::delete(earnings_report::table.filter(earnings_report::company_id.eq(curr_id)))
+            .execute(conn)?;
+        diesel::delete(current_metrics::table.filter(current_metrics::company_id.eq(curr_id)))
+            .execute(conn)?;
+        diesel::delete(forecasts::table.filter(forecasts::company_id.eq(curr_id))).execute(conn)?;
+        diesel::delete(companies::table.filter(companies::id.eq(curr_id))).execute(conn)
+    })
+}
+
+/// marks all earnings data for ratio and growth recalculation
+pub fn reset_calculated_flags(conn: &mut PgConnection) -> Result<usize, DieselError> {
+    use crate::schema::earnings_report::dsl::*;
+    update_table(
+        earnings_report,
+        (ratio_calculated.eq(false), growth_calculated.eq(false)),
         conn,
     )
 }
@@ -1,5 +1,16 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    backfill_requests (id) {
+        id -> Int4,
+        company_id -> Int4,
+        period -> Text,
+        attempts -> Int4,
+        requested_on -> Date,
+        last_attempted -> Nullable<Date>,
+    }
+}
+
 diesel::table! {
     companies (id) {
         id -> Int4,
@@ -14,6 +25,19 @@ diesel::table! {
         #[max_length = 6]
         ticker -> Varchar,
         last_updated -> Date,
+        delisted -> Bool,
+        ticker_failures -> Int4,
+        last_ticker_failure -> Nullable<Date>,
+    }
+}
+
+diesel::table! {
+    corporate_actions (id) {
+        id -> Int4,
+        company_id -> Int4,
+        action_type -> Text,
+        ratio -> Float8,
+        effective_date -> Date,
     }
 }
 
@@ -68,6 +92,31 @@ diesel::table! {
         free_cash_flow_margin_ttm -> Nullable<Float8>,
         ffo_margin_ttm -> Nullable<Float8>,
         ffo_margin_trend -> Nullable<Text>,
+        piotroski_f_score -> Nullable<Int2>,
+        altman_z_score -> Nullable<Float8>,
+        premiums_growth_yoy_ttm -> Nullable<Float8>,
+        premiums_growth_multi_year -> Nullable<Float8>,
+        loss_ratio_ttm -> Nullable<Float8>,
+        loss_ratio_short_term_trend -> Nullable<Text>,
+        loss_ratio_long_term_trend -> Nullable<Text>,
+        combined_ratio_ttm -> Nullable<Float8>,
+        combined_ratio_short_term_trend -> Nullable<Text>,
+        combined_ratio_long_term_trend -> Nullable<Text>,
+        calculation_version -> Int4,
+        pe_ratio_percentile -> Nullable<Float8>,
+        ps_ratio_percentile -> Nullable<Float8>,
+    }
+}
+
+diesel::table! {
+    data_quality_issues (id) {
+        id -> Int4,
+        company_id -> Int4,
+        earnings_id -> Int4,
+        field -> Text,
+        computed_value -> Nullable<Float8>,
+        reported_value -> Nullable<Float8>,
+        detected_on -> Date,
     }
 }
 
@@ -102,14 +151,14 @@ diesel::table! {
         operating_margin -> Float8,
         interest_expenses -> Nullable<Float8>,
         interest_expenses_op_income_ratio -> Nullable<Float8>,
-        goodwill_impairment -> Float8,
+        goodwill_impairment -> Nullable<Float8>,
         net_income -> Float8,
         net_margin -> Float8,
         eps_basic -> Float8,
         eps_diluted -> Float8,
         shares_outstanding_basic -> Float8,
         shares_outstanding_diluted -> Float8,
-        shares_change_yoy -> Float8,
+        shares_change_yoy -> Nullable<Float8>,
         ffo -> Nullable<Float8>,
         ffo_margin -> Nullable<Float8>,
         cash_and_equivalents -> Float8,
@@ -139,6 +188,16 @@ diesel::table! {
         free_cash_flow_margin -> Nullable<Float8>,
         ratio_calculated -> Bool,
         growth_calculated -> Bool,
+        premiums_earned -> Nullable<Float8>,
+        premiums_growth_yoy -> Nullable<Float8>,
+        policy_benefits -> Nullable<Float8>,
+        underwriting_expenses -> Nullable<Float8>,
+        loss_ratio -> Nullable<Float8>,
+        combined_ratio -> Nullable<Float8>,
+        split_factor -> Float8,
+        calculation_version -> Int4,
+        reported_revenue_growth_yoy -> Nullable<Float8>,
+        reported_shares_change_yoy -> Nullable<Float8>,
     }
 }
 
@@ -156,16 +215,131 @@ diesel::table! {
         price_next_year_revenue_growth -> Nullable<Float8>,
         price_multi_year_revenue_growth -> Nullable<Float8>,
         price_multi_year_gp_growth -> Nullable<Float8>,
+        pe_ratio -> Nullable<Float8>,
+        ps_ratio -> Nullable<Float8>,
+        pfcf_ratio -> Nullable<Float8>,
+        ev_ebit -> Nullable<Float8>,
+        ev_sales -> Nullable<Float8>,
+        #[max_length = 3]
+        trading_currency -> Nullable<Varchar>,
+        price_target_available -> Nullable<Bool>,
+    }
+}
+
+diesel::table! {
+    fx_rates (id) {
+        id -> Int4,
+        #[max_length = 3]
+        base_currency -> Varchar,
+        #[max_length = 3]
+        quote_currency -> Varchar,
+        rate -> Float8,
+        last_updated -> Date,
+    }
+}
+
+diesel::table! {
+    ingest_issues (id) {
+        id -> Int4,
+        company_id -> Int4,
+        period -> Text,
+        check_name -> Text,
+        severity -> Text,
+        message -> Text,
+        detected_on -> Date,
+        accepted -> Bool,
+    }
+}
+
+diesel::table! {
+    peer_ranks (id) {
+        id -> Int4,
+        company_id -> Int4,
+        #[max_length = 50]
+        industry -> Varchar,
+        peer_count -> Int4,
+        gross_margin_rank -> Nullable<Float8>,
+        operating_margin_rank -> Nullable<Float8>,
+        revenue_growth_yoy_rank -> Nullable<Float8>,
+        revenue_growth_multi_year_rank -> Nullable<Float8>,
+        free_cash_flow_margin_rank -> Nullable<Float8>,
+        shares_change_rank -> Nullable<Float8>,
+        last_updated -> Date,
+    }
+}
+
+diesel::table! {
+    price_history (id) {
+        id -> Int4,
+        company_id -> Int4,
+        price_date -> Date,
+        close -> Float8,
+        open -> Nullable<Float8>,
+        high -> Nullable<Float8>,
+        low -> Nullable<Float8>,
+        volume -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    red_flags (id) {
+        id -> Int4,
+        company_id -> Int4,
+        flag -> Text,
+        detected_on -> Date,
+    }
+}
+
+diesel::table! {
+    symbol_directory (id) {
+        id -> Int4,
+        #[max_length = 6]
+        ticker -> Varchar,
+        #[max_length = 12]
+        isin -> Nullable<Varchar>,
+        #[max_length = 9]
+        exchange -> Varchar,
+    }
+}
+
+diesel::table! {
+    ticker_history (id) {
+        id -> Int4,
+        company_id -> Int4,
+        #[max_length = 6]
+        ticker -> Varchar,
+        #[max_length = 9]
+        exchange -> Varchar,
+        replaced_on -> Date,
     }
 }
 
+diesel::joinable!(backfill_requests -> companies (company_id));
+diesel::joinable!(corporate_actions -> companies (company_id));
 diesel::joinable!(current_metrics -> companies (company_id));
+diesel::joinable!(data_quality_issues -> companies (company_id));
+diesel::joinable!(data_quality_issues -> earnings_report (earnings_id));
 diesel::joinable!(earnings_report -> companies (company_id));
 diesel::joinable!(forecasts -> companies (company_id));
+diesel::joinable!(ingest_issues -> companies (company_id));
+diesel::joinable!(peer_ranks -> companies (company_id));
+diesel::joinable!(price_history -> companies (company_id));
+diesel::joinable!(red_flags -> companies (company_id));
+diesel::joinable!(ticker_history -> companies (company_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    backfill_requests,
     companies,
+    corporate_actions,
     current_metrics,
+    data_quality_issues,
     earnings_report,
     forecasts,
+    fx_rates,
+    ingest_issues,
+    peer_ranks,
+    price_history,
+    red_flags,
+    symbol_directory,
+    ticker_history,
 );
@@ -0,0 +1,138 @@
+use crate::models::companies_model::Company;
+use crate::models::search_model::SearchResult;
+use std::env;
+use std::fs;
+use std::sync::OnceLock;
+
+const MAX_RESULTS: usize = 10;
+
+static DIRECTORY_FILE_ENTRIES: OnceLock<Vec<SearchResult>> = OnceLock::new();
+
+/// loads listings from SYMBOL_DIRECTORY_FILE once.
+/// each line holds "ticker,exchange,isin,company name" and malformed lines are skipped.
+fn directory_file_entries() -> &'static [SearchResult] {
+    DIRECTORY_FILE_ENTRIES.get_or_init(|| {
+        let content = env::var("SYMBOL_DIRECTORY_FILE")
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        content
+            .lines()
+            .filter_map(|line| {
+                let mut columns = line.splitn(4, ',').map(str::trim);
+                let ticker = columns.next().filter(|val| !val.is_empty())?;
+                let exchange = columns.next().filter(|val| !val.is_empty())?;
+                let isin = columns.next()?;
+                let company_name = columns.next()?;
+                Some(SearchResult::new(
+                    ticker.to_uppercase(),
+                    company_name.to_string(),
+                    isin.to_uppercase(),
+                    exchange.to_uppercase(),
+                    false,
+                ))
+            })
+            .collect()
+    })
+}
+
+/// levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut prev_row: Vec<usize> = (0..=b_chars.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut curr_row = vec![i + 1; b_chars.len() + 1];
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution = prev_row[j] + usize::from(a_char != *b_char);
+            curr_row[j + 1] = substitution.min(prev_row[j + 1] + 1).min(curr_row[j] + 1);
+        }
+        prev_row = curr_row;
+    }
+    prev_row[b_chars.len()]
+}
+
+/// typos allowed for fuzzy matching, scaled by the query length
+fn allowed_typos(query: &str) -> usize {
+    match query.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+/// scores how well the listing matches the query. higher is better.
+/// exact matches rank first, then prefix matches, substring matches and typo-tolerant matches.
+fn match_score(query: &str, candidate: &SearchResult) -> Option<u32> {
+    let ticker = candidate.ticker.to_lowercase();
+    let name = candidate.company_name.to_lowercase();
+    let isin = candidate.isin.to_lowercase();
+    if ticker == query || isin == query {
+        return Some(100);
+    }
+    if ticker.starts_with(query) {
+        return Some(80);
+    }
+    if name.starts_with(query) {
+        return Some(70);
+    }
+    if name.split_whitespace().any(|word| word.starts_with(query)) {
+        return Some(60);
+    }
+    if query.len() >= 3 && (name.contains(query) || isin.starts_with(query)) {
+        return Some(50);
+    }
+    let typos = allowed_typos(query);
+    if typos == 0 {
+        return None;
+    }
+    let ticker_distance = edit_distance(query, &ticker);
+    let name_distance = name
+        .split_whitespace()
+        .map(|word| edit_distance(query, word))
+        .min()
+        .unwrap_or(usize::MAX);
+    let distance = ticker_distance.min(name_distance);
+    if distance <= typos {
+        Some(40 - 10 * distance as u32)
+    } else {
+        None
+    }
+}
+
+/// ranks ingested companies prefiltered by the database and directory file listings against
+/// the query. listings already ingested take precedence over the same listing in the file.
+pub fn rank_candidates(query: &str, companies: Vec<Company>) -> Vec<SearchResult> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let mut candidates: Vec<SearchResult> = companies
+        .into_iter()
+        .filter(|company| !company.delisted)
+        .map(SearchResult::from_company)
+        .collect();
+    for entry in directory_file_entries() {
+        let already_ingested = candidates.iter().any(|candidate| {
+            candidate.ticker.eq_ignore_ascii_case(&entry.ticker)
+                && candidate.exchange.eq_ignore_ascii_case(&entry.exchange)
+        });
+        if !already_ingested {
+            candidates.push(entry.clone());
+        }
+    }
+    let mut scored: Vec<(u32, SearchResult)> = candidates
+        .into_iter()
+        .filter_map(|candidate| match_score(&query, &candidate).map(|score| (score, candidate)))
+        .collect();
+    scored.sort_by(|(score_a, a), (score_b, b)| {
+        score_b
+            .cmp(score_a)
+            .then_with(|| b.ingested.cmp(&a.ingested))
+            .then_with(|| a.ticker.cmp(&b.ticker))
+    });
+    scored
+        .into_iter()
+        .take(MAX_RESULTS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
@@ -0,0 +1,810 @@
+//! Reads SEC companyfacts files (data.sec.gov/api/xbrl/companyfacts/CIK##########.json)
+//! downloaded ahead of time into SEC_FACTS_DIR. The directory also holds
+//! company_tickers_exchange.json, which maps tickers to CIK numbers and is read once per process.
+
+use crate::calculate;
+use crate::errors::BullsEyeError;
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const TICKER_MAP_FILE: &str = "company_tickers_exchange.json";
+
+/// CIK numbers by upper case ticker, read from TICKER_MAP_FILE on the first lookup
+static CIK_BY_TICKER: OnceLock<HashMap<String, u64>> = OnceLock::new();
+
+// us-gaap concepts for each field, in order of preference
+const REVENUE: &[&str] = &[
+    "Revenues",
+    "RevenueFromContractWithCustomerExcludingAssessedTax",
+    "RevenueFromContractWithCustomerIncludingAssessedTax",
+    "SalesRevenueNet",
+];
+const COST_OF_REVENUE: &[&str] = &[
+    "CostOfRevenue",
+    "CostOfGoodsAndServicesSold",
+    "CostOfGoodsSold",
+];
+const GROSS_PROFIT: &[&str] = &["GrossProfit"];
+const SGA_EXPENSES: &[&str] = &["SellingGeneralAndAdministrativeExpense"];
+const RND_EXPENSES: &[&str] = &["ResearchAndDevelopmentExpense"];
+const OPERATING_EXPENSES: &[&str] = &["OperatingExpenses"];
+const OPERATING_INCOME: &[&str] = &["OperatingIncomeLoss"];
+const INTEREST_EXPENSES: &[&str] = &["InterestExpense", "InterestExpenseNonoperating"];
+const GOODWILL_IMPAIRMENT: &[&str] = &["GoodwillImpairmentLoss"];
+const NET_INCOME: &[&str] = &["NetIncomeLoss", "ProfitLoss"];
+const EPS_BASIC: &[&str] = &["EarningsPerShareBasic"];
+const EPS_DILUTED: &[&str] = &["EarningsPerShareDiluted"];
+const SHARES_BASIC: &[&str] = &["WeightedAverageNumberOfSharesOutstandingBasic"];
+const SHARES_DILUTED: &[&str] = &["WeightedAverageNumberOfDilutedSharesOutstanding"];
+const CASH: &[&str] = &[
+    "CashAndCashEquivalentsAtCarryingValue",
+    "CashCashEquivalentsRestrictedCashAndRestrictedCashEquivalents",
+];
+const SHORT_TERM_INVESTMENTS: &[&str] = &[
+    "ShortTermInvestments",
+    "MarketableSecuritiesCurrent",
+    "AvailableForSaleSecuritiesDebtSecuritiesCurrent",
+];
+const ACCOUNTS_RECEIVABLE: &[&str] = &["AccountsReceivableNetCurrent"];
+const INVENTORY: &[&str] = &["InventoryNet"];
+const CURRENT_ASSETS: &[&str] = &["AssetsCurrent"];
+const GOODWILL: &[&str] = &["Goodwill"];
+const TOTAL_ASSETS: &[&str] = &["Assets"];
+const ACCOUNTS_PAYABLE: &[&str] = &["AccountsPayableCurrent"];
+const CURRENT_LIABILITIES: &[&str] = &["LiabilitiesCurrent"];
+const TOTAL_LIABILITIES: &[&str] = &["Liabilities"];
+const LIABILITIES_AND_EQUITY: &[&str] = &["LiabilitiesAndStockholdersEquity"];
+const RETAINED_EARNINGS: &[&str] = &["RetainedEarningsAccumulatedDeficit"];
+const SHAREHOLDERS_EQUITY: &[&str] = &[
+    "StockholdersEquity",
+    "StockholdersEquityIncludingPortionAttributableToNoncontrollingInterest",
+];
+const LONG_TERM_DEBT: &[&str] = &["LongTermDebt"];
+const LONG_TERM_DEBT_CURRENT: &[&str] = &["LongTermDebtCurrent"];
+const LONG_TERM_DEBT_NONCURRENT: &[&str] = &["LongTermDebtNoncurrent"];
+const SHORT_TERM_BORROWINGS: &[&str] = &["ShortTermBorrowings", "CommercialPaper"];
+const DEPRECIATION: &[&str] = &[
+    "DepreciationDepletionAndAmortization",
+    "DepreciationAmortizationAndAccretionNet",
+    "DepreciationAndAmortization",
+];
+const STOCK_COMPENSATION: &[&str] = &["ShareBasedCompensation"];
+const OPERATING_CASH_FLOW: &[&str] = &[
+    "NetCashProvidedByUsedInOperatingActivities",
+    "NetCashProvidedByUsedInOperatingActivitiesContinuingOperations",
+];
+const CAPITAL_EXPENDITURE: &[&str] = &["PaymentsToAcquirePropertyPlantAndEquipment"];
+const INVESTING_CASH_FLOW: &[&str] = &[
+    "NetCashProvidedByUsedInInvestingActivities",
+    "NetCashProvidedByUsedInInvestingActivitiesContinuingOperations",
+];
+const FINANCING_CASH_FLOW: &[&str] = &[
+    "NetCashProvidedByUsedInFinancingActivities",
+    "NetCashProvidedByUsedInFinancingActivitiesContinuingOperations",
+];
+
+#[derive(Deserialize)]
+struct CompanyFacts {
+    facts: HashMap<String, HashMap<String, Concept>>,
+}
+
+#[derive(Deserialize)]
+struct Concept {
+    units: HashMap<String, Vec<Fact>>,
+}
+
+#[derive(Deserialize, Clone)]
+struct Fact {
+    start: Option<NaiveDate>,
+    end: NaiveDate,
+    val: f64,
+    filed: NaiveDate,
+}
+
+/// values of one field, merged from its concepts and keyed by (start, end)
+struct FactSeries {
+    facts: BTreeMap<(Option<NaiveDate>, NaiveDate), f64>,
+}
+
+impl FactSeries {
+    /// earlier concepts win over later ones; the latest filing wins within a concept
+    fn new(us_gaap: &HashMap<String, Concept>, concepts: &[&str], unit: &str) -> Self {
+        let mut facts = BTreeMap::new();
+        for concept in concepts {
+            let Some(values) = us_gaap.get(*concept).and_then(|c| c.units.get(unit)) else {
+                continue;
+            };
+            let mut latest: BTreeMap<(Option<NaiveDate>, NaiveDate), &Fact> = BTreeMap::new();
+            for fact in values {
+                let key = (fact.start, fact.end);
+                match latest.get(&key) {
+                    Some(existing) if existing.filed >= fact.filed => {}
+                    _ => {
+                        latest.insert(key, fact);
+                    }
+                }
+            }
+            for (key, fact) in latest {
+                facts.entry(key).or_insert(fact.val);
+            }
+        }
+        FactSeries { facts }
+    }
+
+    /// value reported over the given period, tolerating 52/53-week calendars
+    fn duration(&self, start: NaiveDate, end: NaiveDate) -> Option<f64> {
+        self.facts.iter().find_map(|((fact_start, fact_end), val)| {
+            let is_match =
+                *fact_end == end && fact_start.is_some_and(|s| (s - start).num_days().abs() <= 10);
+            is_match.then_some(*val)
+        })
+    }
+
+    /// balance sheet value at the given date
+    fn instant(&self, end: NaiveDate) -> Option<f64> {
+        self.facts.get(&(None, end)).copied()
+    }
+
+    /// value for a single quarter. cash flows are only reported year-to-date in 10-Q filings
+    /// and Q4 only as part of the fiscal year, so those are derived by subtraction.
+    fn quarter(&self, quarter: &FiscalQuarter) -> Option<f64> {
+        self.duration(quarter.start, quarter.end).or_else(|| {
+            let ytd = self.duration(quarter.fiscal_year_start, quarter.end)?;
+            match quarter.prev_end {
+                Some(prev_end) => Some(ytd - self.duration(quarter.fiscal_year_start, prev_end)?),
+                None => Some(ytd),
+            }
+        })
+    }
+
+    /// weighted average share counts aren't additive, so Q4 falls back to the fiscal year
+    fn average(&self, quarter: &FiscalQuarter) -> Option<f64> {
+        self.duration(quarter.start, quarter.end)
+            .or_else(|| self.duration(quarter.fiscal_year_start, quarter.end))
+    }
+
+    /// every (start, end) reported for the field
+    fn periods(&self) -> impl Iterator<Item = (NaiveDate, NaiveDate)> + '_ {
+        self.facts
+            .keys()
+            .filter_map(|(start, end)| start.map(|s| (s, *end)))
+    }
+}
+
+struct FiscalYear {
+    start: NaiveDate,
+    /// None for the current year, which has no annual report yet
+    end: Option<NaiveDate>,
+    year: i16,
+}
+
+struct FiscalQuarter {
+    fiscal_year_start: NaiveDate,
+    start: NaiveDate,
+    end: NaiveDate,
+    prev_end: Option<NaiveDate>,
+    year: i16,
+    quarter: i16,
+}
+
+/// fiscal years are named after the calendar year they end in.
+/// 52/53-week years ending in the first days of January belong to the previous year.
+fn fiscal_year_label(end: NaiveDate) -> i16 {
+    if end.month() == 1 && end.day() <= 7 {
+        (end.year() - 1) as i16
+    } else {
+        end.year() as i16
+    }
+}
+
+/// fiscal years covered by an annual report, followed by the current year
+/// whose quarters are only reported in 10-Q filings so far
+fn fiscal_years(anchors: &[&FactSeries]) -> Vec<FiscalYear> {
+    let mut years: BTreeMap<i16, FiscalYear> = BTreeMap::new();
+    for (start, end) in anchors.iter().flat_map(|series| series.periods()) {
+        if (350..=380).contains(&(end - start).num_days()) {
+            let year = fiscal_year_label(end);
+            years.entry(year).or_insert(FiscalYear {
+                start,
+                end: Some(end),
+                year,
+            });
+        }
+    }
+    let current_year = years
+        .values()
+        .next_back()
+        .and_then(|latest| latest.end?.succ_opt())
+        .map(|start| FiscalYear {
+            start,
+            end: None,
+            year: fiscal_year_label(start + Duration::days(364)),
+        });
+    years.into_values().chain(current_year).collect()
+}
+
+fn fiscal_quarters(fiscal_year: &FiscalYear, anchors: &[&FactSeries]) -> Vec<FiscalQuarter> {
+    let mut ends: BTreeMap<i16, NaiveDate> = BTreeMap::new();
+    if let Some(end) = fiscal_year.end {
+        ends.insert(4, end);
+    }
+    // the current year ends at the latest after the longest 53-week year
+    let year_end = fiscal_year
+        .end
+        .unwrap_or(fiscal_year.start + Duration::days(380));
+    for (start, end) in anchors.iter().flat_map(|series| series.periods()) {
+        if end <= fiscal_year.start || end >= year_end {
+            continue;
+        }
+        let length = (end - start).num_days();
+        let is_ytd = (start - fiscal_year.start).num_days().abs() <= 10 && length < 300;
+        let is_quarter = (80..=100).contains(&length);
+        if !is_ytd && !is_quarter {
+            continue;
+        }
+        let quarter = ((end - fiscal_year.start).num_days() as f64 / 91.3).round() as i16;
+        if (1..=3).contains(&quarter) {
+            ends.entry(quarter).or_insert(end);
+        }
+    }
+    let mut quarters = Vec::with_capacity(4);
+    let mut prev_end: Option<NaiveDate> = None;
+    for (quarter, end) in ends {
+        // a missing quarter would make the subtraction span two quarters
+        if quarter != quarters.len() as i16 + 1 {
+            break;
+        }
+        let start = match prev_end {
+            Some(prev) => prev.succ_opt().unwrap_or(prev),
+            None => fiscal_year.start,
+        };
+        quarters.push(FiscalQuarter {
+            fiscal_year_start: fiscal_year.start,
+            start,
+            end,
+            prev_end,
+            year: fiscal_year.year,
+            quarter,
+        });
+        prev_end = Some(end);
+    }
+    quarters
+}
+
+/// statement derived from companyfacts, in the units used by the scraper
+pub struct SecStatement {
+    pub term: String,
+    pub quarter: i16,
+    pub year: i16,
+    pub period_ending: NaiveDate,
+    pub revenue: Option<f64>,
+    pub cost_of_revenue: Option<f64>,
+    pub gross_profit: Option<f64>,
+    pub sga_expenses: Option<f64>,
+    pub rnd_expenses: Option<f64>,
+    pub operating_expenses: Option<f64>,
+    pub operating_income: Option<f64>,
+    pub interest_expenses: Option<f64>,
+    pub goodwill_impairment: Option<f64>,
+    pub net_income: Option<f64>,
+    pub eps_basic: Option<f64>,
+    pub eps_diluted: Option<f64>,
+    pub shares_outstanding_basic: Option<f64>,
+    pub shares_outstanding_diluted: Option<f64>,
+    pub shares_change_yoy: Option<f64>,
+    pub cash_and_equivalents: Option<f64>,
+    pub cash_and_short_term_investments: Option<f64>,
+    pub accounts_receivable: Option<f64>,
+    pub inventory: Option<f64>,
+    pub total_current_assets: Option<f64>,
+    pub goodwill: Option<f64>,
+    pub total_assets: Option<f64>,
+    pub accounts_payable: Option<f64>,
+    pub total_current_liabilities: Option<f64>,
+    pub total_liabilities: Option<f64>,
+    pub retained_earnings: Option<f64>,
+    pub shareholders_equity: Option<f64>,
+    pub total_debt: Option<f64>,
+    pub depreciation_and_amortization: Option<f64>,
+    pub stock_based_compensation: Option<f64>,
+    pub operating_cash_flow: Option<f64>,
+    pub capital_expenditure: Option<f64>,
+    pub investing_cash_flow: Option<f64>,
+    pub financing_cash_flow: Option<f64>,
+}
+
+/// flow fields of a statement, read either for a whole period or summed over quarters
+struct Flows {
+    revenue: Option<f64>,
+    cost_of_revenue: Option<f64>,
+    gross_profit: Option<f64>,
+    sga_expenses: Option<f64>,
+    rnd_expenses: Option<f64>,
+    operating_expenses: Option<f64>,
+    operating_income: Option<f64>,
+    interest_expenses: Option<f64>,
+    goodwill_impairment: Option<f64>,
+    net_income: Option<f64>,
+    depreciation_and_amortization: Option<f64>,
+    stock_based_compensation: Option<f64>,
+    operating_cash_flow: Option<f64>,
+    capital_expenditure: Option<f64>,
+    investing_cash_flow: Option<f64>,
+    financing_cash_flow: Option<f64>,
+}
+
+/// share counts and EPS of a statement. EPS is derived from net income when not reported.
+struct PerShare {
+    shares_basic: Option<f64>,
+    shares_diluted: Option<f64>,
+    eps_basic: Option<f64>,
+    eps_diluted: Option<f64>,
+}
+
+struct Parsed {
+    revenue: FactSeries,
+    cost_of_revenue: FactSeries,
+    gross_profit: FactSeries,
+    sga_expenses: FactSeries,
+    rnd_expenses: FactSeries,
+    operating_expenses: FactSeries,
+    operating_income: FactSeries,
+    interest_expenses: FactSeries,
+    goodwill_impairment: FactSeries,
+    net_income: FactSeries,
+    eps_basic: FactSeries,
+    eps_diluted: FactSeries,
+    shares_basic: FactSeries,
+    shares_diluted: FactSeries,
+    cash: FactSeries,
+    short_term_investments: FactSeries,
+    accounts_receivable: FactSeries,
+    inventory: FactSeries,
+    current_assets: FactSeries,
+    goodwill: FactSeries,
+    total_assets: FactSeries,
+    accounts_payable: FactSeries,
+    current_liabilities: FactSeries,
+    total_liabilities: FactSeries,
+    liabilities_and_equity: FactSeries,
+    retained_earnings: FactSeries,
+    shareholders_equity: FactSeries,
+    long_term_debt: FactSeries,
+    long_term_debt_current: FactSeries,
+    long_term_debt_noncurrent: FactSeries,
+    short_term_borrowings: FactSeries,
+    depreciation: FactSeries,
+    stock_compensation: FactSeries,
+    operating_cash_flow: FactSeries,
+    capital_expenditure: FactSeries,
+    investing_cash_flow: FactSeries,
+    financing_cash_flow: FactSeries,
+}
+
+fn sum_options(vals: &[Option<f64>]) -> Option<f64> {
+    vals.iter().copied().sum()
+}
+
+impl Parsed {
+    fn new(us_gaap: &HashMap<String, Concept>) -> Self {
+        let usd = |concepts: &[&str]| FactSeries::new(us_gaap, concepts, "USD");
+        let shares = |concepts: &[&str]| FactSeries::new(us_gaap, concepts, "shares");
+        let per_share = |concepts: &[&str]| FactSeries::new(us_gaap, concepts, "USD/shares");
+        Parsed {
+            revenue: usd(REVENUE),
+            cost_of_revenue: usd(COST_OF_REVENUE),
+            gross_profit: usd(GROSS_PROFIT),
+            sga_expenses: usd(SGA_EXPENSES),
+            rnd_expenses: usd(RND_EXPENSES),
+            operating_expenses: usd(OPERATING_EXPENSES),
+            operating_income: usd(OPERATING_INCOME),
+            interest_expenses: usd(INTEREST_EXPENSES),
+            goodwill_impairment: usd(GOODWILL_IMPAIRMENT),
+            net_income: usd(NET_INCOME),
+            eps_basic: per_share(EPS_BASIC),
+            eps_diluted: per_share(EPS_DILUTED),
+            shares_basic: shares(SHARES_BASIC),
+            shares_diluted: shares(SHARES_DILUTED),
+            cash: usd(CASH),
+            short_term_investments: usd(SHORT_TERM_INVESTMENTS),
+            accounts_receivable: usd(ACCOUNTS_RECEIVABLE),
+            inventory: usd(INVENTORY),
+            current_assets: usd(CURRENT_ASSETS),
+            goodwill: usd(GOODWILL),
+            total_assets: usd(TOTAL_ASSETS),
+            accounts_payable: usd(ACCOUNTS_PAYABLE),
+            current_liabilities: usd(CURRENT_LIABILITIES),
+            total_liabilities: usd(TOTAL_LIABILITIES),
+            liabilities_and_equity: usd(LIABILITIES_AND_EQUITY),
+            retained_earnings: usd(RETAINED_EARNINGS),
+            shareholders_equity: usd(SHAREHOLDERS_EQUITY),
+            long_term_debt: usd(LONG_TERM_DEBT),
+            long_term_debt_current: usd(LONG_TERM_DEBT_CURRENT),
+            long_term_debt_noncurrent: usd(LONG_TERM_DEBT_NONCURRENT),
+            short_term_borrowings: usd(SHORT_TERM_BORROWINGS),
+            depreciation: usd(DEPRECIATION),
+            stock_compensation: usd(STOCK_COMPENSATION),
+            operating_cash_flow: usd(OPERATING_CASH_FLOW),
+            capital_expenditure: usd(CAPITAL_EXPENDITURE),
+            investing_cash_flow: usd(INVESTING_CASH_FLOW),
+            financing_cash_flow: usd(FINANCING_CASH_FLOW),
+        }
+    }
+
+    fn flows(&self, read: impl Fn(&FactSeries) -> Option<f64>) -> Flows {
+        Flows {
+            revenue: read(&self.revenue),
+            cost_of_revenue: read(&self.cost_of_revenue),
+            gross_profit: read(&self.gross_profit),
+            sga_expenses: read(&self.sga_expenses),
+            rnd_expenses: read(&self.rnd_expenses),
+            operating_expenses: read(&self.operating_expenses),
+            operating_income: read(&self.operating_income),
+            interest_expenses: read(&self.interest_expenses),
+            goodwill_impairment: read(&self.goodwill_impairment),
+            net_income: read(&self.net_income),
+            depreciation_and_amortization: read(&self.depreciation),
+            stock_based_compensation: read(&self.stock_compensation),
+            operating_cash_flow: read(&self.operating_cash_flow),
+            capital_expenditure: read(&self.capital_expenditure),
+            investing_cash_flow: read(&self.investing_cash_flow),
+            financing_cash_flow: read(&self.financing_cash_flow),
+        }
+    }
+
+    /// sums the flows of four consecutive quarters
+    fn trailing_flows(&self, quarters: &[&FiscalQuarter]) -> Flows {
+        self.flows(|series| {
+            let vals: Vec<Option<f64>> = quarters.iter().map(|q| series.quarter(q)).collect();
+            sum_options(&vals)
+        })
+    }
+
+    fn statement(
+        &self,
+        term: &str,
+        year: i16,
+        quarter: i16,
+        end: NaiveDate,
+        flows: Flows,
+        per_share: PerShare,
+    ) -> SecStatement {
+        let PerShare {
+            shares_basic,
+            shares_diluted,
+            eps_basic,
+            eps_diluted,
+        } = per_share;
+        let eps_basic =
+            eps_basic.or_else(|| calculate::calculate_ratio_option(flows.net_income, shares_basic));
+        let eps_diluted = eps_diluted
+            .or_else(|| calculate::calculate_ratio_option(flows.net_income, shares_diluted));
+        let cash = self.cash.instant(end);
+        let short_term_investments = self.short_term_investments.instant(end);
+        let shareholders_equity = self.shareholders_equity.instant(end);
+        let total_liabilities = self.total_liabilities.instant(end).or_else(|| {
+            self.liabilities_and_equity
+                .instant(end)
+                .zip(shareholders_equity)
+                .map(|(total, equity)| total - equity)
+        });
+        let long_term_debt = self.long_term_debt.instant(end).or_else(|| {
+            let current = self.long_term_debt_current.instant(end);
+            let noncurrent = self.long_term_debt_noncurrent.instant(end);
+            match (current, noncurrent) {
+                (None, None) => None,
+                _ => Some(current.unwrap_or(0.) + noncurrent.unwrap_or(0.)),
+            }
+        });
+        let total_debt = match (long_term_debt, self.short_term_borrowings.instant(end)) {
+            (None, None) => None,
+            (debt, borrowings) => Some(debt.unwrap_or(0.) + borrowings.unwrap_or(0.)),
+        };
+        let gross_profit = flows.gross_profit.or_else(|| {
+            flows
+                .revenue
+                .zip(flows.cost_of_revenue)
+                .map(|(rev, cost)| rev - cost)
+        });
+        let operating_expenses = flows.operating_expenses.or_else(|| {
+            gross_profit
+                .or(flows.revenue)
+                .zip(flows.operating_income)
+                .map(|(profit, income)| profit - income)
+        });
+        SecStatement {
+            term: term.to_string(),
+            quarter,
+            year,
+            period_ending: end,
+            revenue: flows.revenue,
+            cost_of_revenue: flows.cost_of_revenue,
+            gross_profit,
+            sga_expenses: flows.sga_expenses,
+            rnd_expenses: flows.rnd_expenses,
+            operating_expenses,
+            operating_income: flows.operating_income,
+            interest_expenses: flows.interest_expenses,
+            goodwill_impairment: flows.goodwill_impairment,
+            net_income: flows.net_income,
+            eps_basic,
+            eps_diluted,
+            shares_outstanding_basic: shares_basic,
+            shares_outstanding_diluted: shares_diluted,
+            shares_change_yoy: None,
+            cash_and_equivalents: cash,
+            cash_and_short_term_investments: cash
+                .map(|val| val + short_term_investments.unwrap_or(0.)),
+            accounts_receivable: self.accounts_receivable.instant(end),
+            inventory: self.inventory.instant(end),
+            total_current_assets: self.current_assets.instant(end),
+            goodwill: self.goodwill.instant(end),
+            total_assets: self.total_assets.instant(end),
+            accounts_payable: self.accounts_payable.instant(end),
+            total_current_liabilities: self.current_liabilities.instant(end),
+            total_liabilities,
+            retained_earnings: self.retained_earnings.instant(end),
+            shareholders_equity,
+            total_debt,
+            depreciation_and_amortization: flows.depreciation_and_amortization,
+            stock_based_compensation: flows.stock_based_compensation,
+            operating_cash_flow: flows.operating_cash_flow,
+            // the scraper reports capital expenditure as an outflow
+            capital_expenditure: flows.capital_expenditure.map(|val| -val),
+            investing_cash_flow: flows.investing_cash_flow,
+            financing_cash_flow: flows.financing_cash_flow,
+        }
+    }
+}
+
+/// fills the share change by comparing each statement with the same period a year earlier.
+/// growth rates are derived from the stored rows like for scraped statements.
+fn fill_share_changes(statements: &mut [SecStatement]) {
+    let previous: HashMap<(String, i16, i16), Option<f64>> = statements
+        .iter()
+        .map(|s| {
+            (
+                (s.term.clone(), s.quarter, s.year),
+                s.shares_outstanding_diluted,
+            )
+        })
+        .collect();
+    for statement in statements.iter_mut() {
+        let key = (
+            statement.term.clone(),
+            statement.quarter,
+            statement.year - 1,
+        );
+        if let Some(prev_shares) = previous.get(&key) {
+            statement.shares_change_yoy = calculate::calculate_yoy_growth_option(
+                statement.shares_outstanding_diluted,
+                *prev_shares,
+            );
+        }
+    }
+}
+
+/// derives TTM statements for every quarter and annual statements for every fiscal year
+pub fn parse_company_facts(
+    content: &str,
+) -> Result<(Vec<SecStatement>, Vec<SecStatement>), BullsEyeError> {
+    let company_facts: CompanyFacts =
+        serde_json::from_str(content).map_err(|e| BullsEyeError::SecFactsError(e.to_string()))?;
+    let us_gaap = company_facts
+        .facts
+        .get("us-gaap")
+        .ok_or_else(|| BullsEyeError::SecFactsError("no us-gaap facts".to_string()))?;
+    let parsed = Parsed::new(us_gaap);
+    let anchors = [&parsed.revenue, &parsed.net_income];
+    let years = fiscal_years(&anchors);
+
+    let mut annual = Vec::with_capacity(years.len());
+    for fiscal_year in &years {
+        let Some(end) = fiscal_year.end else {
+            continue;
+        };
+        let start = fiscal_year.start;
+        let flows = parsed.flows(|series| series.duration(start, end));
+        let per_share = PerShare {
+            shares_basic: parsed.shares_basic.duration(start, end),
+            shares_diluted: parsed.shares_diluted.duration(start, end),
+            eps_basic: parsed.eps_basic.duration(start, end),
+            eps_diluted: parsed.eps_diluted.duration(start, end),
+        };
+        annual.push(parsed.statement("Y", fiscal_year.year, 0, end, flows, per_share));
+    }
+
+    let quarters: Vec<FiscalQuarter> = years
+        .iter()
+        .flat_map(|fiscal_year| fiscal_quarters(fiscal_year, &anchors))
+        .collect();
+    let mut ttm = Vec::with_capacity(quarters.len());
+    for window in quarters.windows(4) {
+        let latest = &window[3];
+        // skips windows spanning a gap in the filings
+        let span = (latest.end - window[0].start).num_days();
+        if !(350..=380).contains(&span) {
+            continue;
+        }
+        let window: Vec<&FiscalQuarter> = window.iter().collect();
+        let flows = parsed.trailing_flows(&window);
+        let per_share = PerShare {
+            shares_basic: parsed.shares_basic.average(latest),
+            shares_diluted: parsed.shares_diluted.average(latest),
+            eps_basic: None,
+            eps_diluted: None,
+        };
+        ttm.push(parsed.statement(
+            "T",
+            latest.year,
+            latest.quarter,
+            latest.end,
+            flows,
+            per_share,
+        ));
+    }
+    fill_share_changes(&mut annual);
+    fill_share_changes(&mut ttm);
+    Ok((ttm, annual))
+}
+
+/// reads the CIK numbers of every listed ticker from the ticker map in the given directory
+fn load_cik_map(dir: &Path) -> Result<HashMap<String, u64>, BullsEyeError> {
+    let ticker_map = dir.join(TICKER_MAP_FILE);
+    let content = fs::read_to_string(&ticker_map)
+        .map_err(|e| BullsEyeError::SecFactsError(format!("{}: {}", ticker_map.display(), e)))?;
+    // {"fields": ["cik", "name", "ticker", "exchange"], "data": [[320193, "Apple Inc.", "AAPL", "Nasdaq"], ...]}
+    let map: Value =
+        serde_json::from_str(&content).map_err(|e| BullsEyeError::SecFactsError(e.to_string()))?;
+    let rows = map["data"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let mut cik_by_ticker = HashMap::with_capacity(rows.len());
+    for row in rows {
+        if let (Some(cik), Some(ticker)) = (row[0].as_u64(), row[2].as_str()) {
+            cik_by_ticker.entry(ticker.to_uppercase()).or_insert(cik);
+        }
+    }
+    Ok(cik_by_ticker)
+}
+
+/// path of the companyfacts file for the given ticker, if it was downloaded
+pub fn find_facts_file(ticker: &str) -> Result<Option<PathBuf>, BullsEyeError> {
+    let Ok(dir) = env::var("SEC_FACTS_DIR") else {
+        return Ok(None);
+    };
+    let dir = PathBuf::from(dir);
+    let cik_by_ticker = match CIK_BY_TICKER.get() {
+        Some(cik_by_ticker) => cik_by_ticker,
+        // a failed read isn't cached, so the next lookup tries again
+        None => {
+            let cik_by_ticker = load_cik_map(&dir)?;
+            CIK_BY_TICKER.get_or_init(|| cik_by_ticker)
+        }
+    };
+    let path = cik_by_ticker
+        .get(&ticker.to_uppercase())
+        .map(|cik| dir.join(format!("CIK{:010}.json", cik)));
+    Ok(path.filter(|path| path.exists()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// calendar year 2023 with its 10-K, three 10-Qs and the first 10-Q of 2024.
+    /// Q1 2023 revenue was restated in the 10-Q of Q1 2024, Q4 is only part of the 10-K
+    /// and operating cash flow is only reported year-to-date.
+    const FIXTURE: &str = r#"{
+        "facts": {
+            "us-gaap": {
+                "Revenues": {
+                    "units": {
+                        "USD": [
+                            {"start": "2023-01-01", "end": "2023-03-31", "val": 200, "filed": "2023-05-01"},
+                            {"start": "2023-04-01", "end": "2023-06-30", "val": 250, "filed": "2023-08-01"},
+                            {"start": "2023-07-01", "end": "2023-09-30", "val": 300, "filed": "2023-11-01"},
+                            {"start": "2023-01-01", "end": "2023-09-30", "val": 760, "filed": "2023-11-01"},
+                            {"start": "2023-01-01", "end": "2023-12-31", "val": 1000, "filed": "2024-02-01"},
+                            {"start": "2023-01-01", "end": "2023-03-31", "val": 210, "filed": "2024-05-01"},
+                            {"start": "2024-01-01", "end": "2024-03-31", "val": 260, "filed": "2024-05-01"}
+                        ]
+                    }
+                },
+                "NetCashProvidedByUsedInOperatingActivities": {
+                    "units": {
+                        "USD": [
+                            {"start": "2023-01-01", "end": "2023-03-31", "val": 50, "filed": "2023-05-01"},
+                            {"start": "2023-01-01", "end": "2023-06-30", "val": 120, "filed": "2023-08-01"},
+                            {"start": "2023-01-01", "end": "2023-09-30", "val": 200, "filed": "2023-11-01"},
+                            {"start": "2023-01-01", "end": "2023-12-31", "val": 290, "filed": "2024-02-01"},
+                            {"start": "2024-01-01", "end": "2024-03-31", "val": 70, "filed": "2024-05-01"}
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+
+    fn date(date_str: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date_str, "%Y-%m-%d").unwrap()
+    }
+
+    fn parsed_fixture() -> Parsed {
+        let company_facts: CompanyFacts = serde_json::from_str(FIXTURE).unwrap();
+        Parsed::new(&company_facts.facts["us-gaap"])
+    }
+
+    fn all_quarters(parsed: &Parsed) -> Vec<FiscalQuarter> {
+        let anchors = [&parsed.revenue, &parsed.net_income];
+        fiscal_years(&anchors)
+            .iter()
+            .flat_map(|fiscal_year| fiscal_quarters(fiscal_year, &anchors))
+            .collect()
+    }
+
+    #[test]
+    fn derives_quarters_of_finished_and_current_year() {
+        let parsed = parsed_fixture();
+        let quarters: Vec<(i16, i16, NaiveDate)> = all_quarters(&parsed)
+            .iter()
+            .map(|quarter| (quarter.year, quarter.quarter, quarter.end))
+            .collect();
+        assert_eq!(
+            quarters,
+            vec![
+                (2023, 1, date("2023-03-31")),
+                (2023, 2, date("2023-06-30")),
+                (2023, 3, date("2023-09-30")),
+                (2023, 4, date("2023-12-31")),
+                (2024, 1, date("2024-03-31")),
+            ]
+        );
+    }
+
+    #[test]
+    fn derives_q4_as_fiscal_year_minus_nine_months() {
+        let parsed = parsed_fixture();
+        let quarters = all_quarters(&parsed);
+        assert_eq!(parsed.revenue.quarter(&quarters[3]), Some(240.));
+        assert_eq!(parsed.operating_cash_flow.quarter(&quarters[1]), Some(70.));
+        assert_eq!(parsed.operating_cash_flow.quarter(&quarters[3]), Some(90.));
+    }
+
+    #[test]
+    fn keeps_latest_filing_of_duplicate_facts() {
+        let parsed = parsed_fixture();
+        assert_eq!(
+            parsed
+                .revenue
+                .duration(date("2023-01-01"), date("2023-03-31")),
+            Some(210.)
+        );
+    }
+
+    #[test]
+    fn builds_ttm_and_annual_statements() {
+        let (ttm, annual) = parse_company_facts(FIXTURE).unwrap();
+        let ttm: Vec<(i16, i16, Option<f64>, Option<f64>)> = ttm
+            .iter()
+            .map(|s| (s.year, s.quarter, s.revenue, s.operating_cash_flow))
+            .collect();
+        assert_eq!(
+            ttm,
+            vec![
+                (2023, 4, Some(1000.), Some(290.)),
+                (2024, 1, Some(1050.), Some(310.)),
+            ]
+        );
+        assert_eq!(annual.len(), 1);
+        assert_eq!(annual[0].year, 2023);
+        assert_eq!(annual[0].revenue, Some(1000.));
+    }
+}
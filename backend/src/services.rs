@@ -1,34 +1,94 @@
+use crate::backtest::{self, TargetStats};
+use crate::calculate;
 use crate::db;
 use crate::errors::BullsEyeError;
+use crate::explain::{self, Derivation};
+use crate::fx::{self, FxRateSource, FxTable};
+use crate::gaps;
+use crate::helper::{self, parse_exchange};
+use crate::import::{ImportBatch, PriceRow};
+use crate::models::backfill_model::BackfillRequest;
 use crate::models::companies_model::{Company, NewCompany};
+use crate::models::corporate_actions_model::{CorporateActionType, NewCorporateAction};
+use crate::models::data_quality_model::DataQualityIssue;
 use crate::models::earnings_model;
-use crate::models::earnings_model::NewEarningsReport;
-use crate::models::forecast_models::NewForecasts;
+use crate::models::earnings_model::{EarningsReport, NewEarningsReport};
+use crate::models::flags_model::{CompanyRedFlag, RedFlag};
+use crate::models::forecast_models::{Forecasts, NewForecasts};
+use crate::models::fx_model::{self, FxRate};
+use crate::models::industry_model::{IndustryDetail, IndustrySummary};
+use crate::models::ingest_issues_model::IngestIssue;
 use crate::models::metrics_model::{CurrentMetrics, NewCurrentMetrics};
+use crate::models::peers_model::{PeerRanks, PeerSummary, PeersModel};
+use crate::models::price_history_model::{self, NewPriceHistory, PriceHistory};
 use crate::models::returning_model::ReturningModel;
+use crate::models::search_model::SearchResult;
+use crate::models::symbols_model::{NewSymbolEntry, SymbolEntry};
+use crate::models::ticker_history_model::{NewTickerHistory, TickerHistory};
 use crate::query;
+use crate::search;
+use crate::sec;
+use crate::validation;
+use crate::valuation::{self, MultipleBand, MultiplePoint};
+use bullseye_api::errors::ScraperError;
 use bullseye_api::model::get_exchange_string;
 use bullseye_api::model::Exchange;
+use chrono::NaiveDate;
 use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+
+/// SEC companyfacts amounts are read from the USD unit only
+const SEC_CURRENCY: &str = "USD";
+/// separate days the ticker must fail to resolve before the company is marked as delisted
+const DELISTING_CONFIRMATIONS: i32 = 3;
+/// daily prices per insert, keeping the bind parameters below the postgres limit
+const PRICE_IMPORT_CHUNK_SIZE: usize = 10_000;
 
 /// runs when handling new ticker data.
-/// creates new company row for all 3 tables.
+/// creates new company row for all 3 tables. the ticker is stored in upper case.
+/// moves the previous symbol to the ticker history when the ISIN is found under a new one,
+/// and marks known companies as delisted once their ticker keeps failing to resolve.
 pub async fn get_company(
     ticker: &str,
     exchange: &Exchange,
     conn: &mut PgConnection,
 ) -> Result<Company, BullsEyeError> {
-    if let Some(company) =
-        Company::load_by_ticker_if_existed(ticker, get_exchange_string(exchange), conn)?
-    {
-        if !company.ticker_check_needed() {
+    let ticker = &helper::normalize_ticker(ticker);
+    let exchange_str = get_exchange_string(exchange);
+    let existing_company = match Company::load_by_ticker_if_existed(ticker, exchange_str, conn)? {
+        Some(company) if company.delisted || !company.ticker_check_needed() => {
             return Ok(company);
         }
-    }
-    let company_profile = bullseye_api::scrape_profile(ticker, exchange).await?;
+        existing_company => existing_company,
+    };
+    let company_profile = match (
+        bullseye_api::scrape_profile(ticker, exchange).await,
+        existing_company,
+    ) {
+        (Ok(profile), _) => profile,
+        (Err(ScraperError::InvalidTickerError(_)), Some(company)) => {
+            record_invalid_ticker(&company, conn)?;
+            return Ok(Company::load_by_id(company.id, conn)?);
+        }
+        (Err(e), _) => return Err(e.into()),
+    };
+    let isin = helper::normalize_ticker(&company_profile.isin_number);
+    NewSymbolEntry::create_new_entry(ticker, Some(&isin), exchange_str).upsert(conn)?;
     if let Some(company) = Company::load_if_existed(&company_profile, conn)? {
-        query::update_company_table(company.id, conn)?; //TODO: reflect ticker change
-        Ok(company)
+        if company.is_listed_as(ticker, exchange_str) {
+            query::update_company_table(company.id, conn)?;
+            return Ok(company);
+        }
+        conn.transaction(|conn| {
+            NewTickerHistory::create_new_entry(company.id, &company.ticker, &company.exchange)
+                .insert_new_history(conn)?;
+            query::update_company_listing(company.id, ticker, exchange_str, conn)
+        })?;
+        Ok(Company::load_by_id(company.id, conn)?)
     } else {
         let new_company_entry = NewCompany::create_new_entry(
             &company_profile.company_name,
@@ -37,25 +97,860 @@ pub async fn get_company(
             exchange,
             ticker,
         );
-        let new_company = new_company_entry.add_new_company(conn)?;
-        let new_metrics_entry = NewCurrentMetrics::create_new_entry(new_company.id, "")?;
-        new_metrics_entry.insert_new_metrics(conn)?;
-        let new_forecast_entry = NewForecasts::create_empty(new_company.id);
-        new_forecast_entry.insert_new_forecast(conn)?;
-        Ok(new_company)
+        insert_company(new_company_entry, conn)
+    }
+}
+
+/// counts a failed ticker check of the given company, at most once a day.
+/// the company is marked as delisted once its ticker failed on enough separate days
+/// and its ISIN isn't listed under another symbol in the symbol directory.
+fn record_invalid_ticker(company: &Company, conn: &mut PgConnection) -> Result<(), BullsEyeError> {
+    let Some(failures) = query::update_company_ticker_failure(company.id, conn)? else {
+        return Ok(());
+    };
+    if failures < DELISTING_CONFIRMATIONS {
+        return Ok(());
+    }
+    let is_listed_elsewhere = SymbolEntry::load_by_symbol(&company.isin, conn)?
+        .iter()
+        .any(|entry| !company.is_listed_as(&entry.ticker, &entry.exchange));
+    if is_listed_elsewhere {
+        eprintln!(
+            "{}:{} does not resolve but its ISIN is listed under another symbol",
+            company.exchange, company.ticker
+        );
+        return Ok(());
+    }
+    query::update_company_delisted(company.id, conn)?;
+    Ok(())
+}
+
+/// creates the company row together with its empty metrics and forecast rows
+/// and the corporate actions listed for it
+fn insert_company(
+    new_company_entry: NewCompany,
+    conn: &mut PgConnection,
+) -> Result<Company, BullsEyeError> {
+    let new_company = new_company_entry.add_new_company(conn)?;
+    let new_metrics_entry = NewCurrentMetrics::create_new_entry(new_company.id, "")?;
+    new_metrics_entry.insert_new_metrics(conn)?;
+    let new_forecast_entry = NewForecasts::create_empty(new_company.id);
+    new_forecast_entry.insert_new_forecast(conn)?;
+    // earnings are restated with these actions once they are stored
+    store_corporate_actions(&new_company, &read_corporate_actions()?, conn)?;
+    Ok(new_company)
+}
+
+/// returns the current company when the given symbol only exists in the ticker history
+pub fn find_renamed_company(
+    ticker: &str,
+    exchange: &Exchange,
+    conn: &mut PgConnection,
+) -> Result<Option<Company>, BullsEyeError> {
+    let exchange_str = get_exchange_string(exchange);
+    if Company::load_by_ticker_if_existed(ticker, exchange_str, conn)?.is_some() {
+        return Ok(None);
+    }
+    match TickerHistory::load_company_id_if_existed(ticker, exchange_str, conn)? {
+        Some(comp_id) => Ok(Some(Company::load_by_id(comp_id, conn)?)),
+        None => Ok(None),
     }
 }
 
-///returns vector of returning model for the list view
-pub fn get_all_companies(conn: &mut PgConnection) -> Result<Vec<ReturningModel>, BullsEyeError> {
+///returns vector of returning model for the list view.
+///companies with any of the excluded red flags are left out.
+///absolute amounts are also converted when a target currency is given.
+pub fn get_all_companies(
+    excluded_flags: &[RedFlag],
+    target_currency: Option<&str>,
+    conn: &mut PgConnection,
+) -> Result<Vec<ReturningModel>, BullsEyeError> {
+    let fx_table = match target_currency {
+        Some(_) => Some(load_fx_table(conn)?),
+        None => None,
+    };
     let joined_db = db::join_data(conn)?;
+    let mut all_flags = CompanyRedFlag::load_all(conn)?;
     let returning_vec = joined_db
         .into_iter()
-        .map(|(company, metrics, forecast)| ReturningModel::new(company, metrics, forecast))
+        .filter_map(|(company, metrics, forecast)| {
+            let company_flags = all_flags.remove(&company.id).unwrap_or_default();
+            if company_flags
+                .iter()
+                .any(|flag| excluded_flags.contains(flag))
+            {
+                return None;
+            }
+            let returning = ReturningModel::new(company, metrics, forecast, company_flags);
+            match (target_currency, &fx_table) {
+                (Some(currency), Some(table)) => Some(returning.with_currency(currency, table)),
+                _ => Some(returning),
+            }
+        })
         .collect();
     Ok(returning_vec)
 }
 
+/// validates the requested currency code and returns it in upper case
+pub fn parse_currency(currency: Option<String>) -> Result<Option<String>, BullsEyeError> {
+    match currency {
+        Some(code) if fx::is_currency_code(&code) => Ok(Some(code.to_uppercase())),
+        Some(code) => Err(BullsEyeError::InvalidCurrencyError(code)),
+        None => Ok(None),
+    }
+}
+
+/// parses comma separated red flag names and rejects names that aren't red flags
+pub fn parse_red_flags(flag_names: &str) -> Result<Vec<RedFlag>, BullsEyeError> {
+    flag_names
+        .split(',')
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| {
+            RedFlag::from_name(name).ok_or_else(|| {
+                let valid_names: Vec<&str> = RedFlag::ALL.iter().map(RedFlag::as_str).collect();
+                BullsEyeError::UnknownFlagError(name.trim().to_string(), valid_names.join(", "))
+            })
+        })
+        .collect()
+}
+
+/// loads every stored exchange rate for conversions
+pub fn load_fx_table(conn: &mut PgConnection) -> Result<FxTable, BullsEyeError> {
+    Ok(FxTable::new(FxRate::load_all(conn)?))
+}
+
+/// stores exchange rates from the configured source. does nothing when no source is configured.
+pub fn refresh_fx_rates(conn: &mut PgConnection) -> Result<(), BullsEyeError> {
+    if let Some(source) = FxRateSource::from_env() {
+        let entries = source.load_rates()?;
+        fx_model::upsert_fx_rates(entries, conn)?;
+    }
+    Ok(())
+}
+
+/// returns peer ranks and other companies in the same industry for the given ticker
+pub fn get_peers(
+    ticker: &str,
+    exchange: &Exchange,
+    conn: &mut PgConnection,
+) -> Result<PeersModel, BullsEyeError> {
+    let company = Company::load_by_ticker_if_existed(ticker, get_exchange_string(exchange), conn)?
+        .ok_or_else(|| BullsEyeError::CompanyNotFoundError(ticker.to_string()))?;
+    let ranks = PeerRanks::load_by_id_if_existed(company.id, conn)?;
+    let peers = db::load_industry_metrics(&company.industry, conn)?
+        .into_iter()
+        .filter(|(peer, _)| peer.id != company.id)
+        .map(|(peer, metrics)| PeerSummary::new(peer, metrics))
+        .collect();
+    Ok(PeersModel::new(
+        company.ticker,
+        company.industry,
+        ranks,
+        peers,
+    ))
+}
+
+/// returns every earnings data of the given ticker, oldest first with annual data after TTM
+pub fn get_earnings_history(
+    ticker: &str,
+    exchange: &Exchange,
+    conn: &mut PgConnection,
+) -> Result<(Company, Vec<EarningsReport>), BullsEyeError> {
+    let company = Company::load_by_ticker_if_existed(ticker, get_exchange_string(exchange), conn)?
+        .ok_or_else(|| BullsEyeError::CompanyNotFoundError(ticker.to_string()))?;
+    let mut history = EarningsReport::load_all_by_id(company.id, conn)?;
+    history.sort_by(|a, b| {
+        b.duration
+            .cmp(&a.duration)
+            .then(a.year_str.cmp(&b.year_str))
+            .then(a.quarter_str.cmp(&b.quarter_str))
+    });
+    Ok((company, history))
+}
+
+/// daily prices of a listing in its trading currency
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceSeries {
+    pub ticker: String,
+    pub exchange: String,
+    pub currency: Option<String>,
+    pub prices: Vec<PriceHistory>,
+}
+
+/// returns the stored daily prices of the given ticker between the given days, oldest first
+pub fn get_price_history(
+    ticker: &str,
+    exchange: &Exchange,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    conn: &mut PgConnection,
+) -> Result<PriceSeries, BullsEyeError> {
+    let company = Company::load_by_ticker_if_existed(ticker, get_exchange_string(exchange), conn)?
+        .ok_or_else(|| BullsEyeError::CompanyNotFoundError(ticker.to_string()))?;
+    let prices = PriceHistory::load_range(company.id, from, to, conn)?;
+    Ok(PriceSeries {
+        currency: helper::trading_currency(&company.exchange).map(str::to_string),
+        ticker: company.ticker,
+        exchange: company.exchange,
+        prices,
+    })
+}
+
+/// historical P/E and P/S of a listing with their bands and the percentiles of the current values
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValuationHistory {
+    pub ticker: String,
+    pub exchange: String,
+    pub pe_ratio: Option<f64>,
+    pub pe_ratio_percentile: Option<f64>,
+    pub pe_ratio_bands: Vec<MultipleBand>,
+    pub ps_ratio: Option<f64>,
+    pub ps_ratio_percentile: Option<f64>,
+    pub ps_ratio_bands: Vec<MultipleBand>,
+    pub history: Vec<MultiplePoint>,
+}
+
+/// returns the daily multiples of the given ticker along with their 3, 5 and 10 year bands
+pub fn get_valuation_history(
+    ticker: &str,
+    exchange: &Exchange,
+    conn: &mut PgConnection,
+) -> Result<ValuationHistory, BullsEyeError> {
+    let company = Company::load_by_ticker_if_existed(ticker, get_exchange_string(exchange), conn)?
+        .ok_or_else(|| BullsEyeError::CompanyNotFoundError(ticker.to_string()))?;
+    let target_metrics = CurrentMetrics::load_by_id(company.id, conn)?;
+    let target_forecast = Forecasts::load_by_id(company.id, conn)?;
+    let history = db::load_multiple_history(company.id, conn)?;
+    Ok(ValuationHistory {
+        ticker: company.ticker,
+        exchange: company.exchange,
+        pe_ratio: target_forecast.pe_ratio,
+        pe_ratio_percentile: target_metrics.pe_ratio_percentile,
+        pe_ratio_bands: valuation::multiple_bands(&history, |point| point.pe_ratio),
+        ps_ratio: target_forecast.ps_ratio,
+        ps_ratio_percentile: target_metrics.ps_ratio_percentile,
+        ps_ratio_bands: valuation::multiple_bands(&history, |point| point.ps_ratio),
+        history,
+    })
+}
+
+/// missing periods of a company and whether trends and growth rates have enough data
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataGapReport {
+    pub ticker: String,
+    pub exchange: String,
+    pub ttm_quarters: usize,
+    pub annual_years: usize,
+    pub missing_quarters: Vec<String>,
+    pub missing_fiscal_years: Vec<String>,
+    /// false when short-term trends are "irrelevant" for lack of data
+    pub has_short_term_history: bool,
+    /// false when YoY growth of the latest quarter is missing for lack of data
+    pub has_prev_year_quarter: bool,
+    pub backfills: Vec<BackfillRequest>,
+}
+
+/// returns the periods missing from the stored statements of the given ticker
+pub fn get_data_gaps(
+    ticker: &str,
+    exchange: &Exchange,
+    conn: &mut PgConnection,
+) -> Result<DataGapReport, BullsEyeError> {
+    let company = Company::load_by_ticker_if_existed(ticker, get_exchange_string(exchange), conn)?
+        .ok_or_else(|| BullsEyeError::CompanyNotFoundError(ticker.to_string()))?;
+    let reports = EarningsReport::load_all_by_id(company.id, conn)?;
+    let ttm_quarters = reports
+        .iter()
+        .filter(|report| report.duration == "T")
+        .count();
+    Ok(DataGapReport {
+        ticker: company.ticker,
+        exchange: company.exchange,
+        ttm_quarters,
+        annual_years: reports.len() - ttm_quarters,
+        missing_quarters: gaps::missing_quarters(&reports),
+        missing_fiscal_years: gaps::missing_fiscal_years(&reports),
+        has_short_term_history: gaps::has_short_term_history(&reports),
+        has_prev_year_quarter: gaps::has_prev_year_quarter(&reports),
+        backfills: BackfillRequest::load_by_id(company.id, conn)?,
+    })
+}
+
+/// scrapes the full history of every company with due backfill requests
+/// and returns the companies that were backfilled
+pub async fn run_due_backfills(conn: &mut PgConnection) -> Result<Vec<Company>, BullsEyeError> {
+    let mut backfilled = Vec::new();
+    for comp_id in BackfillRequest::load_due_company_ids(conn)? {
+        let company = Company::load_by_id(comp_id, conn)?;
+        if company.delisted {
+            continue;
+        }
+        let exchange = parse_exchange(&company.exchange)
+            .ok_or_else(|| BullsEyeError::UnsupportedExchangeError(company.exchange.clone()))?;
+        update_earnings_all(company.id, &company.ticker, &exchange, conn).await?;
+        update_metrics_annual(company.id, conn)?;
+        backfilled.push(company);
+    }
+    if !backfilled.is_empty() {
+        update_all_peer_ranks(conn)?;
+    }
+    Ok(backfilled)
+}
+
+/// how a metric of a company is derived, along with the value currently stored
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricExplanation {
+    pub ticker: String,
+    pub exchange: String,
+    pub field: String,
+    pub stored_value: serde_json::Value,
+    pub derivation: Derivation,
+}
+
+/// rebuilds the derivation of the given field of the company response.
+/// the field can be given in camelCase as in the response or in snake_case.
+pub fn get_metric_explanation(
+    ticker: &str,
+    exchange: &Exchange,
+    field: &str,
+    conn: &mut PgConnection,
+) -> Result<MetricExplanation, BullsEyeError> {
+    let company = Company::load_by_ticker_if_existed(ticker, get_exchange_string(exchange), conn)?
+        .ok_or_else(|| BullsEyeError::CompanyNotFoundError(ticker.to_string()))?;
+    let field_name = helper::to_snake_case(field);
+    let derivation = explain::explain_field(company.id, &field_name, conn)?
+        .ok_or_else(|| BullsEyeError::UnexplainableFieldError(field.to_string()))?;
+    let metrics = serde_json::to_value(CurrentMetrics::load_by_id(company.id, conn)?)?;
+    let forecast = serde_json::to_value(Forecasts::load_by_id(company.id, conn)?)?;
+    let ranks = serde_json::to_value(PeerRanks::load_by_id_if_existed(company.id, conn)?)?;
+    let flags = serde_json::to_value(CompanyRedFlag::load_by_id(company.id, conn)?)?;
+    let camel_name = helper::to_camel_case(&field_name);
+    let stored_value = match field_name.as_str() {
+        "red_flags" => Some(&flags),
+        _ => metrics
+            .get(&camel_name)
+            .or_else(|| forecast.get(&camel_name))
+            .or_else(|| ranks.get(&camel_name)),
+    }
+    .cloned()
+    .unwrap_or(serde_json::Value::Null);
+    Ok(MetricExplanation {
+        ticker: company.ticker,
+        exchange: company.exchange,
+        field: field_name,
+        stored_value,
+        derivation,
+    })
+}
+
+/// returns listings matching the query, ranked by match quality
+pub fn search_companies(
+    query: &str,
+    conn: &mut PgConnection,
+) -> Result<Vec<SearchResult>, BullsEyeError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let candidates = Company::load_search_candidates(query, conn)?;
+    Ok(search::rank_candidates(query, candidates))
+}
+
+/// returns aggregated metrics for every industry
+pub fn get_all_industries(conn: &mut PgConnection) -> Result<Vec<IndustrySummary>, BullsEyeError> {
+    let mut grouped = BTreeMap::new();
+    for (company, metrics) in db::load_all_metrics(conn)? {
+        grouped
+            .entry(company.industry.clone())
+            .or_insert_with(Vec::new)
+            .push((company, metrics));
+    }
+    let summaries = grouped
+        .into_iter()
+        .map(|(industry, members)| IndustrySummary::new(industry, &members))
+        .collect();
+    Ok(summaries)
+}
+
+/// returns aggregated metrics and member companies of the given industry
+pub fn get_industry(
+    industry: &str,
+    conn: &mut PgConnection,
+) -> Result<IndustryDetail, BullsEyeError> {
+    let members = db::load_industry_metrics(industry, conn)?;
+    if members.is_empty() {
+        return Err(BullsEyeError::IndustryNotFoundError(industry.to_string()));
+    }
+    Ok(IndustryDetail::new(industry.to_string(), members))
+}
+
+/// split or reverse split listed in CORPORATE_ACTIONS_FILE
+#[derive(Deserialize)]
+struct CorporateActionRow {
+    exchange: String,
+    ticker: String,
+    effective_date: NaiveDate,
+    action: CorporateActionType,
+    ratio: f64,
+}
+
+/// reads the actions listed in CORPORATE_ACTIONS_FILE. returns none when no file is configured.
+/// the file starts with the header "exchange,ticker,effective_date,action,ratio",
+/// dates are given as YYYY-MM-DD and actions as split or reverse_split.
+fn read_corporate_actions() -> Result<Vec<CorporateActionRow>, BullsEyeError> {
+    let Ok(path) = env::var("CORPORATE_ACTIONS_FILE") else {
+        return Ok(Vec::new());
+    };
+    let to_load_error =
+        |e: csv::Error| BullsEyeError::CorporateActionLoadError(format!("{}: {}", path, e));
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(&path)
+        .map_err(to_load_error)?;
+    let mut rows = Vec::new();
+    for row in reader.deserialize() {
+        let mut row: CorporateActionRow = row.map_err(to_load_error)?;
+        if row.ratio <= 0. {
+            return Err(BullsEyeError::CorporateActionLoadError(format!(
+                "{}: ratio of {}:{} on {} must be positive",
+                path, row.exchange, row.ticker, row.effective_date
+            )));
+        }
+        row.exchange = row.exchange.to_uppercase();
+        row.ticker = helper::normalize_ticker(&row.ticker);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// stores the listed actions of the given company
+fn store_corporate_actions(
+    company: &Company,
+    rows: &[CorporateActionRow],
+    conn: &mut PgConnection,
+) -> Result<(), BullsEyeError> {
+    for row in rows
+        .iter()
+        .filter(|row| company.is_listed_as(&row.ticker, &row.exchange))
+    {
+        NewCorporateAction::create_new_entry(company.id, row.action, row.ratio, row.effective_date)
+            .upsert(conn)?;
+    }
+    Ok(())
+}
+
+/// stores splits and reverse splits listed in CORPORATE_ACTIONS_FILE and restates affected earnings.
+/// actions of companies that are not ingested yet are stored when the company is created.
+pub fn import_corporate_actions(conn: &mut PgConnection) -> Result<(), BullsEyeError> {
+    let rows = read_corporate_actions()?;
+    let listings: BTreeSet<(&str, &str)> = rows
+        .iter()
+        .map(|row| (row.exchange.as_str(), row.ticker.as_str()))
+        .collect();
+    for (exchange_str, ticker) in listings {
+        let Some(company) = Company::load_by_ticker_if_existed(ticker, exchange_str, conn)? else {
+            continue;
+        };
+        store_corporate_actions(&company, &rows, conn)?;
+        db::update_split_adjustments(company.id, conn)?;
+        db::update_derived_fields(company.id, conn)?;
+    }
+    Ok(())
+}
+
+/// number of statements newly stored for one imported company.
+/// periods that were already stored are not counted.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub ticker: String,
+    pub exchange: String,
+    pub ttm_rows: usize,
+    pub annual_rows: usize,
+}
+
+/// stores statements from the import layout and runs them through the usual ratio,
+/// growth and metrics updates. companies that are not ingested yet are created.
+pub fn import_statements(
+    batch: ImportBatch,
+    conn: &mut PgConnection,
+) -> Result<ImportSummary, BullsEyeError> {
+    let exchange = parse_exchange(&batch.exchange)
+        .ok_or_else(|| BullsEyeError::UnsupportedExchangeError(batch.exchange.clone()))?;
+    let exchange_str = get_exchange_string(&exchange);
+    let ticker = helper::normalize_ticker(&batch.ticker);
+    let company = match Company::load_by_ticker_if_existed(&ticker, exchange_str, conn)? {
+        Some(company) => company,
+        None => {
+            let new_company_entry = NewCompany::create_new_entry(
+                &batch.company_name,
+                &batch.industry,
+                &batch.isin,
+                &exchange,
+                &ticker,
+            );
+            insert_company(new_company_entry, conn)?
+        }
+    };
+    let isin = helper::normalize_ticker(&batch.isin);
+    NewSymbolEntry::create_new_entry(&ticker, Some(&isin), exchange_str).upsert(conn)?;
+    let (ttm_entries, rejected_ttm_periods) = NewEarningsReport::create_new_entry(
+        company.id,
+        &batch.currency,
+        batch.to_earnings(batch.ttm.clone())?,
+    );
+    let (annual_entries, rejected_annual_periods) = NewEarningsReport::create_new_entry(
+        company.id,
+        &batch.currency,
+        batch.to_earnings(batch.annual.clone())?,
+    );
+    let summary = ImportSummary {
+        ticker,
+        exchange: exchange_str.to_string(),
+        ttm_rows: earnings_model::insert_earnings_report_batch(ttm_entries, conn)?,
+        annual_rows: earnings_model::insert_earnings_report_batch(annual_entries, conn)?,
+    };
+    db::update_ingest_issues(
+        company.id,
+        &rejected_ttm_periods,
+        Some(rejected_annual_periods.as_slice()),
+        conn,
+    )?;
+    db::update_backfill_requests(company.id, false, conn)?;
+    if summary.ttm_rows == 0 && summary.annual_rows == 0 {
+        return Ok(summary);
+    }
+    db::update_split_adjustments(company.id, conn)?;
+    db::update_derived_fields(company.id, conn)?;
+    if summary.annual_rows > 0 {
+        update_metrics_annual(company.id, conn)?;
+    } else {
+        update_metrics_ttm(company.id, conn)?;
+    }
+    Ok(summary)
+}
+
+/// stores statements from the downloaded SEC companyfacts file of US listings.
+/// these overwrite stored periods and scraped ones are never written over them,
+/// so filed numbers win for overlapping periods.
+fn insert_sec_statements(
+    company_id: i32,
+    ticker: &str,
+    exchange: &Exchange,
+    conn: &mut PgConnection,
+) -> Result<bool, BullsEyeError> {
+    if helper::trading_currency(get_exchange_string(exchange)) != Some(SEC_CURRENCY) {
+        return Ok(false);
+    }
+    let Some(path) = sec::find_facts_file(ticker)? else {
+        return Ok(false);
+    };
+    let content = fs::read_to_string(&path)
+        .map_err(|e| BullsEyeError::SecFactsError(format!("{}: {}", path.display(), e)))?;
+    let (ttm_statements, annual_statements) = sec::parse_company_facts(&content)?;
+    let ttm_entries = NewEarningsReport::create_from_sec(company_id, SEC_CURRENCY, ttm_statements);
+    let annual_entries =
+        NewEarningsReport::create_from_sec(company_id, SEC_CURRENCY, annual_statements);
+    let is_ttm_entries_existed =
+        earnings_model::upsert_earnings_report_batch(ttm_entries, conn)? > 0;
+    let is_annual_entries_existed =
+        earnings_model::upsert_earnings_report_batch(annual_entries, conn)? > 0;
+    Ok(is_ttm_entries_existed || is_annual_entries_existed)
+}
+
+/// number of stored daily prices and the listings in the file that are not ingested
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceImportSummary {
+    pub price_rows: usize,
+    pub unknown_listings: Vec<String>,
+}
+
+/// stores daily prices from the price CSV layout for companies that are already ingested.
+/// tickers are matched in the upper case form companies are stored under.
+pub fn import_prices(
+    rows: Vec<PriceRow>,
+    conn: &mut PgConnection,
+) -> Result<PriceImportSummary, BullsEyeError> {
+    let mut listings: BTreeMap<(String, String), Vec<PriceRow>> = BTreeMap::new();
+    for row in rows {
+        let listing = (
+            row.exchange.trim().to_uppercase(),
+            helper::normalize_ticker(&row.ticker),
+        );
+        listings.entry(listing).or_default().push(row);
+    }
+    let mut summary = PriceImportSummary {
+        price_rows: 0,
+        unknown_listings: Vec::new(),
+    };
+    for ((exchange_str, ticker), rows) in listings {
+        let company = match parse_exchange(&exchange_str) {
+            Some(exchange) => {
+                Company::load_by_ticker_if_existed(&ticker, get_exchange_string(&exchange), conn)?
+            }
+            None => None,
+        };
+        let Some(company) = company else {
+            summary
+                .unknown_listings
+                .push(format!("{}:{}", exchange_str, ticker));
+            continue;
+        };
+        let entries: Vec<NewPriceHistory> = rows
+            .iter()
+            .map(|row| {
+                NewPriceHistory::create_new_entry(
+                    company.id, row.date, row.open, row.high, row.low, row.close, row.volume,
+                )
+            })
+            .collect();
+        for chunk in entries.chunks(PRICE_IMPORT_CHUNK_SIZE) {
+            summary.price_rows += price_history_model::upsert_price_history(chunk, conn)?;
+        }
+    }
+    Ok(summary)
+}
+
+/// backtest statistics of the price targets over the given horizon
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BacktestReport {
+    pub horizon_days: i64,
+    /// number of companies with stored earnings, prices and exchange rate
+    pub companies: usize,
+    pub targets: Vec<TargetStats>,
+}
+
+/// replays the price targets of past quarters and compares them with the imported prices.
+/// runs over every stored company, delisted ones included, unless a listing is given.
+pub fn run_backtest(
+    listing: Option<(&str, &Exchange)>,
+    horizon_days: i64,
+    conn: &mut PgConnection,
+) -> Result<BacktestReport, BullsEyeError> {
+    let companies = match listing {
+        Some((ticker, exchange)) => {
+            vec![
+                Company::load_by_ticker_if_existed(ticker, get_exchange_string(exchange), conn)?
+                    .ok_or_else(|| BullsEyeError::CompanyNotFoundError(ticker.to_string()))?,
+            ]
+        }
+        None => Company::load_all(conn)?,
+    };
+    let mut tested_companies = 0;
+    let mut samples = Vec::new();
+    for company in companies {
+        let prices = PriceHistory::load_by_id(company.id, conn)?;
+        let reports = EarningsReport::load_all_by_id(company.id, conn)?;
+        if prices.is_empty() || reports.is_empty() {
+            continue;
+        }
+        let target_metrics = CurrentMetrics::load_by_id(company.id, conn)?;
+        let target_forecast = Forecasts::load_by_id(company.id, conn)?;
+        // past targets are converted at the current rate as no rate history is stored
+        let Some(fx_rate) = db::reporting_to_trading_rate(&target_metrics, &target_forecast, conn)?
+        else {
+            continue;
+        };
+        tested_companies += 1;
+        samples.extend(backtest::replay_company(
+            &company.industry,
+            &reports,
+            &prices,
+            Some(fx_rate),
+            horizon_days,
+        ));
+    }
+    Ok(BacktestReport {
+        horizon_days,
+        companies: tested_companies,
+        targets: backtest::summarize(&samples),
+    })
+}
+
+/// metric that moved by more than MATERIAL_CHANGE_PCT after a recomputation
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricChange {
+    pub field: String,
+    pub before: Option<f64>,
+    pub after: Option<f64>,
+}
+
+/// material metric changes of one company after a recomputation
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecomputeReport {
+    pub ticker: String,
+    pub exchange: String,
+    pub changes: Vec<MetricChange>,
+}
+
+/// relative change treated as material. values below 1 are compared as absolute changes.
+const MATERIAL_CHANGE_PCT: f64 = 5.;
+
+/// numeric metrics and forecasts of the given company keyed by field name
+fn metrics_snapshot(
+    comp_id: i32,
+    conn: &mut PgConnection,
+) -> Result<serde_json::Map<String, serde_json::Value>, BullsEyeError> {
+    let metrics = serde_json::to_value(CurrentMetrics::load_by_id(comp_id, conn)?)?;
+    let forecast = serde_json::to_value(Forecasts::load_by_id(comp_id, conn)?)?;
+    let mut snapshot = serde_json::Map::new();
+    for value in [metrics, forecast] {
+        if let serde_json::Value::Object(fields) = value {
+            snapshot.extend(
+                fields
+                    .into_iter()
+                    .filter(|(_, field)| field.is_number() || field.is_null()),
+            );
+        }
+    }
+    Ok(snapshot)
+}
+
+fn material_changes(
+    before: &serde_json::Map<String, serde_json::Value>,
+    after: &serde_json::Map<String, serde_json::Value>,
+) -> Vec<MetricChange> {
+    after
+        .iter()
+        .filter_map(|(field, value)| {
+            let before_val = before.get(field).and_then(|val| val.as_f64());
+            let after_val = value.as_f64();
+            let is_material = match (before_val, after_val) {
+                (Some(prev), Some(curr)) => {
+                    (curr - prev).abs() / prev.abs().max(1.) * 100. > MATERIAL_CHANGE_PCT
+                }
+                (None, None) => false,
+                _ => true,
+            };
+            is_material.then(|| MetricChange {
+                field: field.clone(),
+                before: before_val,
+                after: after_val,
+            })
+        })
+        .collect()
+}
+
+/// companies recomputed by recompute_metrics
+pub struct RecomputeSummary {
+    /// companies whose metrics changed materially
+    pub reports: Vec<RecomputeReport>,
+    /// companies left as they are because of a blocking ingest issue
+    pub blocked: Vec<Company>,
+}
+
+/// recalculates ratios, growth rates, trends and price targets without scraping.
+/// only companies derived with older formulas are recomputed unless `all` is set.
+/// companies with a blocking ingest issue are skipped and reported as blocked.
+pub fn recompute_metrics(
+    all: bool,
+    conn: &mut PgConnection,
+) -> Result<RecomputeSummary, BullsEyeError> {
+    if all {
+        query::reset_calculated_flags(conn)?;
+    }
+    let mut reports = Vec::new();
+    let mut blocked = Vec::new();
+    for company in Company::load_all(conn)? {
+        if EarningsReport::latest_quarter_data_if_existed(company.id, conn)?.is_none() {
+            continue;
+        }
+        let calculation_version = CurrentMetrics::load_by_id(company.id, conn)?.calculation_version;
+        if !all && calculation_version >= calculate::CALCULATION_VERSION {
+            continue;
+        }
+        if has_blocking_issue(company.id, conn)? {
+            blocked.push(company);
+            continue;
+        }
+        let before = metrics_snapshot(company.id, conn)?;
+        db::update_derived_fields(company.id, conn)?;
+        update_metrics_annual(company.id, conn)?;
+        let after = metrics_snapshot(company.id, conn)?;
+        let changes = material_changes(&before, &after);
+        if !changes.is_empty() {
+            reports.push(RecomputeReport {
+                ticker: company.ticker,
+                exchange: company.exchange,
+                changes,
+            });
+        }
+    }
+    update_all_peer_ranks(conn)?;
+    Ok(RecomputeSummary { reports, blocked })
+}
+
+/// recalculates the peer ranks of every industry from the stored metrics.
+/// runs once per batch instead of after each company, as the whole industry is ranked at once.
+pub fn update_all_peer_ranks(conn: &mut PgConnection) -> Result<usize, BullsEyeError> {
+    let industries = Company::load_all_industries(conn)?;
+    for industry in &industries {
+        db::update_peer_ranks(industry, conn)?;
+    }
+    Ok(industries.len())
+}
+
+/// recalculates derived earnings fields when the company's earnings predate the current formulas.
+/// metrics themselves are refreshed by the caller, and keep their version while they are blocked.
+pub fn update_outdated_calculations(
+    comp_id: i32,
+    conn: &mut PgConnection,
+) -> Result<(), BullsEyeError> {
+    if EarningsReport::is_outdated(comp_id, conn)? {
+        db::update_derived_fields(comp_id, conn)?;
+    }
+    Ok(())
+}
+
+/// returns listed companies whose earnings or price data is out of date
+pub fn get_stale_companies(
+    conn: &mut PgConnection,
+) -> Result<Vec<(Company, Forecasts)>, BullsEyeError> {
+    let mut stale = Vec::new();
+    for company in Company::load_all(conn)? {
+        if company.delisted {
+            continue;
+        }
+        let forecast = Forecasts::load_by_id(company.id, conn)?;
+        if forecast.is_earnings_update_needed() || forecast.is_regular_update_needed() {
+            stale.push((company, forecast));
+        }
+    }
+    Ok(stale)
+}
+
+/// clears the delisted flag of the given listing after an admin confirmed it still trades
+pub fn relist_company(
+    ticker: &str,
+    exchange: &Exchange,
+    conn: &mut PgConnection,
+) -> Result<Company, BullsEyeError> {
+    let company = Company::load_by_ticker_if_existed(ticker, get_exchange_string(exchange), conn)?
+        .ok_or_else(|| BullsEyeError::CompanyNotFoundError(ticker.to_string()))?;
+    query::update_company_relisted(company.id, conn)?;
+    Ok(Company::load_by_id(company.id, conn)?)
+}
+
+/// removes the given listing and everything stored for it
+pub fn delete_company(
+    ticker: &str,
+    exchange: &Exchange,
+    conn: &mut PgConnection,
+) -> Result<Company, BullsEyeError> {
+    let company = Company::load_by_ticker_if_existed(ticker, get_exchange_string(exchange), conn)?
+        .ok_or_else(|| BullsEyeError::CompanyNotFoundError(ticker.to_string()))?;
+    query::delete_company(company.id, conn)?;
+    Ok(company)
+}
+
 /// runs after Q4 Earnings or for the initial update.
 /// includes:
 ///     storing latest earnings data (TTM & Annual)
@@ -69,15 +964,25 @@ pub async fn update_earnings_all(
 ) -> Result<(), BullsEyeError> {
     let (earnings_enum_ttm, earnings_enum_annual, currency, earnings_date, price, next_yr_rev) =
         bullseye_api::scrape_all(ticker, exchange).await?;
-    let ttm_entries = NewEarningsReport::create_new_entry(company_id, &currency, earnings_enum_ttm);
-    let annual_entries =
+    let is_sec_entries_existed = insert_sec_statements(company_id, ticker, exchange, conn)?;
+    let (ttm_entries, rejected_ttm_periods) =
+        NewEarningsReport::create_new_entry(company_id, &currency, earnings_enum_ttm);
+    let (annual_entries, rejected_annual_periods) =
         NewEarningsReport::create_new_entry(company_id, &currency, earnings_enum_annual);
-    let is_ttm_entries_existed = earnings_model::insert_earnings_report_batch(ttm_entries, conn)?;
+    let is_ttm_entries_existed =
+        earnings_model::insert_earnings_report_batch(ttm_entries, conn)? > 0;
     let is_annual_entries_existed =
-        earnings_model::insert_earnings_report_batch(annual_entries, conn)?;
-    if is_ttm_entries_existed || is_annual_entries_existed {
-        db::update_growths_batch(conn)?;
-        db::update_ratios_batch(conn)?;
+        earnings_model::insert_earnings_report_batch(annual_entries, conn)? > 0;
+    db::update_ingest_issues(
+        company_id,
+        &rejected_ttm_periods,
+        Some(rejected_annual_periods.as_slice()),
+        conn,
+    )?;
+    db::update_backfill_requests(company_id, true, conn)?;
+    if is_sec_entries_existed || is_ttm_entries_existed || is_annual_entries_existed {
+        db::update_split_adjustments(company_id, conn)?;
+        db::update_derived_fields(company_id, conn)?;
     }
     db::update_earnings_date(company_id, earnings_date, conn)?;
     db::update_estimate(company_id, next_yr_rev, conn)?;
@@ -98,11 +1003,16 @@ pub async fn update_earnings_ttm(
 ) -> Result<(), BullsEyeError> {
     let (earnings_enum_ttm, currency, earnings_date, price, next_yr_rev) =
         bullseye_api::scrape_quarter_update(ticker, exchange).await?;
-    let ttm_entries = NewEarningsReport::create_new_entry(company_id, &currency, earnings_enum_ttm);
-    let is_entries_existed = earnings_model::insert_earnings_report_batch(ttm_entries, conn)?;
-    if is_entries_existed {
-        db::update_growths_batch(conn)?;
-        db::update_ratios_batch(conn)?;
+    let is_sec_entries_existed = insert_sec_statements(company_id, ticker, exchange, conn)?;
+    let (ttm_entries, rejected_ttm_periods) =
+        NewEarningsReport::create_new_entry(company_id, &currency, earnings_enum_ttm);
+    let is_entries_existed = earnings_model::insert_earnings_report_batch(ttm_entries, conn)? > 0;
+    // the annual statements weren't scraped, so their dropped periods are kept
+    db::update_ingest_issues(company_id, &rejected_ttm_periods, None, conn)?;
+    db::update_backfill_requests(company_id, false, conn)?;
+    if is_sec_entries_existed || is_entries_existed {
+        db::update_split_adjustments(company_id, conn)?;
+        db::update_derived_fields(company_id, conn)?;
     }
     db::update_earnings_date(company_id, earnings_date, conn)?;
     db::update_estimate(company_id, next_yr_rev, conn)?;
@@ -125,28 +1035,99 @@ pub async fn update_regular(
     Ok(())
 }
 
+/// tells if an ingest issue of the given company keeps its metrics from being updated
+fn has_blocking_issue(comp_id: i32, conn: &mut PgConnection) -> Result<bool, BullsEyeError> {
+    let reports = EarningsReport::load_all_by_id(comp_id, conn)?;
+    let metric_periods = validation::metric_periods(&reports);
+    let issues = IngestIssue::load_by_id(comp_id, conn)?;
+    Ok(issues
+        .iter()
+        .any(|issue| validation::is_blocking(issue, &metric_periods)))
+}
+
+/// lists the ingest issues of the given listing
+pub fn get_ingest_issues(
+    ticker: &str,
+    exchange: &Exchange,
+    conn: &mut PgConnection,
+) -> Result<Vec<IngestIssue>, BullsEyeError> {
+    let company = Company::load_by_ticker_if_existed(ticker, get_exchange_string(exchange), conn)?
+        .ok_or_else(|| BullsEyeError::CompanyNotFoundError(ticker.to_string()))?;
+    Ok(IngestIssue::load_by_id(company.id, conn)?)
+}
+
+/// lists the stored values of the given listing that disagree with the values derived from
+/// its other stored data
+pub fn get_data_quality_issues(
+    ticker: &str,
+    exchange: &Exchange,
+    conn: &mut PgConnection,
+) -> Result<Vec<DataQualityIssue>, BullsEyeError> {
+    let company = Company::load_by_ticker_if_existed(ticker, get_exchange_string(exchange), conn)?
+        .ok_or_else(|| BullsEyeError::CompanyNotFoundError(ticker.to_string()))?;
+    Ok(DataQualityIssue::load_by_id(company.id, conn)?)
+}
+
+/// accepts an ingest issue after review so it no longer blocks the metrics,
+/// and recalculates the metrics of the company
+pub fn accept_ingest_issue(
+    ticker: &str,
+    exchange: &Exchange,
+    period: &str,
+    check_name: &str,
+    conn: &mut PgConnection,
+) -> Result<CurrentMetrics, BullsEyeError> {
+    let company = Company::load_by_ticker_if_existed(ticker, get_exchange_string(exchange), conn)?
+        .ok_or_else(|| BullsEyeError::CompanyNotFoundError(ticker.to_string()))?;
+    if IngestIssue::accept(company.id, period, check_name, conn)? == 0 {
+        return Err(BullsEyeError::IngestIssueNotFoundError(format!(
+            "{} in {}",
+            check_name, period
+        )));
+    }
+    update_metrics_annual(company.id, conn)
+}
+
 /// updates all metrics after earnings
+/// metrics are kept as they are while a blocking ingest issue is open.
+/// peer ranks are left to the batch jobs, see update_all_peer_ranks.
 pub fn update_metrics_ttm(
     comp_id: i32,
     conn: &mut PgConnection,
 ) -> Result<CurrentMetrics, BullsEyeError> {
+    if has_blocking_issue(comp_id, conn)? {
+        return Ok(CurrentMetrics::load_by_id(comp_id, conn)?);
+    }
     db::copy_latest_data(comp_id, conn)?;
+    db::update_health_scores(comp_id, conn)?;
+    db::update_red_flags(comp_id, conn)?;
+    db::update_valuation_multiples(comp_id, conn)?;
     let latest_metrics = db::update_short_term_trends(comp_id, conn)?;
     db::update_price_target(comp_id, conn)?;
     db::update_guidance(comp_id, conn)?;
+    db::update_calculation_version(comp_id, conn)?;
     Ok(latest_metrics)
 }
 
 /// updates all metrics after earnings. This only runs after Q4 Earnings.
+/// metrics are kept as they are while a blocking ingest issue is open.
+/// peer ranks are left to the batch jobs, see update_all_peer_ranks.
 pub fn update_metrics_annual(
     comp_id: i32,
     conn: &mut PgConnection,
 ) -> Result<CurrentMetrics, BullsEyeError> {
+    if has_blocking_issue(comp_id, conn)? {
+        return Ok(CurrentMetrics::load_by_id(comp_id, conn)?);
+    }
     db::copy_latest_data(comp_id, conn)?;
+    db::update_health_scores(comp_id, conn)?;
+    db::update_red_flags(comp_id, conn)?;
+    db::update_valuation_multiples(comp_id, conn)?;
     db::update_short_term_trends(comp_id, conn)?;
     db::update_multi_yr_growth(comp_id, conn)?;
     let latest_metrics = db::update_long_term_trends(comp_id, conn)?;
     db::update_price_target(comp_id, conn)?;
     db::update_guidance(comp_id, conn)?;
+    db::update_calculation_version(comp_id, conn)?;
     Ok(latest_metrics)
 }
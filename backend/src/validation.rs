@@ -0,0 +1,294 @@
+use crate::gaps;
+use crate::metrics;
+use crate::models::earnings_model::EarningsReport;
+use crate::models::ingest_issues_model::{IngestIssue, IssueSeverity};
+
+/// relative gap between total assets and liabilities plus equity that is still accepted
+const BALANCE_WARNING_PCT: f64 = 2.;
+/// relative gap between total assets and liabilities plus equity that blocks the metrics
+const BALANCE_CRITICAL_PCT: f64 = 20.;
+/// relative gap between gross profit and revenue minus cost of revenue that is still accepted
+const GROSS_PROFIT_WARNING_PCT: f64 = 2.;
+
+/// checks of TTM and annual statements dropped by the latest ingest of each duration
+const UNPARSEABLE_TTM_PERIOD_CHECK: &str = "unparseable_ttm_period";
+pub const UNPARSEABLE_ANNUAL_PERIOD_CHECK: &str = "unparseable_annual_period";
+
+/// failed check of a single period
+pub struct IngestFinding {
+    pub period: String,
+    pub check_name: &'static str,
+    pub severity: IssueSeverity,
+    pub message: String,
+}
+
+impl IngestFinding {
+    fn new(
+        period: &str,
+        check_name: &'static str,
+        severity: IssueSeverity,
+        message: String,
+    ) -> Self {
+        IngestFinding {
+            period: period.to_string(),
+            check_name,
+            severity,
+            message,
+        }
+    }
+}
+
+/// label of the period as shown in the statements ("Qn YYYY" or "FY YYYY")
+pub fn period_label(stock_data: &EarningsReport) -> String {
+    match stock_data.quarter_str {
+        0 => format!("FY {}", stock_data.year_str),
+        quarter => format!("Q{} {}", quarter, stock_data.year_str),
+    }
+}
+
+/// relative gap in percent between the value and the expected value
+fn gap_pct(value: f64, expected: f64) -> f64 {
+    let base = value.abs().max(expected.abs());
+    if base == 0. {
+        return 0.;
+    }
+    (value - expected).abs() / base * 100.
+}
+
+/// checks fiscal period, accounting identities and sign conventions of a single statement
+fn check_statement(stock_data: &EarningsReport) -> Vec<IngestFinding> {
+    use IssueSeverity::{Critical, Warning};
+    let period = period_label(stock_data);
+    let mut findings = Vec::new();
+    let is_valid_quarter = match stock_data.duration.as_str() {
+        "Y" => stock_data.quarter_str == 0,
+        _ => (1..=4).contains(&stock_data.quarter_str),
+    };
+    if !is_valid_quarter || stock_data.year_str <= 0 {
+        findings.push(IngestFinding::new(
+            &period,
+            "invalid_fiscal_period",
+            Critical,
+            format!(
+                "fiscal period could not be read (duration {}, quarter {}, year {})",
+                stock_data.duration, stock_data.quarter_str, stock_data.year_str
+            ),
+        ));
+    }
+    if stock_data.total_assets <= 0. {
+        findings.push(IngestFinding::new(
+            &period,
+            "non_positive_total_assets",
+            Critical,
+            format!(
+                "total assets of {} are not positive",
+                stock_data.total_assets
+            ),
+        ));
+    } else {
+        let liabilities_and_equity = stock_data.total_liabilities + stock_data.shareholders_equity;
+        let gap = gap_pct(stock_data.total_assets, liabilities_and_equity);
+        let severity = match gap {
+            gap if gap > BALANCE_CRITICAL_PCT => Some(Critical),
+            gap if gap > BALANCE_WARNING_PCT => Some(Warning),
+            _ => None,
+        };
+        if let Some(severity) = severity {
+            findings.push(IngestFinding::new(
+                &period,
+                "balance_sheet_identity",
+                severity,
+                format!(
+                    "total assets of {} differ by {:.1}% from liabilities plus equity of {}",
+                    stock_data.total_assets, gap, liabilities_and_equity
+                ),
+            ));
+        }
+    }
+    let share_counts = [
+        ("basic", stock_data.shares_outstanding_basic),
+        ("diluted", stock_data.shares_outstanding_diluted),
+    ];
+    for (share_type, share_count) in share_counts {
+        if share_count < 0. {
+            findings.push(IngestFinding::new(
+                &period,
+                "negative_share_count",
+                Critical,
+                format!("{} share count of {} is negative", share_type, share_count),
+            ));
+        }
+    }
+    let non_negative_fields = [
+        ("revenue", Some(stock_data.revenue)),
+        (
+            "cash and equivalents",
+            Some(stock_data.cash_and_equivalents),
+        ),
+        ("total debt", stock_data.total_debt),
+    ];
+    for (field, value) in non_negative_fields {
+        if let Some(value) = value.filter(|val| *val < 0.) {
+            findings.push(IngestFinding::new(
+                &period,
+                "negative_value",
+                Critical,
+                format!("{} of {} is negative", field, value),
+            ));
+        }
+    }
+    // capital expenditure is stored as an outflow
+    if let Some(capex) = stock_data.capital_expenditure.filter(|val| *val > 0.) {
+        findings.push(IngestFinding::new(
+            &period,
+            "capex_sign",
+            Warning,
+            format!("capital expenditure of {} is positive", capex),
+        ));
+    }
+    if let Some(gross_profit) = stock_data.gross_profit {
+        if gross_profit > stock_data.revenue {
+            findings.push(IngestFinding::new(
+                &period,
+                "gross_profit_exceeds_revenue",
+                Warning,
+                format!(
+                    "gross profit of {} exceeds revenue of {}",
+                    gross_profit, stock_data.revenue
+                ),
+            ));
+        } else if let Some(cost_of_revenue) = stock_data.cost_of_revenue {
+            let expected = stock_data.revenue - cost_of_revenue;
+            let gap = gap_pct(gross_profit, expected);
+            if gap > GROSS_PROFIT_WARNING_PCT {
+                findings.push(IngestFinding::new(
+                    &period,
+                    "gross_profit_identity",
+                    Warning,
+                    format!(
+                        "gross profit of {} differs by {:.1}% from revenue minus cost of revenue of {}",
+                        gross_profit, gap, expected
+                    ),
+                ));
+            }
+        }
+    }
+    findings
+}
+
+/// index of the period used to find gaps (quarters for TTM data, years for annual data)
+pub fn period_index(stock_data: &EarningsReport) -> i32 {
+    match stock_data.duration.as_str() {
+        "Y" => stock_data.year_str as i32,
+        _ => stock_data.year_str as i32 * 4 + stock_data.quarter_str as i32 - 1,
+    }
+}
+
+/// checks that periods of the same duration follow each other without gaps
+/// and that period ending dates move forward with the fiscal periods
+fn check_continuity(reports: &[&EarningsReport]) -> Vec<IngestFinding> {
+    let mut findings = Vec::new();
+    for pair in reports.windows(2) {
+        let (prev, curr) = (pair[0], pair[1]);
+        let period = period_label(curr);
+        let missing_count = period_index(curr) - period_index(prev) - 1;
+        if missing_count > 0 {
+            let (check_name, unit) = match curr.duration.as_str() {
+                "Y" => ("missing_fiscal_year", "fiscal year(s)"),
+                _ => ("missing_quarter", "quarter(s)"),
+            };
+            findings.push(IngestFinding::new(
+                &period,
+                check_name,
+                IssueSeverity::Warning,
+                format!(
+                    "{} {} missing after {}",
+                    missing_count,
+                    unit,
+                    period_label(prev)
+                ),
+            ));
+        }
+        if curr.period_ending <= prev.period_ending {
+            findings.push(IngestFinding::new(
+                &period,
+                "period_order",
+                IssueSeverity::Critical,
+                format!(
+                    "period ending {} is not after {} of {}",
+                    curr.period_ending,
+                    prev.period_ending,
+                    period_label(prev)
+                ),
+            ));
+        }
+    }
+    findings
+}
+
+/// runs every check on the stored statements of a company
+pub fn validate_statements(reports: &[EarningsReport]) -> Vec<IngestFinding> {
+    let mut findings: Vec<IngestFinding> = reports.iter().flat_map(check_statement).collect();
+    for curr_duration in ["T", "Y"] {
+        let mut series: Vec<&EarningsReport> = reports
+            .iter()
+            .filter(|report| report.duration == curr_duration)
+            .collect();
+        series.sort_by_key(|report| period_index(report));
+        findings.extend(check_continuity(&series));
+    }
+    findings
+}
+
+/// statement that was dropped before storing because its period could not be parsed
+pub fn rejected_statement(period: &str, is_annual: bool) -> IngestFinding {
+    let (check_name, statement) = if is_annual {
+        (UNPARSEABLE_ANNUAL_PERIOD_CHECK, "annual statement")
+    } else {
+        (UNPARSEABLE_TTM_PERIOD_CHECK, "TTM statement")
+    };
+    IngestFinding::new(
+        period,
+        check_name,
+        IssueSeverity::Critical,
+        format!(
+            "{} of {} was dropped: period could not be parsed",
+            statement, period
+        ),
+    )
+}
+
+/// periods the current metrics are calculated from:
+/// the TTM quarters of the short term trends and the fiscal years of the long term trends
+pub fn metric_periods(reports: &[EarningsReport]) -> Vec<String> {
+    let annual_years = metrics::LONG_TERM_TREND_YEARS.max(metrics::MULTI_YEAR_GROWTH_YEARS);
+    [
+        ("T", gaps::SHORT_TERM_TREND_QUARTERS as usize),
+        ("Y", annual_years as usize),
+    ]
+    .into_iter()
+    .flat_map(|(curr_duration, count)| {
+        let mut series: Vec<&EarningsReport> = reports
+            .iter()
+            .filter(|report| report.duration == curr_duration)
+            .collect();
+        series.sort_by_key(|report| std::cmp::Reverse(period_index(report)));
+        series
+            .into_iter()
+            .take(count)
+            .map(period_label)
+            .collect::<Vec<String>>()
+    })
+    .collect()
+}
+
+/// tells if the issue keeps the metrics from being updated.
+/// critical issues block until accepted when they concern the periods the metrics are
+/// calculated from or statements dropped by the latest ingest.
+pub fn is_blocking(issue: &IngestIssue, metric_periods: &[String]) -> bool {
+    issue.severity == IssueSeverity::Critical
+        && !issue.accepted
+        && (issue.check_name == UNPARSEABLE_TTM_PERIOD_CHECK
+            || issue.check_name == UNPARSEABLE_ANNUAL_PERIOD_CHECK
+            || metric_periods.contains(&issue.period))
+}
@@ -0,0 +1,239 @@
+use crate::calculate;
+use crate::metrics;
+use crate::models::earnings_model::EarningsReport;
+use crate::models::metrics_model::CurrentMetrics;
+use crate::models::price_history_model::PriceHistory;
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+
+/// lengths in years of the windows the multiple bands are calculated over
+pub const BAND_YEARS: [i64; 3] = [3, 5, 10];
+/// days the stored prices may start after a window and still cover it
+const MAX_BAND_START_GAP_DAYS: i64 = 31;
+
+/// P/E and P/S of one trading day, based on the TTM earnings public on that day
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiplePoint {
+    pub price_date: NaiveDate,
+    pub pe_ratio: Option<f64>,
+    pub ps_ratio: Option<f64>,
+}
+
+/// range of a multiple within the latest years of its history
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultipleBand {
+    pub years: i64,
+    pub observations: usize,
+    pub min: Option<f64>,
+    pub median: Option<f64>,
+    pub max: Option<f64>,
+}
+
+/// figures the current valuation multiples are calculated from.
+/// amounts are in the reporting currency except for the price.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValuationInputs {
+    pub price: Option<f64>,
+    pub shares_outstanding_diluted: Option<f64>,
+    /// rate converting the reporting currency into the trading currency
+    pub fx_rate: Option<f64>,
+    pub market_cap: Option<f64>,
+    pub net_cash: Option<f64>,
+    pub enterprise_value: Option<f64>,
+    pub net_income: Option<f64>,
+    pub revenue: Option<f64>,
+    pub free_cash_flow: Option<f64>,
+    pub operating_income: Option<f64>,
+}
+
+type InputValue = fn(&ValuationInputs) -> Option<f64>;
+
+/// valuation multiples in the forecasts table with the names and values of their numerator
+/// and denominator
+pub const MULTIPLE_FIELDS: [(&str, &str, InputValue, &str, InputValue); 5] = [
+    (
+        "pe_ratio",
+        "market_cap",
+        |v| v.market_cap,
+        "net_income",
+        |v| v.net_income,
+    ),
+    (
+        "ps_ratio",
+        "market_cap",
+        |v| v.market_cap,
+        "revenue",
+        |v| v.revenue,
+    ),
+    (
+        "pfcf_ratio",
+        "market_cap",
+        |v| v.market_cap,
+        "free_cash_flow",
+        |v| v.free_cash_flow,
+    ),
+    (
+        "ev_ebit",
+        "enterprise_value",
+        |v| v.enterprise_value,
+        "operating_income",
+        |v| v.operating_income,
+    ),
+    (
+        "ev_sales",
+        "enterprise_value",
+        |v| v.enterprise_value,
+        "revenue",
+        |v| v.revenue,
+    ),
+];
+
+impl ValuationInputs {
+    /// fx_rate converts the reporting currency of the metrics into the trading currency of the price
+    pub fn new(
+        price: Option<f64>,
+        fx_rate: Option<f64>,
+        target_metrics: &CurrentMetrics,
+        operating_income: Option<f64>,
+    ) -> Self {
+        let shares_outstanding_diluted = target_metrics.shares_outstanding_diluted_ttm;
+        // market cap is brought into the reporting currency so that it can be compared with financials
+        let market_cap = price
+            .zip(shares_outstanding_diluted)
+            .zip(fx_rate)
+            .map(|((price, shares), rate)| price * shares / rate);
+        let net_cash = target_metrics.net_cash_ttm;
+        let enterprise_value = market_cap.zip(net_cash).map(|(cap, cash)| cap - cash);
+        ValuationInputs {
+            price,
+            shares_outstanding_diluted,
+            fx_rate,
+            market_cap,
+            net_cash,
+            enterprise_value,
+            net_income: calculate::calculate_margin_portion(
+                target_metrics.revenue_ttm,
+                target_metrics.net_margin_ttm,
+            ),
+            revenue: target_metrics.revenue_ttm,
+            free_cash_flow: target_metrics.free_cash_flow_ttm,
+            operating_income,
+        }
+    }
+
+    /// value of the given multiple in MULTIPLE_FIELDS
+    pub fn multiple(&self, name: &str) -> Option<f64> {
+        let (_, _, numerator, _, denominator) = MULTIPLE_FIELDS
+            .iter()
+            .find(|(metric, _, _, _, _)| *metric == name)?;
+        calculate::calculate_multiple(numerator(self), denominator(self))
+    }
+}
+
+/// daily P/E and P/S from the stored prices and the latest TTM earnings public on each day.
+/// fx_rate converts the reporting currency into the trading currency of the prices.
+pub fn multiple_history(
+    reports: &[EarningsReport],
+    prices: &[PriceHistory],
+    fx_rate: f64,
+) -> Vec<MultiplePoint> {
+    let mut ttm_reports: Vec<&EarningsReport> = reports
+        .iter()
+        .filter(|report| report.duration == "T")
+        .collect();
+    ttm_reports.sort_by_key(|report| report.period_ending);
+    let public_date =
+        |report: &EarningsReport| report.period_ending + Duration::days(metrics::REPORT_LAG_DAYS);
+    let mut public_count = 0;
+    prices
+        .iter()
+        .filter_map(|price| {
+            while ttm_reports
+                .get(public_count)
+                .copied()
+                .is_some_and(|report| public_date(report) <= price.price_date)
+            {
+                public_count += 1;
+            }
+            let stock_data = ttm_reports[..public_count].last()?;
+            // market cap is brought into the reporting currency like in the current multiples
+            let market_cap = Some(price.close * stock_data.shares_outstanding_diluted / fx_rate);
+            let net_income = calculate::calculate_margin_portion(
+                Some(stock_data.revenue),
+                Some(stock_data.net_margin),
+            );
+            Some(MultiplePoint {
+                price_date: price.price_date,
+                pe_ratio: calculate::calculate_multiple(market_cap, net_income),
+                ps_ratio: calculate::calculate_multiple(market_cap, Some(stock_data.revenue)),
+            })
+        })
+        .collect()
+}
+
+/// values of the multiple within the given number of years before the latest point.
+/// returns None when the history doesn't reach back to the start of the window.
+fn window_values(
+    points: &[MultiplePoint],
+    value: fn(&MultiplePoint) -> Option<f64>,
+    years: i64,
+) -> Option<Vec<f64>> {
+    let (first, latest) = (points.first()?, points.last()?);
+    let window_start = latest.price_date - Duration::days(365 * years);
+    if first.price_date - window_start > Duration::days(MAX_BAND_START_GAP_DAYS) {
+        return None;
+    }
+    Some(
+        points
+            .iter()
+            .filter(|point| point.price_date > window_start)
+            .filter_map(value)
+            .collect(),
+    )
+}
+
+/// min, median and max of the multiple for each window of BAND_YEARS the history covers
+pub fn multiple_bands(
+    points: &[MultiplePoint],
+    value: fn(&MultiplePoint) -> Option<f64>,
+) -> Vec<MultipleBand> {
+    BAND_YEARS
+        .into_iter()
+        .filter_map(|years| {
+            let values = window_values(points, value, years)?;
+            Some(MultipleBand {
+                years,
+                observations: values.len(),
+                min: calculate::calculate_quantile(&values, 0.),
+                median: calculate::calculate_quantile(&values, 0.5),
+                max: calculate::calculate_quantile(&values, 1.),
+            })
+        })
+        .collect()
+}
+
+/// length in years and values of the longest window of BAND_YEARS the history covers.
+/// returns None when the history is shorter than the shortest window.
+pub fn percentile_window(
+    points: &[MultiplePoint],
+    value: fn(&MultiplePoint) -> Option<f64>,
+) -> Option<(i64, Vec<f64>)> {
+    BAND_YEARS
+        .into_iter()
+        .rev()
+        .find_map(|years| window_values(points, value, years).map(|values| (years, values)))
+}
+
+/// percentile rank (0-100) of the current multiple within the longest window the history covers.
+/// returns None when the history is shorter than the shortest window.
+pub fn historical_percentile(
+    points: &[MultiplePoint],
+    value: fn(&MultiplePoint) -> Option<f64>,
+    current: Option<f64>,
+) -> Option<f64> {
+    let (_, population) = percentile_window(points, value)?;
+    calculate::calculate_percentile_rank(current, &population)
+}
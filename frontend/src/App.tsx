@@ -12,11 +12,11 @@ function App() {
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | undefined>(undefined);
   const [metrics, updateMetrics] = useState<Metrics | undefined>(undefined);
-  async function loadMetrics(ticker: string) {
+  async function loadMetrics(ticker: string, exchange?: string) {
     setLoading(true);
     setTicker(ticker);
     try {
-      const metrics = await getMetrics(ticker);
+      const metrics = await getMetrics(ticker, exchange);
       updateMetrics(metrics);
       setError(undefined);
     } catch (e) {
@@ -51,7 +51,9 @@ function App() {
         >
           Search
         </NavLink>
-        <SearchBar onSearch={(ticker) => loadMetrics(ticker)} />
+        <SearchBar
+          onSearch={(ticker, exchange) => loadMetrics(ticker, exchange)}
+        />
       </header>
       <div className="bg-gray-200 min-h-screen w-full">
         <Routes>
@@ -3,6 +3,7 @@ export interface Metrics {
   industry: string;
   exchange: string;
   ticker: string;
+  delisted: boolean;
   currency: string;
   netInterestIncomeGrowthYoyTtm: number | undefined;
   netInterestIncomeGrowthMultiYear: number | undefined;
@@ -49,6 +50,17 @@ export interface Metrics {
   freeCashFlowMarginTtm: number | undefined;
   ffoMarginTtm: number | undefined;
   ffoMarginTrend: string | undefined;
+  piotroskiFScore: number | undefined;
+  altmanZScore: number | undefined;
+  premiumsGrowthYoyTtm: number | undefined;
+  premiumsGrowthMultiYear: number | undefined;
+  lossRatioTtm: number | undefined;
+  lossRatioShortTermTrend: string | undefined;
+  lossRatioLongTermTrend: string | undefined;
+  combinedRatioTtm: number | undefined;
+  combinedRatioShortTermTrend: string | undefined;
+  combinedRatioLongTermTrend: string | undefined;
+  redFlags: string[];
   nextEarningsDate: Date | undefined;
   latestPrice: number | undefined;
   lastUpdated: Date | undefined;
@@ -59,4 +71,29 @@ export interface Metrics {
   priceNextYearRevenueGrowth: number | undefined;
   priceMultiYearRevenueGrowth: number | undefined;
   priceMultiYearGpGrowth: number | undefined;
+  peRatio: number | undefined;
+  psRatio: number | undefined;
+  pfcfRatio: number | undefined;
+  evEbit: number | undefined;
+  evSales: number | undefined;
+  tradingCurrency: string | undefined;
+  priceTargetAvailable: boolean | undefined;
+  converted: ConvertedAmounts | undefined;
+}
+
+export interface ConvertedAmounts {
+  currency: string;
+  rate: number | undefined;
+  revenueTtm: number | undefined;
+  retainedEarningsTtm: number | undefined;
+  netCashTtm: number | undefined;
+  operatingCashFlowTtm: number | undefined;
+  freeCashFlowTtm: number | undefined;
+  revenueNextYear: number | undefined;
+  latestPrice: number | undefined;
+  priceCurrentRevenueGrowth: number | undefined;
+  priceCurrentGpGrowth: number | undefined;
+  priceNextYearRevenueGrowth: number | undefined;
+  priceMultiYearRevenueGrowth: number | undefined;
+  priceMultiYearGpGrowth: number | undefined;
 }
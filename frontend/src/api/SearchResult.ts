@@ -0,0 +1,7 @@
+export interface SearchResult {
+  ticker: string;
+  companyName: string;
+  isin: string;
+  exchange: string;
+  ingested: boolean;
+}
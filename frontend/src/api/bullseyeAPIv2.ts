@@ -1,10 +1,16 @@
 import { Metrics } from "../api/Metrics";
+import { SearchResult } from "../api/SearchResult";
 
 // const baseUrl = "http://192.168.1.12:3000"; // dev
 const baseUrl = "/api";
 
-export async function getMetrics(ticker: string): Promise<Metrics> {
-  const url = `${baseUrl}/companies/${ticker.toLowerCase()}`;
+export async function getMetrics(
+  ticker: string,
+  exchange?: string
+): Promise<Metrics> {
+  const url = exchange
+    ? `${baseUrl}/exchanges/${exchange}/companies/${ticker.toLowerCase()}`
+    : `${baseUrl}/companies/${ticker.toLowerCase()}`;
   try {
     const metrics = await fetchData<Metrics>(url);
     // For Debug
@@ -43,6 +49,16 @@ export async function getAllMetrics(): Promise<Metrics[]> {
   }
 }
 
+export async function searchSymbols(query: string): Promise<SearchResult[]> {
+  const url = `${baseUrl}/search?q=${encodeURIComponent(query)}`;
+  try {
+    return await fetchData<SearchResult[]>(url);
+  } catch {
+    // suggestions are optional, so failures are ignored
+    return [];
+  }
+}
+
 async function fetchData<T>(url: string, options?: RequestInit): Promise<T> {
   const response = await fetch(url, options);
   if (!response.ok) {
@@ -26,7 +26,7 @@ export interface StockData {
   eps_diluted: number;
   shares_outstanding_basic: number;
   shares_outstanding_diluted: number;
-  shares_change_yoy: number;
+  shares_change_yoy?: number;
   cash_and_equivalents: number;
   cash_and_short_term_investments: number;
   accounts_receivable: number;
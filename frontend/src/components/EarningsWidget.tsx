@@ -103,6 +103,44 @@ function EarningsWidget({ metrics }: { metrics: Metrics }) {
         </div>
       )}
 
+      {metrics?.lossRatioTtm != null && (
+        <div className="flex flex-col col-span-full  bg-white rounded-xl sm:col-span-3 xl:col-span-2">
+          <MetricsCardWithTwoIndicators
+            title="Loss Ratio (TTM)"
+            value={`${metrics?.lossRatioTtm?.toFixed(2) ?? "-"}%`}
+            firstIndicatorTitle="Short-term"
+            firstIndicator={`${metrics?.lossRatioShortTermTrend ?? "-"}`}
+            firstClassName={colorcodeTrendRev(
+              metrics?.lossRatioShortTermTrend
+            )}
+            secondIndicatorTitle="Long-term"
+            secondIndicator={` ${metrics?.lossRatioLongTermTrend ?? "-"}`}
+            secondClassName={colorcodeTrendRev(
+              metrics?.lossRatioLongTermTrend
+            )}
+          />
+        </div>
+      )}
+
+      {metrics?.combinedRatioTtm != null && (
+        <div className="flex flex-col col-span-full  bg-white rounded-xl sm:col-span-3 xl:col-span-2">
+          <MetricsCardWithTwoIndicators
+            title="Combined Ratio (TTM)"
+            value={`${metrics?.combinedRatioTtm?.toFixed(2) ?? "-"}%`}
+            firstIndicatorTitle="Short-term"
+            firstIndicator={`${metrics?.combinedRatioShortTermTrend ?? "-"}`}
+            firstClassName={colorcodeTrendRev(
+              metrics?.combinedRatioShortTermTrend
+            )}
+            secondIndicatorTitle="Long-term"
+            secondIndicator={` ${metrics?.combinedRatioLongTermTrend ?? "-"}`}
+            secondClassName={colorcodeTrendRev(
+              metrics?.combinedRatioLongTermTrend
+            )}
+          />
+        </div>
+      )}
+
       {metrics?.grossMarginTtm !== 100 &&
         metrics?.grossMarginTtm !== 0 &&
         metrics?.grossMarginTtm && (
@@ -15,6 +15,14 @@ function ScreenerPage() {
   }, []);
   return (
     <div className="flex flex-col w-full">
+      <div className="flex justify-end gap-3 px-4 pt-3 text-sm">
+        <a href="/api/screener?format=csv" download>
+          Download CSV
+        </a>
+        <a href="/api/screener?format=xlsx" download>
+          Download XLSX
+        </a>
+      </div>
       {allMetrics && <CompanyList metrics={allMetrics} />}
     </div>
   );
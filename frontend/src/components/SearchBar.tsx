@@ -1,25 +1,60 @@
-import { FormEvent, useState } from "react";
+import { FormEvent, useEffect, useState } from "react";
 import { useNavigate } from "react-router-dom";
+import { searchSymbols } from "../api/bullseyeAPIv2";
+import { SearchResult } from "../api/SearchResult";
 
 interface SearchBarProps {
-  onSearch: (ticker: string) => void;
+  onSearch: (ticker: string, exchange?: string) => void;
 }
 
 function SearchBar({ onSearch }: SearchBarProps) {
   const [searchTerm, updateSearchTerm] = useState<string>("");
+  const [suggestions, updateSuggestions] = useState<SearchResult[]>([]);
   const navigate = useNavigate();
+
+  useEffect(() => {
+    const query = searchTerm.trim();
+    if (!query) {
+      updateSuggestions([]);
+      return;
+    }
+    let cancelled = false;
+    const timer = setTimeout(async () => {
+      const results = await searchSymbols(query);
+      if (!cancelled) {
+        updateSuggestions(results);
+      }
+    }, 250);
+    return () => {
+      cancelled = true;
+      clearTimeout(timer);
+    };
+  }, [searchTerm]);
+
   const handleInput = (event: React.ChangeEvent<HTMLInputElement>) => {
     let { value } = event.target;
     updateSearchTerm(value);
   };
+  const selectSuggestion = (suggestion: SearchResult) => {
+    updateSearchTerm(suggestion.ticker);
+    updateSuggestions([]);
+    onSearch(suggestion.ticker, suggestion.exchange);
+    navigate("/search");
+  };
   const handleSubmit = (event: FormEvent) => {
     event.preventDefault();
+    // prefer the top suggestion so that typos do not trigger a scrape
+    if (suggestions.length > 0) {
+      selectSuggestion(suggestions[0]);
+      return;
+    }
+    updateSuggestions([]);
     onSearch(searchTerm);
     navigate("/search");
   };
 
   return (
-    <div className="bg-white rounded-xl border border-black">
+    <div className="relative bg-white rounded-xl border border-black">
       <form onSubmit={handleSubmit}>
         <input
           type="text"
@@ -27,9 +62,27 @@ function SearchBar({ onSearch }: SearchBarProps) {
           value={searchTerm}
           placeholder="Search Ticker"
           onChange={handleInput}
+          autoComplete="off"
           className="ps-3"
         />
       </form>
+      {suggestions.length > 0 && (
+        <ul className="absolute z-10 mt-1 w-72 bg-white rounded-xl border border-black shadow-lg">
+          {suggestions.map((suggestion) => (
+            <li
+              key={`${suggestion.exchange}:${suggestion.ticker}`}
+              onClick={() => selectSuggestion(suggestion)}
+              className="px-3 py-1 cursor-pointer hover:bg-green-100"
+            >
+              <span className="font-semibold">{suggestion.ticker}</span>
+              <span className="text-xs text-gray-500"> {suggestion.exchange}</span>
+              <div className="text-sm text-gray-700 truncate">
+                {suggestion.companyName}
+              </div>
+            </li>
+          ))}
+        </ul>
+      )}
     </div>
   );
 }
@@ -116,6 +116,12 @@ function TickerMetricsPage(props: MetricsPageProps) {
                 </h1>
               </div>
               <h2 className="font-bold">Price target list</h2>
+              {metrics?.priceTargetAvailable === false && (
+                <p className="px-2 text-sm text-red-600">
+                  Price targets are unavailable: no exchange rate from{" "}
+                  {metrics?.currency} to {metrics?.tradingCurrency}.
+                </p>
+              )}
               <div className="p-2 space-y-2.5 flex flex-col">
                 <p>
                   Using current revenue growth (